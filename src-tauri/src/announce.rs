@@ -0,0 +1,278 @@
+//! Accessibility announcements: short sentences saying what just changed on
+//! the wall ("Camera 3, Stage Left, went offline"), for screen readers and
+//! text-to-speech monitoring stations. They are worded from the events the
+//! wall already emits, and each is tagged with the least verbosity that
+//! includes it. `/api/announcements` streams them as Server-Sent Events (a
+//! client can ask for its own verbosity); the wall and the control panel read
+//! the ones at the configured verbosity out through an ARIA live region.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tokio::sync::{broadcast, mpsc};
+use tracing::debug;
+
+/// Announcements kept for SSE clients that reconnect with `Last-Event-ID`.
+const RECENT: usize = 50;
+
+/// App events announcements are worded from.
+const SOURCE_EVENTS: &[&str] = &[
+    "camera-status", "view-changed", "page-changed", "pip-changed", "audio-monitor-changed", "detection",
+    "highlight", "screensaver", "standby-screen", "blackout", "maintenance",
+];
+
+/// Failed connection attempts after which a reconnecting camera counts as
+/// offline; the wall's error toast waits as long.
+const OUTAGE_ATTEMPTS: u32 = 3;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    Off,
+    Minimal, // Cameras going offline and coming back, blackout, standby, maintenance
+    #[default]
+    Normal, // Also solo/grid, pages, PiP, audio monitoring, detections
+    Verbose, // Also cameras connecting and reconnecting, rule highlights, the screensaver
+}
+
+impl Verbosity {
+    pub fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(value.to_ascii_lowercase())).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AnnounceConfig {
+    #[serde(default)]
+    pub verbosity: Verbosity, // The wall, the control panel, and SSE clients that don't ask for their own
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Announcement {
+    pub id: u64, // Increasing; the SSE event id
+    pub time: String, // ISO 8601 UTC
+    pub verbosity: Verbosity, // Least verbosity that includes it
+    pub event: &'static str, // App event it was worded from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_id: Option<String>,
+    pub text: String,
+}
+
+pub struct AnnounceState {
+    sender: broadcast::Sender<Announcement>,
+    recent: Mutex<VecDeque<Announcement>>, // Oldest first
+    last_id: AtomicU64,
+}
+
+impl AnnounceState {
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(RECENT).0,
+            recent: Mutex::new(VecDeque::new()),
+            last_id: AtomicU64::new(0),
+        }
+    }
+}
+
+/// The attempt in a status like "reconnecting (attempt 2)". Long waits
+/// ("reconnecting (5m wait)") carry none.
+fn reconnect_attempt(status: &str) -> Option<u32> {
+    status.split_once("attempt ")?.1.trim_end_matches(')').trim().parse().ok()
+}
+
+/// "Camera 3, Stage Left": the position an operator calls it by, then its name.
+fn camera_label(config: &crate::AppConfig, camera_id: &str) -> String {
+    match config.cameras.iter().position(|c| c.id == camera_id) {
+        Some(i) if config.cameras[i].name.trim().is_empty() => format!("Camera {}", i + 1),
+        Some(i) => format!("Camera {}, {}", i + 1, config.cameras[i].name.trim()),
+        None => "A camera".to_string(),
+    }
+}
+
+/// What has been said so far, so a state is announced once rather than on
+/// every event that repeats it.
+#[derive(Default)]
+struct Wording {
+    statuses: HashMap<String, &'static str>, // camera_id -> "online", "offline", or "connecting"
+    down: HashSet<String>, // Cameras announced as offline and not back yet
+    last: HashMap<&'static str, String>, // Event -> its last announcement
+}
+
+impl Wording {
+    fn word(&mut self, config: &crate::AppConfig, event: &'static str, payload: &Value) -> Option<(Verbosity, Option<String>, String)> {
+        let field = |key: &str| payload.get(key).and_then(Value::as_str).filter(|s| !s.is_empty());
+        let active = payload.get("active").and_then(Value::as_bool).unwrap_or(false);
+        let camera = field("camera_id").map(str::to_string);
+        let label = camera.as_deref().map(|id| camera_label(config, id));
+
+        let (verbosity, text) = match event {
+            "camera-status" => {
+                let id = camera.clone()?;
+                let now = match field("status")? {
+                    "online" => "online",
+                    "offline" | "error" => "offline",
+                    "connecting" => "connecting",
+                    status if status.starts_with("reconnecting") => match reconnect_attempt(status) {
+                        Some(attempt) if attempt < OUTAGE_ATTEMPTS => "connecting",
+                        _ => "offline", // Still failing after a few tries
+                    },
+                    _ => return None, // Idle: not streaming on purpose
+                };
+                let before = self.statuses.insert(id.clone(), now);
+                if before == Some(now) {
+                    return None;
+                }
+                let label = label?;
+                match now {
+                    // Retries go offline and connecting again; say so once per outage
+                    "offline" if self.down.insert(id.clone()) => (Verbosity::Minimal, format!("{} went offline", label)),
+                    "online" if self.down.remove(&id) => (Verbosity::Minimal, format!("{} is back online", label)),
+                    "online" => (Verbosity::Verbose, format!("{} is online", label)),
+                    "connecting" if self.down.contains(&id) => return None,
+                    "connecting" if before == Some("online") => (Verbosity::Normal, format!("{} is reconnecting", label)),
+                    "connecting" => (Verbosity::Verbose, format!("{} is connecting", label)),
+                    _ => return None,
+                }
+            }
+            "view-changed" => match (field("view")?, label) {
+                ("solo", Some(label)) => (Verbosity::Normal, format!("Showing {}", label)),
+                _ => (Verbosity::Normal, "Showing all cameras".to_string()),
+            },
+            "page-changed" => {
+                let page = payload.get("page").and_then(Value::as_u64)?;
+                let count = payload.get("page_count").and_then(Value::as_u64)?;
+                if count <= 1 {
+                    return None;
+                }
+                (Verbosity::Normal, format!("Page {} of {}", page, count))
+            }
+            "pip-changed" => match label.filter(|_| active) {
+                Some(label) => (Verbosity::Normal, format!("Picture-in-picture: {}", label)),
+                None => (Verbosity::Normal, "Picture-in-picture off".to_string()),
+            },
+            "audio-monitor-changed" => match label {
+                Some(label) => (Verbosity::Normal, format!("Audio from {}", label)),
+                None => (Verbosity::Normal, "Audio follows the soloed camera".to_string()),
+            },
+            "detection" => {
+                let label = label?;
+                match field("label") {
+                    Some(what) => {
+                        let mut what = what.replace('_', " ");
+                        if let Some(first) = what.get_mut(..1) {
+                            first.make_ascii_uppercase();
+                        }
+                        (Verbosity::Normal, format!("{} detected on {}", what, label))
+                    }
+                    None => (Verbosity::Normal, format!("Detection on {}", label)),
+                }
+            }
+            "highlight" if active => (Verbosity::Verbose, format!("Rule highlighting {}", label?)),
+            "highlight" => return None,
+            "screensaver" if active => (Verbosity::Verbose, "Screensaver on".to_string()),
+            "screensaver" => (Verbosity::Verbose, "Screensaver off".to_string()),
+            "standby-screen" if active => match field("reason") {
+                Some(reason) => (Verbosity::Minimal, format!("Wall on standby, {}", reason)),
+                None => (Verbosity::Minimal, "Wall on standby".to_string()),
+            },
+            "standby-screen" => (Verbosity::Minimal, "Wall back from standby".to_string()),
+            "blackout" if active => (Verbosity::Minimal, "Wall blacked out".to_string()),
+            "blackout" => (Verbosity::Minimal, "Wall back from blackout".to_string()),
+            "maintenance" if active => match field("message") {
+                Some(message) => (Verbosity::Minimal, format!("Maintenance mode on: {}", message)),
+                None => (Verbosity::Minimal, "Maintenance mode on".to_string()),
+            },
+            "maintenance" => (Verbosity::Minimal, "Maintenance mode off".to_string()),
+            _ => return None,
+        };
+
+        // Detections and highlights repeat on purpose; the rest are states
+        // re-sent on config reloads, montage steps, and drain progress
+        if !matches!(event, "camera-status" | "detection" | "highlight") {
+            if self.last.get(event) == Some(&text) {
+                return None;
+            }
+            self.last.insert(event, text.clone());
+        }
+        Some((verbosity, camera, text))
+    }
+}
+
+fn publish(app: &AppHandle, verbosity: Verbosity, event: &'static str, camera_id: Option<String>, text: String) {
+    let state = app.state::<crate::AppState>();
+    let announcements = &state.announcements;
+    let announcement = Announcement {
+        id: announcements.last_id.fetch_add(1, Ordering::Relaxed) + 1,
+        time: crate::iso8601_utc(crate::unix_millis() / 1000),
+        verbosity,
+        event,
+        camera_id,
+        text,
+    };
+    debug!("Announcement ({:?}): {}", verbosity, announcement.text);
+
+    // Sent under the lock so a new subscriber gets each one exactly once
+    if let Ok(mut recent) = announcements.recent.lock() {
+        recent.push_back(announcement.clone());
+        while recent.len() > RECENT {
+            recent.pop_front();
+        }
+        let _ = announcements.sender.send(announcement.clone());
+    }
+
+    let configured = state.config.lock().map(|c| c.announce.verbosity).unwrap_or_default();
+    if verbosity <= configured {
+        let _ = app.emit("announcement", announcement);
+    }
+}
+
+/// Word the wall's events into announcements for as long as the app runs.
+pub fn start(app: &AppHandle) {
+    let (tx, mut events) = mpsc::unbounded_channel::<(&'static str, String)>();
+    for &name in SOURCE_EVENTS {
+        let tx = tx.clone();
+        // Worded on a task: some of these are emitted with the config locked
+        app.listen_any(name, move |event| {
+            let _ = tx.send((name, event.payload().to_string()));
+        });
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut wording = Wording::default();
+        while let Some((event, payload)) = events.recv().await {
+            let Ok(payload) = serde_json::from_str::<Value>(&payload) else { continue };
+            // Worded even while the wall's verbosity is off: SSE clients pick their own
+            let worded = match app.state::<crate::AppState>().config.lock() {
+                Ok(config) => wording.word(&config, event, &payload),
+                Err(_) => continue,
+            };
+            if let Some((verbosity, camera_id, text)) = worded {
+                publish(&app, verbosity, event, camera_id, text);
+            }
+        }
+    });
+}
+
+/// Announcements after `last_id` that are still kept, and a receiver for the
+/// ones to come.
+pub fn subscribe(app: &AppHandle, last_id: Option<u64>) -> (VecDeque<Announcement>, broadcast::Receiver<Announcement>) {
+    let state = app.state::<crate::AppState>();
+    let announcements = &state.announcements;
+    // Subscribed under the lock, so nothing falls between the two
+    let Ok(recent) = announcements.recent.lock() else {
+        return (VecDeque::new(), announcements.sender.subscribe());
+    };
+    let receiver = announcements.sender.subscribe();
+    let missed = recent.iter().filter(|a| last_id.is_some_and(|last| a.id > last)).cloned().collect();
+    (missed, receiver)
+}
+
+/// One announcement as a Server-Sent Event.
+pub fn sse_event(announcement: &Announcement) -> String {
+    let data = serde_json::to_string(announcement).unwrap_or_default();
+    format!("id: {}\nevent: announcement\ndata: {}\n\n", announcement.id, data)
+}
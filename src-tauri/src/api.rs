@@ -0,0 +1,1317 @@
+//! HTTP API server: control panel, remote viewer, JSON commands for Stream Deck /
+//! Companion, and the per-camera fMP4, HLS, WHEP, and snapshot endpoints.
+//! Connections are accepted here and served by hyper (HTTP/1.1 with keep-alive,
+//! chunked bodies, and WebSocket upgrades); requests are routed by axum to the
+//! handlers below, which call into the same functions the Tauri commands use.
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use axum::body::{Body, Bytes};
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{ConnectInfo, DefaultBodyLimit, Path, RawQuery, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::routing::{any, delete, get, post};
+use axum::Router;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper_util::rt::{TokioIo, TokioTimer};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tower::Service;
+use tracing::{debug, error, info, warn};
+
+use crate::{AppState, PageTarget, TokenBucket};
+
+/// A client has this long to send its request headers before the connection is closed.
+const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whole-connection budget on the failover listener, so a stalled client can't hold it.
+const FAILOVER_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Quiet `/api/announcements` streams get a comment this often.
+const ANNOUNCE_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Every route, listed in the 404 response so clients can discover the API.
+const ENDPOINTS: &[&str] = &[
+    "/", "/viewer", "/api/ping", "/healthz", "/api/solo/:index", "/api/grid", "/api/view", "/api/rules", "/api/rules/on", "/api/rules/off", "/api/screensaver",
+    "/api/screensaver/on", "/api/screensaver/off", "/api/blackout/on", "/api/blackout/off", "/api/restart-app", "/api/status", "/api/cameras", "/api/cameras?q=", "/api/cameras/:id", "/api/cameras/import", "/api/camera/:id/enable", "/api/camera/:id/disable", "/api/config/export", "/api/config/import", "/api/config/backups", "/api/config/backups/:version/restore", "/api/theme", "/api/announcements", "/api/gateway/sync", "/api/follower/sync", "/api/detection", "/api/events", "/api/lint", "/api/bulk",
+    "/camera/:id/snapshot.jpg", "/ws", "/api/layout/tiles", "/api/page/next", "/api/page/prev",
+    "/api/page/:n", "/api/clients", "/api/clients/:id/kick", "/api/pip", "/api/pip/:index/on", "/api/pip/:index/off", "/api/audio/:index",
+    "/api/audio/off", "/api/identify/:index", "/api/record/:id/start", "/api/record/:id/stop", "/api/talkback", "/api/talkback/:id/on", "/api/talkback/:id/off",
+    "/api/ptz/:id/move", "/api/ptz/:id/stop", "/api/ptz/:id/presets", "/api/ptz/:id/preset/:n", "/api/ptz/:id/preset/:n/save",
+    "/api/replay/all/:seconds", "/api/clip/:id", "/api/diagnose/:id", "/api/health/:id", "/api/capture", "/api/capture/:id", "/api/sync", "/api/profiles", "/api/profile/:name/activate", "/api/profile/:name/save", "/api/replication", "/api/spare", "/api/spare/promote", "/api/overlay", "/api/overlay/image", "/api/dashboard",
+    "/api/maintenance", "/api/maintenance/on", "/api/maintenance/off", "/api/fullscreen", "/api/reload",
+];
+
+fn router(app: AppHandle) -> Router {
+    Router::new()
+        // Pages and custom panels
+        .route("/", get(control_panel))
+        .route("/control", get(control_panel))
+        .route("/viewer", get(viewer))
+        .route("/www", get(|| async { Redirect::permanent("/www/") }))
+        .route("/www/", get(|| www(String::new())))
+        .route("/www/{*path}", get(|Path(path): Path<String>| www(path)))
+        // Live event channel: camera status, health, and remote commands over WebSocket
+        .route("/ws", get(crate::ws::handler))
+        // Per-camera media
+        .route("/camera/{id}/stream", get(camera_stream))
+        .route("/camera/{id}/snapshot.jpg", get(snapshot))
+        .route("/camera/{id}/hls/{*resource}", get(hls))
+        .route("/camera/{id}/whep", post(whep_offer))
+        .route("/camera/{id}/whep/{session}", delete(whep_close))
+        // Discovery, health, and the emergency commands shared with the failover API
+        .route("/api/ping", any(ping))
+        .route("/healthz", any(healthz))
+        .route("/api/grid", any(emergency))
+        .route("/api/blackout/on", any(emergency))
+        .route("/api/blackout/off", any(emergency))
+        .route("/api/restart-app", any(emergency))
+        // Wall control
+        .route("/api/solo/{index}", any(solo))
+        .route("/api/view", get(view))
+        .route("/api/rules", get(rules))
+        .route("/api/rules/{action}", any(set_rules))
+        .route("/api/screensaver", get(screensaver))
+        .route("/api/screensaver/{action}", any(set_screensaver))
+        .route("/api/talkback", get(talkback))
+        .route("/api/talkback/{id}/{action}", any(set_talkback))
+        .route("/api/ptz/{id}/{action}", any(ptz))
+        .route("/api/ptz/{id}/preset/{n}", any(ptz_preset))
+        .route("/api/ptz/{id}/preset/{n}/save", any(ptz_save_preset))
+        .route("/api/layout/tiles", get(tile_layout).post(set_tile_layout).put(set_tile_layout))
+        .route("/api/page", any(page))
+        .route("/api/page/{target}", any(go_to_page))
+        .route("/api/pip", any(pip))
+        .route("/api/pip/{index}/{action}", any(switch_pip))
+        .route("/api/audio", any(audio))
+        .route("/api/audio/{target}", any(set_audio))
+        .route("/api/status", any(status))
+        .route("/api/clients", get(clients))
+        .route("/api/clients/{id}/kick", any(kick_client))
+        .route("/api/identify/{target}", any(identify))
+        .route("/api/dashboard", any(dashboard))
+        .route("/api/fullscreen", any(fullscreen))
+        .route("/api/reload", any(reload))
+        .route("/api/cameras", any(camera_list))
+        .route("/api/cameras/import", post(import_cameras).layer(DefaultBodyLimit::max(crate::IMPORT_MAX_FILE)))
+        .route("/api/cameras/{id}", any(cameras))
+        .route("/api/camera/{id}/{action}", any(camera_enabled))
+        .route("/api/config/export", get(export_config))
+        .route("/api/config/import", post(import_config).layer(DefaultBodyLimit::max(crate::IMPORT_MAX_FILE)))
+        .route("/api/config/backups", get(config_backups))
+        .route("/api/config/backups/{version}/restore", post(restore_config))
+        .route("/api/theme", any(theme))
+        .route("/api/announcements", get(announcements))
+        .route("/api/gateway/sync", post(gateway_sync))
+        .route("/api/follower/sync", post(follower_sync))
+        .route("/api/detection", post(detection))
+        .route("/api/events", get(events))
+        .route("/api/lint", get(lint))
+        .route("/api/bulk", post(bulk))
+        .route("/api/overlay", any(overlay).layer(DefaultBodyLimit::max(crate::OVERLAY_MAX_IMAGE)))
+        .route("/api/overlay/image", get(overlay_image))
+        .route("/api/maintenance", any(maintenance_status))
+        .route("/api/maintenance/{action}", any(maintenance))
+        // Recording and replay
+        .route("/api/record/{id}/{action}", any(record))
+        .route("/api/replay/all/{seconds}", any(replay_all))
+        .route("/api/clip/{id}", any(clip))
+        .route("/api/diagnose/{id}", get(diagnose))
+        .route("/api/health/{id}", get(health_csv))
+        .route("/api/capture", get(capture_status))
+        .route("/api/capture/{id}", post(capture))
+        .route("/api/sync", post(sync_check))
+        .route("/api/profiles", get(profiles))
+        .route("/api/profile/{name}/{action}", any(profile))
+        .route("/api/replication", get(replication))
+        .route("/api/spare", get(spare))
+        .route("/api/spare/promote", post(promote_spare))
+        .route("/api/replay/file/{*path}", get(replay_file))
+        .fallback(not_found)
+        .layer(DefaultBodyLimit::max(crate::MAX_REQUEST_BODY))
+        .layer(middleware::from_fn_with_state(app.clone(), gate))
+        .layer(middleware::from_fn(cors))
+        .with_state(app)
+}
+
+/// Lightweight HTTP API server for remote control (Stream Deck / Companion).
+/// Listens on the configured port and forwards commands to the frontend via events.
+/// Each connection is served on its own task so remote ones can be cut off
+/// when maintenance mode drains.
+pub async fn run(app: AppHandle, port: u16) {
+    let addr = format!("0.0.0.0:{}", port);
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(l) => {
+            info!("API server listening on http://0.0.0.0:{}", port);
+            if let Some(ip) = crate::get_local_ipv4() {
+                info!("Control panel: http://{}:{}/ or http://stageview.local:{}/", ip, port, port);
+            }
+            l
+        }
+        Err(e) => {
+            error!("Failed to start API server on {}: {}", addr, e);
+            return;
+        }
+    };
+    let router = router(app.clone());
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        // Connections from other machines that were open when maintenance mode
+        // began are drained: counted, then cut off once the drain window ends
+        let drain = (!peer.ip().is_loopback() && !crate::in_maintenance(&app.state::<AppState>()))
+            .then(|| app.state::<AppState>().maintenance_drain.subscribe());
+
+        let router = router.clone();
+        let connection = tokio::spawn(async move {
+            let service = hyper::service::service_fn(move |mut request: hyper::Request<Incoming>| {
+                request.extensions_mut().insert(ConnectInfo(peer));
+                router.clone().call(request)
+            });
+            let result = http1::Builder::new()
+                .timer(TokioTimer::new())
+                .header_read_timeout(HEADER_READ_TIMEOUT)
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades()
+                .await;
+            if let Err(e) = result {
+                debug!("API connection from {} ended: {}", peer, e);
+            }
+        });
+        if let Some(mut drain) = drain {
+            let app_handle = app.clone();
+            tokio::spawn(async move {
+                let state = app_handle.state::<AppState>();
+                state.remote_connections.fetch_add(1, Ordering::Relaxed);
+                let mut connection = connection;
+                tokio::select! {
+                    _ = &mut connection => {}
+                    _ = drain.wait_for(|drained| *drained) => connection.abort(),
+                }
+                state.remote_connections.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+    }
+}
+
+// ── Failover API ─────────────────────────────────────────────────────────────
+
+/// Every route on the failover listener.
+const FAILOVER_ENDPOINTS: &[&str] = &["/api/ping", "/healthz", "/api/grid", "/api/blackout/on", "/api/blackout/off", "/api/restart-app"];
+
+fn failover_router(app: AppHandle) -> Router {
+    Router::new()
+        .route("/api/ping", any(failover_ping))
+        .route("/healthz", any(healthz))
+        .route("/api/grid", any(emergency))
+        .route("/api/blackout/on", any(emergency))
+        .route("/api/blackout/off", any(emergency))
+        .route("/api/restart-app", any(emergency))
+        .fallback(|| async {
+            json(StatusCode::NOT_FOUND, serde_json::json!({"ok": false, "error": "unknown endpoint", "endpoints": FAILOVER_ENDPOINTS}).to_string())
+        })
+        .layer(middleware::from_fn_with_state(app.clone(), failover_gate))
+        .layer(middleware::from_fn(cors))
+        .with_state(app)
+}
+
+/// Minimal second listener: health and emergency commands only, one request
+/// per connection, each on its own task with a hard time limit. It shares the
+/// API token with the main server (loopback exempt).
+pub async fn run_failover(app: AppHandle, config: crate::FailoverApiConfig) {
+    let listener = match TcpListener::bind((config.bind.as_str(), config.port)).await {
+        Ok(l) => {
+            info!("Failover API listening on http://{}:{}", config.bind, config.port);
+            l
+        }
+        Err(e) => {
+            error!("Failed to start failover API on {}:{}: {}", config.bind, config.port, e);
+            return;
+        }
+    };
+    let router = failover_router(app);
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let router = router.clone();
+        tokio::spawn(async move {
+            let service = hyper::service::service_fn(move |mut request: hyper::Request<Incoming>| {
+                request.extensions_mut().insert(ConnectInfo(peer));
+                router.clone().call(request)
+            });
+            let connection = http1::Builder::new()
+                .timer(TokioTimer::new())
+                .header_read_timeout(HEADER_READ_TIMEOUT)
+                .keep_alive(false)
+                .serve_connection(TokioIo::new(stream), service);
+            match tokio::time::timeout(FAILOVER_REQUEST_TIMEOUT, connection).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => debug!("Failover API connection from {} ended: {}", peer, e),
+                Err(_) => debug!("Failover API request from {} timed out", peer),
+            }
+        });
+    }
+}
+
+/// Token auth only; the failover listener stays up through maintenance mode.
+async fn failover_gate(State(app): State<AppHandle>, ConnectInfo(peer): ConnectInfo<SocketAddr>, request: Request, next: Next) -> Response {
+    debug!("Failover API request from {}: {} {}", peer, request.method(), request.uri().path());
+    if let Some(response) = unauthorized(&app, peer, &request) {
+        return response;
+    }
+    next.run(request).await
+}
+
+async fn failover_ping() -> Response {
+    json(StatusCode::OK, serde_json::json!({"ok": true, "app": "StageView", "version": env!("CARGO_PKG_VERSION"), "failover": true}).to_string())
+}
+
+// ── Middleware ───────────────────────────────────────────────────────────────
+
+/// Answer CORS preflights and allow any origin on every response.
+async fn cors(request: Request, next: Next) -> Response {
+    if request.method() == Method::OPTIONS {
+        return (
+            StatusCode::NO_CONTENT,
+            [
+                (header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"),
+                (header::ACCESS_CONTROL_ALLOW_METHODS, "GET, POST, PUT, DELETE, OPTIONS"),
+                (header::ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type, Authorization"),
+                (header::ACCESS_CONTROL_EXPOSE_HEADERS, "Location"),
+                (header::ACCESS_CONTROL_MAX_AGE, "86400"),
+            ],
+        ).into_response();
+    }
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    response
+}
+
+/// The 401 for a request without the API token, when one is set. Loopback
+/// callers and discovery (`/api/ping`) don't need it.
+fn unauthorized(app: &AppHandle, peer: SocketAddr, request: &Request) -> Option<Response> {
+    if peer.ip().is_loopback() || request.uri().path() == "/api/ping" {
+        return None;
+    }
+    let token = app.state::<AppState>().config.lock()
+        .ok()
+        .and_then(|c| c.api_token.clone())
+        .filter(|t| !t.is_empty())?;
+    let authorization = request.headers().get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+    let query = request.uri().query().unwrap_or("");
+    if crate::api_request_authorized(authorization, query, &token) {
+        return None;
+    }
+    let mut response = json_error(StatusCode::UNAUTHORIZED, "unauthorized");
+    response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Basic realm=\"StageView\""));
+    Some(response)
+}
+
+/// Token auth and the maintenance gate. The local desktop UI talks to the API
+/// over loopback, so it's exempt from both.
+async fn gate(State(app): State<AppHandle>, ConnectInfo(peer): ConnectInfo<SocketAddr>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    debug!("API request from {}: {} {}", peer, request.method(), path);
+    let activity = is_activity(request.method(), &path);
+    if peer.ip().is_loopback() {
+        if activity {
+            crate::screensaver::touch(&app);
+        }
+        return next.run(request).await;
+    }
+    let state = app.state::<AppState>();
+    if let Some(response) = unauthorized(&app, peer, &request) {
+        return response;
+    }
+
+    // Maintenance mode: other machines only get discovery, health, and the
+    // maintenance endpoints; the local wall keeps working until shutdown
+    if crate::in_maintenance(&state)
+        && !matches!(path.as_str(), "/api/ping" | "/healthz")
+        && !path.starts_with("/api/maintenance")
+    {
+        let body = serde_json::json!({
+            "ok": false,
+            "error": "maintenance",
+            "maintenance": crate::maintenance_status(&state),
+        });
+        let mut response = json(StatusCode::SERVICE_UNAVAILABLE, body.to_string());
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("60"));
+        return response;
+    }
+
+    if activity {
+        crate::screensaver::touch(&app);
+    }
+    next.run(request).await
+}
+
+/// Whether a request may be handed credentials: only with an `api_token`
+/// set, since the gate then let it through with the token.
+fn trusted(app: &AppHandle) -> bool {
+    app.state::<AppState>().config.lock().ok().and_then(|c| c.api_token.clone()).is_some_and(|t| !t.is_empty())
+}
+
+/// Whether a request counts as a command for the idle screensaver. Media,
+/// pages, and the read-only endpoints monitoring polls don't, so a dashboard
+/// left open doesn't keep the wall awake.
+fn is_activity(method: &Method, path: &str) -> bool {
+    if !path.starts_with("/api/") {
+        return false;
+    }
+    if method != Method::GET && method != Method::HEAD {
+        return true;
+    }
+    !matches!(
+        path,
+        "/api/ping" | "/api/status" | "/api/view" | "/api/rules" | "/api/events" | "/api/lint" | "/api/screensaver" | "/api/talkback" | "/api/clients" | "/api/cameras"
+            | "/api/layout/tiles" | "/api/page" | "/api/pip" | "/api/audio" | "/api/overlay/image" | "/api/dashboard" | "/api/maintenance"
+            | "/api/replication" | "/api/spare" | "/api/profiles" | "/api/config/export" | "/api/config/backups" | "/api/theme" | "/api/announcements"
+    ) && !path.starts_with("/api/cameras/")
+        && !path.starts_with("/api/replay/file/")
+        && !path.starts_with("/api/diagnose/")
+        && !path.starts_with("/api/health/")
+}
+
+// ── Responses ────────────────────────────────────────────────────────────────
+
+fn json(status: StatusCode, body: String) -> Response {
+    (status, [(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+fn json_error(status: StatusCode, error: &str) -> Response {
+    json(status, serde_json::json!({"ok": false, "error": error}).to_string())
+}
+
+/// `{"ok": true, "<key>": value}`, or a 400 with the error.
+fn reply<T: Serialize>(key: &str, result: Result<T, String>) -> Response {
+    match result {
+        Ok(value) => json(StatusCode::OK, serde_json::json!({"ok": true, key: value}).to_string()),
+        Err(e) => json_error(StatusCode::BAD_REQUEST, &e),
+    }
+}
+
+/// A handler result that is already the whole response body.
+fn value(result: Result<serde_json::Value, String>, error_status: StatusCode) -> Response {
+    match result {
+        Ok(value) => json(StatusCode::OK, value.to_string()),
+        Err(e) => json_error(error_status, &e),
+    }
+}
+
+/// Status from a shared helper's status line, e.g. "404 Not Found".
+fn status_code(line: &str) -> StatusCode {
+    line.split(' ')
+        .next()
+        .and_then(|code| code.parse().ok())
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn body_error(rejection: BytesRejection) -> Response {
+    json_error(rejection.status(), &rejection.body_text())
+}
+
+async fn not_found() -> Response {
+    json(StatusCode::NOT_FOUND, serde_json::json!({"ok": false, "error": "unknown endpoint", "endpoints": ENDPOINTS}).to_string())
+}
+
+// ── Pages ────────────────────────────────────────────────────────────────────
+
+async fn control_panel() -> Html<&'static str> {
+    Html(include_str!("control_panel.html"))
+}
+
+async fn viewer() -> Html<&'static str> {
+    Html(include_str!("viewer.html"))
+}
+
+/// Custom panels from <config dir>/www/
+async fn www(path: String) -> Response {
+    match crate::read_www_file(&path) {
+        Ok((content_type, data)) => ([(header::CONTENT_TYPE, content_type), (header::CACHE_CONTROL, "no-cache")], data).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e).into_response(),
+    }
+}
+
+// ── Camera Media ─────────────────────────────────────────────────────────────
+
+/// Live fMP4 for MSE: cached init segment, then the fragments since the last
+/// keyframe, then boxes as the broadcaster sends them.
+struct StreamEgress {
+    app: AppHandle,
+    camera_id: String, // Source camera whose caches and broadcaster are used
+    init: Option<Bytes>,
+    recent: VecDeque<Bytes>,
+    rx: broadcast::Receiver<Bytes>,
+    skip: Option<Bytes>, // After a resync, the newest cached fragment, if it may also arrive live
+    client_bucket: Option<TokenBucket>,
+    total_kbps: u32,
+    client: crate::StreamClientHandle, // Listed in /api/clients until the body is dropped
+}
+
+impl Drop for StreamEgress {
+    fn drop(&mut self) {
+        crate::unregister_stream_client(&self.app.state::<AppState>(), self.client.id);
+    }
+}
+
+impl StreamEgress {
+    /// The next box to send, or None when the stream ends or the client is kicked.
+    async fn next(&mut self) -> Option<Bytes> {
+        let kick = self.client.kick.clone();
+        tokio::select! {
+            _ = kick.notified() => None,
+            data = self.next_box() => data,
+        }
+    }
+
+    async fn next_box(&mut self) -> Option<Bytes> {
+        let app = self.app.clone();
+        let state = app.state::<AppState>();
+        let data = loop {
+            if let Some(init) = self.init.take() {
+                break init;
+            }
+            let data = match self.recent.pop_front() {
+                Some(fragment) => fragment,
+                None => match self.rx.recv().await {
+                    Ok(data) => {
+                        if self.skip.take().as_ref() == Some(&data) {
+                            continue; // Already sent from the cache
+                        }
+                        data
+                    }
+                    // A lagged client is missing fragments its decoder needs, so
+                    // the next live one would show artifacts until the following
+                    // keyframe. Start it over from the caches instead; dropping the
+                    // connection would make the frontend rebuild its MSE pipeline,
+                    // which leaks a blob URL each time and degrades over 24+ h uptime.
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("HTTP stream client lagged by {} MP4 boxes, resending from the last keyframe", n);
+                        self.resync();
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+            };
+            crate::pace_egress(&state, &mut self.client_bucket, self.total_kbps, data.len()).await;
+            break data;
+        };
+        state.egress_bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+        self.client.bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+        Some(data)
+    }
+
+    /// Queue the cached init segment and the fragments since the last keyframe,
+    /// and continue live from just after them.
+    fn resync(&mut self) {
+        let state = self.app.state::<AppState>();
+        {
+            let Ok(recent) = state.recent_segments.lock() else { return };
+            // Fragments are cached before they are sent, so resubscribing under the
+            // cache lock leaves at most the newest cached fragment still to arrive live
+            self.rx = self.rx.resubscribe();
+            self.recent = recent.get(&self.camera_id).cloned().unwrap_or_default();
+            self.skip = self.recent.back().cloned();
+        }
+        self.init = state.init_segments.lock().ok().and_then(|cache| cache.get(&self.camera_id).cloned());
+    }
+}
+
+async fn camera_stream(State(app): State<AppHandle>, ConnectInfo(peer): ConnectInfo<SocketAddr>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+    let state = app.state::<AppState>();
+    // Duplicate-URL aliases are served from their primary's broadcaster and caches
+    let camera_id = crate::stream_source_id(&state, &id);
+
+    // Get or create broadcast sender for this camera
+    let rx = {
+        let Ok(mut broadcasters) = state.frame_broadcasters.lock() else {
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, "Broadcaster mutex poisoned - please restart application");
+        };
+        let sender = broadcasters.entry(camera_id.clone())
+            .or_insert_with(|| broadcast::channel::<Bytes>(60).0);
+        sender.subscribe()
+    };
+
+    // Egress caps are read once per connection; 0 = unlimited
+    let (client_kbps, total_kbps) = state.config.lock()
+        .map(|c| (c.stream_client_max_kbps, c.stream_total_max_kbps))
+        .unwrap_or((0, 0));
+
+    // Cached recent fragments give the browser a decodable keyframe immediately
+    // instead of waiting up to GOP-length (1-3 seconds) for the next live one
+    let egress = StreamEgress {
+        app: app.clone(),
+        camera_id: camera_id.clone(),
+        init: state.init_segments.lock().ok().and_then(|cache| cache.get(&camera_id).cloned()),
+        recent: state.recent_segments.lock()
+            .ok()
+            .and_then(|cache| cache.get(&camera_id).cloned())
+            .unwrap_or_default(),
+        rx,
+        skip: None,
+        client_bucket: (client_kbps > 0).then(|| TokenBucket::new(client_kbps)),
+        total_kbps,
+        client: crate::register_stream_client(
+            &state,
+            peer,
+            &id,
+            headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok()).map(str::to_string),
+        ),
+    };
+    let body = futures_util::stream::unfold(egress, |mut egress| async move {
+        let data = egress.next().await?;
+        Some((Ok::<_, Infallible>(data), egress))
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, "video/mp4"),
+            (header::CACHE_CONTROL, "no-cache, no-store, must-revalidate"),
+            (header::PRAGMA, "no-cache"),
+        ],
+        Body::from_stream(body),
+    ).into_response()
+}
+
+/// JPEG still of the latest keyframe (dashboards, Companion button images)
+async fn snapshot(State(app): State<AppHandle>, Path(id): Path<String>, RawQuery(query): RawQuery) -> Response {
+    let state = app.state::<AppState>();
+    let known = state.config.lock().map(|c| c.cameras.iter().any(|c| c.id == id)).unwrap_or(false);
+    if !known {
+        return json_error(StatusCode::NOT_FOUND, &format!("Unknown camera: {}", id));
+    }
+    let width = crate::query_param(query.as_deref().unwrap_or(""), "width")
+        .and_then(|w| w.parse::<u32>().ok())
+        .map(|w| w.clamp(16, crate::SNAPSHOT_MAX_WIDTH));
+    match crate::capture_snapshot(&state, &id, width).await {
+        Ok(jpeg) => ([(header::CONTENT_TYPE, "image/jpeg"), (header::CACHE_CONTROL, "no-cache")], jpeg.to_vec()).into_response(),
+        Err(e) => json_error(StatusCode::SERVICE_UNAVAILABLE, &e),
+    }
+}
+
+/// HLS for clients without MSE (iOS Safari): /camera/:id/hls/<resource>
+async fn hls(State(app): State<AppHandle>, Path((id, resource)): Path<(String, String)>) -> Response {
+    let state = app.state::<AppState>();
+    let camera_id = crate::stream_source_id(&state, &id);
+    let (status, content_type, body) = crate::serve_hls(&state, &camera_id, &resource);
+    if resource == "playlist.m3u8" {
+        // HLS viewers get the main stream, not the grid's substream
+        crate::refresh_stream_choice(&app, &camera_id);
+    }
+    (status_code(status), [(header::CONTENT_TYPE, content_type), (header::CACHE_CONTROL, "no-cache")], body).into_response()
+}
+
+/// WebRTC (WHEP): POST an SDP offer, DELETE the returned Location to hang up
+async fn whep_offer(State(app): State<AppHandle>, ConnectInfo(peer): ConnectInfo<SocketAddr>, Path(id): Path<String>, body: Result<Bytes, BytesRejection>) -> Response {
+    let camera_id = crate::stream_source_id(&app.state::<AppState>(), &id);
+    let offer = body.ok()
+        .map(|b| String::from_utf8_lossy(&b).into_owned())
+        .filter(|sdp| sdp.starts_with("v=0"));
+    let Some(offer) = offer else {
+        return (StatusCode::BAD_REQUEST, "expected an SDP offer").into_response();
+    };
+    match crate::whep::create_session(&app, &camera_id, offer, !peer.ip().is_loopback()).await {
+        Ok((session_id, answer)) => (
+            StatusCode::CREATED,
+            [
+                (header::CONTENT_TYPE, "application/sdp".to_string()),
+                (header::LOCATION, format!("/camera/{}/whep/{}", id, session_id)),
+                (header::ACCESS_CONTROL_EXPOSE_HEADERS, "Location".to_string()),
+            ],
+            answer,
+        ).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e).into_response(),
+    }
+}
+
+async fn whep_close(State(app): State<AppHandle>, Path((_, session)): Path<(String, String)>) -> Response {
+    if crate::whep::close_session(&app.state::<AppState>(), &session).await {
+        StatusCode::OK.into_response()
+    } else {
+        (StatusCode::NOT_FOUND, "unknown session").into_response()
+    }
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+async fn ping(State(app): State<AppHandle>) -> Response {
+    let auth_required = app.state::<AppState>().config.lock()
+        .map(|c| c.api_token.as_deref().map(|t| !t.is_empty()).unwrap_or(false))
+        .unwrap_or(false);
+    json(StatusCode::OK, serde_json::json!({
+        "ok": true,
+        "app": "StageView",
+        "version": env!("CARGO_PKG_VERSION"),
+        "auth_required": auth_required,
+    }).to_string())
+}
+
+async fn healthz(State(app): State<AppHandle>) -> Response {
+    let (healthy, details) = crate::health_check(&app.state::<AppState>());
+    json(if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE }, details.to_string())
+}
+
+async fn emergency(State(app): State<AppHandle>, method: Method, uri: Uri) -> Response {
+    match crate::emergency_command(&app, method.as_str(), uri.path()) {
+        Some((status, body)) => json(status_code(status), body),
+        None => not_found().await,
+    }
+}
+
+async fn solo(State(app): State<AppHandle>, Path(index): Path<String>) -> Response {
+    match index.parse::<usize>() {
+        Ok(index) if index >= 1 => {
+            crate::emit_remote_command(&app, "solo", Some(index));
+            json(StatusCode::OK, serde_json::json!({"ok": true, "action": "solo", "index": index}).to_string())
+        }
+        Ok(_) => json_error(StatusCode::BAD_REQUEST, "index must be >= 1"),
+        Err(_) => json_error(StatusCode::BAD_REQUEST, "invalid index"),
+    }
+}
+
+async fn tile_layout(State(app): State<AppHandle>) -> Response {
+    let state = app.state::<AppState>();
+    match state.config.lock().map(|c| c.clone()) {
+        Ok(config) => json(StatusCode::OK, crate::tile_layout_json(&state, &config).to_string()),
+        Err(_) => json_error(StatusCode::INTERNAL_SERVER_ERROR, "config unavailable"),
+    }
+}
+
+async fn set_tile_layout(State(app): State<AppHandle>, body: Result<Bytes, BytesRejection>) -> Response {
+    match body {
+        Ok(body) => value(crate::set_tile_layout(&app, &body), StatusCode::BAD_REQUEST),
+        Err(rejection) => body_error(rejection),
+    }
+}
+
+async fn page(State(app): State<AppHandle>) -> Response {
+    reply("page", crate::current_page_info(&app.state::<AppState>()))
+}
+
+async fn go_to_page(State(app): State<AppHandle>, Path(target): Path<String>) -> Response {
+    let target = match target.as_str() {
+        "next" => PageTarget::Next,
+        "prev" | "previous" => PageTarget::Previous,
+        n => PageTarget::Number(n.parse().unwrap_or(0)), // 0 is rejected as out of range
+    };
+    reply("page", crate::go_to_page(&app, target))
+}
+
+async fn pip(State(app): State<AppHandle>) -> Response {
+    match crate::current_pip_state(&app.state::<AppState>()) {
+        Ok(pip) => json(StatusCode::OK, serde_json::json!({"ok": true, "pip": pip}).to_string()),
+        Err(e) => json_error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    }
+}
+
+async fn switch_pip(State(app): State<AppHandle>, Path((index, action)): Path<(String, String)>) -> Response {
+    let result = match (index.parse::<usize>(), action.as_str()) {
+        (Ok(index), "on" | "off") => crate::switch_pip(&app, index, action == "on"),
+        (Err(_), "on" | "off") => Err("invalid index".to_string()),
+        _ => Err("Expected /api/pip/:index/on or /api/pip/:index/off".to_string()),
+    };
+    reply("pip", result)
+}
+
+async fn audio(State(app): State<AppHandle>) -> Response {
+    reply("audio", crate::current_audio_monitor(&app.state::<AppState>()))
+}
+
+async fn set_audio(State(app): State<AppHandle>, Path(target): Path<String>) -> Response {
+    let result = match target.as_str() {
+        "off" | "none" => crate::set_audio_monitor(&app, None),
+        index => {
+            let camera_id = index.parse::<usize>().ok()
+                .and_then(|i| i.checked_sub(1))
+                .and_then(|i| app.state::<AppState>().config.lock().ok()?.cameras.get(i).map(|c| c.id.clone()));
+            match camera_id {
+                Some(id) => crate::set_audio_monitor(&app, Some(id)),
+                None => Err("invalid index".to_string()),
+            }
+        }
+    };
+    reply("audio", result)
+}
+
+async fn status(State(app): State<AppHandle>) -> Response {
+    let state = app.state::<AppState>();
+    let scores: std::collections::HashMap<String, u8> = state.stream_health.lock()
+        .map(|h| h.iter().map(|(id, health)| (id.clone(), health.health_score)).collect())
+        .unwrap_or_default();
+    let formats = state.stream_info.lock().map(|i| i.clone()).unwrap_or_default();
+    let attempts = state.reconnect_attempts.lock().map(|a| a.clone()).unwrap_or_default();
+    let pip_camera = state.pip_camera.lock().map(|p| p.clone()).unwrap_or_default();
+    let monitor_camera = state.monitor_camera.lock().map(|m| m.clone()).unwrap_or_default();
+    let Ok(config) = state.config.lock() else {
+        return json_error(StatusCode::INTERNAL_SERVER_ERROR, "Config mutex poisoned");
+    };
+    let cameras_json: Vec<serde_json::Value> = config.cameras.iter().enumerate().map(|(i, c)| {
+        serde_json::json!({"index": i + 1, "id": c.id, "name": c.name, "enabled": c.enabled, "health_score": scores.get(&c.id), "format": formats.get(&c.id), "reconnect_attempts": attempts.get(&c.id).copied().unwrap_or(0)})
+    }).collect();
+    let page = crate::page_info(&config, state.current_page.load(Ordering::Relaxed));
+    let pip = crate::pip_state(&config, pip_camera.as_ref());
+    let audio = crate::audio_monitor_state(&config, monitor_camera.as_ref());
+    json(StatusCode::OK, serde_json::json!({"ok": true, "cameras": cameras_json, "page": page, "pip": pip, "audio": audio}).to_string())
+}
+
+async fn identify(State(app): State<AppHandle>, Path(target): Path<String>, RawQuery(query): RawQuery) -> Response {
+    let seconds = crate::query_param(query.as_deref().unwrap_or(""), "seconds").and_then(|s| s.parse::<u64>().ok());
+    let index = match target.as_str() {
+        "all" => Ok(None),
+        idx => idx.parse::<usize>().map(Some).map_err(|_| "invalid index".to_string()),
+    };
+    value(index.and_then(|index| crate::identify_camera(app.clone(), index, seconds)), StatusCode::BAD_REQUEST)
+}
+
+async fn dashboard(State(app): State<AppHandle>) -> Response {
+    json(StatusCode::OK, crate::build_dashboard(&app.state::<AppState>()).to_string())
+}
+
+/// Grid or the soloed camera, for button feedback.
+async fn view(State(app): State<AppHandle>) -> Response {
+    let mut body = serde_json::to_value(crate::view_state(&app)).unwrap_or_default();
+    body["ok"] = serde_json::Value::Bool(true);
+    json(StatusCode::OK, body.to_string())
+}
+
+async fn rules(State(app): State<AppHandle>) -> Response {
+    let rules = app.state::<AppState>().config.lock()
+        .map(|c| c.rules.clone())
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string());
+    reply("rules", rules)
+}
+
+async fn set_rules(State(app): State<AppHandle>, Path(action): Path<String>) -> Response {
+    let result = match action.as_str() {
+        "on" => crate::set_rules_enabled(app, true),
+        "off" => crate::set_rules_enabled(app, false),
+        _ => Err("Expected /api/rules/on or /api/rules/off".to_string()),
+    };
+    reply("rules", result)
+}
+
+async fn screensaver(State(app): State<AppHandle>) -> Response {
+    reply("screensaver", Ok(crate::screensaver::status(&app)))
+}
+
+async fn set_screensaver(State(app): State<AppHandle>, Path(action): Path<String>) -> Response {
+    let result = match action.as_str() {
+        "on" => Ok(crate::set_screensaver(app, true)),
+        "off" => Ok(crate::set_screensaver(app, false)),
+        _ => Err("Expected /api/screensaver/on or /api/screensaver/off".to_string()),
+    };
+    reply("screensaver", result)
+}
+
+async fn talkback(State(app): State<AppHandle>) -> Response {
+    reply("talkback", Ok(crate::talkback::status(&app)))
+}
+
+/// Push-to-talk from a control surface: `/on` when the button goes down, `/off` when it comes up.
+async fn set_talkback(State(app): State<AppHandle>, Path((id, action)): Path<(String, String)>) -> Response {
+    let result = match action.as_str() {
+        "on" => crate::talkback::set(&app, &id, true),
+        "off" => crate::talkback::set(&app, &id, false),
+        _ => Err("Expected /api/talkback/:id/on or /api/talkback/:id/off".to_string()),
+    };
+    reply("talkback", result)
+}
+
+/// `/api/ptz/:id/move?pan=-0.5&tilt=0&zoom=0&timeout_ms=1000`, `/stop`, and `/presets`.
+/// Speeds run from -1 to 1; a Stream Deck button sends `move` on press and `stop` on release.
+async fn ptz(State(app): State<AppHandle>, Path((id, action)): Path<(String, String)>, RawQuery(query): RawQuery) -> Response {
+    let query = query.unwrap_or_default();
+    let speed = |key: &str| crate::query_param(&query, key).and_then(|v| v.parse::<f32>().ok()).unwrap_or(0.0);
+    let result = match action.as_str() {
+        "move" => {
+            let timeout_ms = crate::query_param(&query, "timeout_ms").and_then(|v| v.parse().ok());
+            crate::ptz::continuous_move(&app, &id, speed("pan"), speed("tilt"), speed("zoom"), timeout_ms).await
+                .map(|_| serde_json::json!({"ok": true, "action": "move"}))
+        }
+        "stop" => crate::ptz::stop(&app, &id).await.map(|_| serde_json::json!({"ok": true, "action": "stop"})),
+        "presets" => crate::ptz::presets(&app, &id).await.map(|presets| serde_json::json!({"ok": true, "presets": presets})),
+        _ => Err("Expected /api/ptz/:id/move, /stop, /presets, or /preset/:n".to_string()),
+    };
+    value(result, StatusCode::BAD_GATEWAY)
+}
+
+async fn ptz_preset(State(app): State<AppHandle>, Path((id, n)): Path<(String, String)>) -> Response {
+    let Some(n) = n.parse::<u32>().ok().filter(|&n| n >= 1) else {
+        return json_error(StatusCode::BAD_REQUEST, "preset must be a number >= 1");
+    };
+    let result = crate::ptz::goto_preset(&app, &id, n).await
+        .map(|token| serde_json::json!({"ok": true, "action": "preset", "preset": n, "token": token}));
+    value(result, StatusCode::BAD_GATEWAY)
+}
+
+async fn ptz_save_preset(State(app): State<AppHandle>, Path((id, n)): Path<(String, String)>) -> Response {
+    let Some(n) = n.parse::<u32>().ok().filter(|&n| n >= 1) else {
+        return json_error(StatusCode::BAD_REQUEST, "preset must be a number >= 1");
+    };
+    let result = crate::ptz::set_preset(&app, &id, n).await
+        .map(|token| serde_json::json!({"ok": true, "action": "save_preset", "preset": n, "token": token}));
+    value(result, StatusCode::BAD_GATEWAY)
+}
+
+/// Connected `/camera/:id/stream` clients: peer, camera, connect time, bytes sent.
+async fn clients(State(app): State<AppHandle>) -> Response {
+    reply("clients", Ok(crate::stream_clients(&app.state::<AppState>())))
+}
+
+async fn kick_client(State(app): State<AppHandle>, Path(id): Path<String>) -> Response {
+    let Ok(id) = id.parse::<u64>() else {
+        return json_error(StatusCode::BAD_REQUEST, "invalid client id");
+    };
+    match crate::kick_stream_client(&app.state::<AppState>(), id) {
+        Ok(client) => json(StatusCode::OK, serde_json::json!({"ok": true, "kicked": client}).to_string()),
+        Err(e) => json_error(StatusCode::NOT_FOUND, &e),
+    }
+}
+
+async fn fullscreen(State(app): State<AppHandle>) -> Response {
+    value(crate::api_fullscreen(app).await, StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn reload(State(app): State<AppHandle>) -> Response {
+    value(crate::api_reload(app).await, StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// `GET /api/cameras?q=` searches the inventory instead of listing everything.
+async fn camera_list(app: State<AppHandle>, method: Method, RawQuery(query): RawQuery, body: Result<Bytes, BytesRejection>) -> Response {
+    let search = crate::query_param(query.as_deref().unwrap_or(""), "q")
+        .filter(|_| method == Method::GET)
+        .map(|q| crate::percent_decode(&q.replace('+', " ")));
+    match search {
+        Some(q) => match app.state::<AppState>().config.lock() {
+            Ok(config) => json(StatusCode::OK, serde_json::json!({"ok": true, "matches": crate::find_cameras(&config, &q)}).to_string()),
+            Err(_) => json_error(StatusCode::INTERNAL_SERVER_ERROR, "Config mutex poisoned - please restart application"),
+        },
+        None => cameras(app, method, Path(String::new()), body).await,
+    }
+}
+
+async fn cameras(State(app): State<AppHandle>, method: Method, Path(id): Path<String>, body: Result<Bytes, BytesRejection>) -> Response {
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return body_error(rejection),
+    };
+    let body = matches!(method, Method::POST | Method::PUT).then_some(&body[..]);
+    match crate::camera_request(&app, method.as_str(), &id, body) {
+        Ok((status, json_body)) => json(status_code(status), json_body.to_string()),
+        Err((status, e)) => json_error(status_code(status), &e),
+    }
+}
+
+async fn overlay(State(app): State<AppHandle>, method: Method, headers: HeaderMap, RawQuery(query): RawQuery, body: Result<Bytes, BytesRejection>) -> Response {
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return body_error(rejection),
+    };
+    let content_type = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("");
+    let body = (method == Method::POST).then_some(&body[..]);
+    match crate::overlay_request(&app, method.as_str(), query.as_deref().unwrap_or(""), content_type, body) {
+        Ok((status, json_body)) => json(status_code(status), json_body.to_string()),
+        Err((status, e)) => json_error(status_code(status), &e),
+    }
+}
+
+/// `POST /api/cameras/import?format=frigate[&dry_run=1]` with the export file as the body.
+async fn import_cameras(State(app): State<AppHandle>, RawQuery(query): RawQuery, body: Result<Bytes, BytesRejection>) -> Response {
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return body_error(rejection),
+    };
+    let query = query.unwrap_or_default();
+    let Some(format) = crate::query_param(&query, "format") else {
+        return json_error(StatusCode::BAD_REQUEST, "format is required: hikvision, dahua, or frigate");
+    };
+    let dry_run = matches!(crate::query_param(&query, "dry_run"), Some("1" | "true"));
+    match crate::import_cameras_request(&app, format, &String::from_utf8_lossy(&body), dry_run) {
+        Ok((status, json_body)) => json(status_code(status), json_body.to_string()),
+        Err((status, e)) => json_error(status_code(status), &e),
+    }
+}
+
+/// `GET /api/config/export[?redact=0]`: the config as a bundle for another
+/// machine, redacted unless a trusted client asks otherwise.
+async fn export_config(State(app): State<AppHandle>, RawQuery(query): RawQuery) -> Response {
+    let full = matches!(crate::query_param(&query.unwrap_or_default(), "redact"), Some("0" | "false"));
+    if full && !trusted(&app) {
+        return json_error(StatusCode::FORBIDDEN, "A full export needs the API token; set api_token, or export with redact");
+    }
+    reply("bundle", crate::bundle::export(&app, !full))
+}
+
+/// `POST /api/config/import[?dry_run=1]` with a bundle (or config.json) as the
+/// body. Validation errors come back per field with a 400.
+async fn import_config(State(app): State<AppHandle>, RawQuery(query): RawQuery, body: Result<Bytes, BytesRejection>) -> Response {
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return body_error(rejection),
+    };
+    let bundle: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(bundle) => bundle,
+        Err(e) => return json_error(StatusCode::BAD_REQUEST, &format!("Invalid JSON: {}", e)),
+    };
+    let dry_run = matches!(crate::query_param(&query.unwrap_or_default(), "dry_run"), Some("1" | "true"));
+    match crate::bundle::import(&app, bundle, dry_run, true) {
+        Ok(report) => {
+            let status = if report.ok { StatusCode::OK } else { StatusCode::BAD_REQUEST };
+            json(status, serde_json::to_value(report).unwrap_or_default().to_string())
+        }
+        Err(e) => json_error(StatusCode::BAD_REQUEST, &e),
+    }
+}
+
+/// `GET /api/config/backups`: earlier versions of config.json, newest first.
+async fn config_backups() -> Response {
+    reply("backups", crate::list_backup_files())
+}
+
+/// `POST /api/config/backups/:version/restore`: roll the config back.
+async fn restore_config(State(app): State<AppHandle>, Path(version): Path<String>) -> Response {
+    value(crate::restore_config_backup(&app, &version).map(|change| serde_json::json!({
+        "ok": true,
+        "action": "restore",
+        "version": version,
+        "restarted": change.restarted,
+        "removed": change.removed,
+    })), StatusCode::BAD_REQUEST)
+}
+
+/// `GET /api/theme` returns the resolved theme; `PUT`/`POST` with a theme
+/// object as the body replaces it.
+async fn theme(State(app): State<AppHandle>, method: Method, body: Result<Bytes, BytesRejection>) -> Response {
+    if method == Method::GET || method == Method::HEAD {
+        return reply("theme", crate::theme::get(&app));
+    }
+    if method != Method::PUT && method != Method::POST {
+        return json_error(StatusCode::METHOD_NOT_ALLOWED, "Use GET, or PUT/POST with a theme");
+    }
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return body_error(rejection),
+    };
+    match serde_json::from_slice::<crate::theme::ThemeConfig>(&body) {
+        Ok(theme) => reply("theme", crate::theme::set(&app, theme)),
+        Err(e) => json_error(StatusCode::BAD_REQUEST, &format!("Invalid theme: {}", e)),
+    }
+}
+
+/// `GET /api/announcements[?verbosity=minimal]`: accessibility announcements as
+/// Server-Sent Events. Reconnecting clients get the ones they missed from
+/// `Last-Event-ID`, and a comment every 15s keeps idle proxies from closing it.
+/// Remote streams end when maintenance mode drains.
+async fn announcements(State(app): State<AppHandle>, ConnectInfo(peer): ConnectInfo<SocketAddr>, headers: HeaderMap, RawQuery(query): RawQuery) -> Response {
+    let configured = app.state::<AppState>().config.lock().map(|c| c.announce.verbosity).unwrap_or_default();
+    let verbosity = match crate::query_param(&query.unwrap_or_default(), "verbosity") {
+        None => configured,
+        Some(v) => match crate::announce::Verbosity::parse(v) {
+            Some(verbosity) => verbosity,
+            None => return json_error(StatusCode::BAD_REQUEST, "verbosity must be off, minimal, normal, or verbose"),
+        },
+    };
+    let last_id = headers.get("last-event-id").and_then(|v| v.to_str().ok()).and_then(|v| v.trim().parse().ok());
+    let (missed, receiver) = crate::announce::subscribe(&app, last_id);
+    let drain = (!peer.ip().is_loopback()).then(|| app.state::<AppState>().maintenance_drain.subscribe());
+
+    let body = futures_util::stream::unfold((missed, receiver, drain), move |(mut missed, mut receiver, mut drain)| async move {
+        loop {
+            let announcement = match missed.pop_front() {
+                Some(announcement) => announcement,
+                None => {
+                    let drained = async {
+                        match drain.as_mut() {
+                            Some(drain) => { let _ = drain.wait_for(|drained| *drained).await; }
+                            None => std::future::pending().await,
+                        }
+                    };
+                    let next = tokio::select! {
+                        _ = drained => return None,
+                        next = tokio::time::timeout(ANNOUNCE_KEEPALIVE, receiver.recv()) => next,
+                    };
+                    match next {
+                        Ok(Ok(announcement)) => announcement,
+                        Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                        Ok(Err(broadcast::error::RecvError::Closed)) => return None,
+                        Err(_) => return Some((Ok::<_, Infallible>(Bytes::from_static(b": keep-alive\n\n")), (missed, receiver, drain))),
+                    }
+                }
+            };
+            if announcement.verbosity <= verbosity {
+                let event = Bytes::from(crate::announce::sse_event(&announcement));
+                return Some((Ok(event), (missed, receiver, drain)));
+            }
+        }
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        Body::from_stream(body),
+    ).into_response()
+}
+
+async fn gateway_sync(State(app): State<AppHandle>) -> Response {
+    match crate::sync_gateway(app).await {
+        Ok(report) => {
+            let mut body = serde_json::to_value(report).unwrap_or_default();
+            body["ok"] = serde_json::Value::Bool(true);
+            json(StatusCode::OK, body.to_string())
+        }
+        Err(e) => json_error(StatusCode::BAD_GATEWAY, &e),
+    }
+}
+
+async fn follower_sync(State(app): State<AppHandle>) -> Response {
+    match crate::sync_follower(app).await {
+        Ok(report) => {
+            let mut body = serde_json::to_value(report).unwrap_or_default();
+            body["ok"] = serde_json::Value::Bool(true);
+            json(StatusCode::OK, body.to_string())
+        }
+        Err(e) => json_error(StatusCode::BAD_GATEWAY, &e),
+    }
+}
+
+/// `POST /api/detection` with `{"camera": "front_door", "label": "person"}`, e.g.
+/// from a Home Assistant `rest_command`.
+async fn detection(State(app): State<AppHandle>, body: Result<Bytes, BytesRejection>) -> Response {
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return body_error(rejection),
+    };
+    let enabled = app.state::<AppState>().config.lock().map(|c| c.detections.enabled).unwrap_or(false);
+    if !enabled {
+        return json_error(StatusCode::CONFLICT, "Detections are not enabled");
+    }
+    let detection: crate::detection::Detection = match serde_json::from_slice(&body) {
+        Ok(d) => d,
+        Err(e) => return json_error(StatusCode::BAD_REQUEST, &format!("Invalid detection: {}", e)),
+    };
+    let event = crate::detection::report(&app, detection, "http");
+    json(StatusCode::OK, serde_json::json!({"ok": true, "matched": event.is_some(), "event": event}).to_string())
+}
+
+/// `GET /api/events?camera=<id>&limit=<n>`: the event log, newest first.
+async fn events(State(app): State<AppHandle>, RawQuery(query): RawQuery) -> Response {
+    let query = query.unwrap_or_default();
+    let camera = crate::query_param(&query, "camera").map(crate::percent_decode);
+    let limit = crate::query_param(&query, "limit").and_then(|l| l.parse().ok()).unwrap_or(100);
+    let events = crate::event_log(&app.state::<AppState>(), camera.as_deref(), limit);
+    json(StatusCode::OK, serde_json::json!({"ok": true, "events": events}).to_string())
+}
+
+/// `GET /api/lint`: risky settings in the current config, most severe first.
+async fn lint(State(app): State<AppHandle>) -> Response {
+    let warnings = match app.state::<AppState>().config.lock() {
+        Ok(config) => crate::lint::lint(&config),
+        Err(_) => return json_error(StatusCode::INTERNAL_SERVER_ERROR, "Config mutex poisoned - please restart application"),
+    };
+    json(StatusCode::OK, serde_json::json!({"ok": true, "warnings": warnings}).to_string())
+}
+
+async fn bulk(State(app): State<AppHandle>, body: Result<Bytes, BytesRejection>) -> Response {
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return body_error(rejection),
+    };
+    match crate::bulk_request(&app, &body) {
+        Ok((status, json_body)) => json(status_code(status), json_body.to_string()),
+        Err((status, e)) => json_error(status_code(status), &e),
+    }
+}
+
+/// Uploaded overlay PNG, loaded by the wall via the image_url in overlay-changed
+async fn overlay_image(State(app): State<AppHandle>) -> Response {
+    let png = app.state::<AppState>().overlay.lock()
+        .ok()
+        .and_then(|o| o.as_ref().and_then(|o| o.png.clone()));
+    match png {
+        Some(png) => ([(header::CONTENT_TYPE, "image/png"), (header::CACHE_CONTROL, "no-cache")], png.to_vec()).into_response(),
+        None => json_error(StatusCode::NOT_FOUND, "no uploaded overlay"),
+    }
+}
+
+async fn maintenance_status(app: State<AppHandle>, query: RawQuery) -> Response {
+    maintenance(app, Path(String::new()), query).await
+}
+
+async fn maintenance(State(app): State<AppHandle>, Path(action): Path<String>, RawQuery(query): RawQuery) -> Response {
+    let result = match action.as_str() {
+        "" => Ok(crate::maintenance_status(&app.state::<AppState>())),
+        "on" => {
+            let message = crate::query_param(query.as_deref().unwrap_or(""), "message")
+                .map(|m| crate::percent_decode(&m.replace('+', " ")));
+            crate::enter_maintenance(&app, message)
+        }
+        "off" => crate::exit_maintenance(&app),
+        _ => Err("Expected /api/maintenance, /api/maintenance/on, or /api/maintenance/off".to_string()),
+    };
+    reply("maintenance", result)
+}
+
+/// `/api/camera/:id/enable|disable`: take a broken camera out of service
+/// without deleting its configuration, or bring it back.
+async fn camera_enabled(State(app): State<AppHandle>, Path((id, action)): Path<(String, String)>) -> Response {
+    let enabled = match action.as_str() {
+        "enable" => true,
+        "disable" => false,
+        _ => return json_error(StatusCode::BAD_REQUEST, "Expected /api/camera/:id/enable or /api/camera/:id/disable"),
+    };
+    match crate::change_camera_enabled(&app, &id, enabled) {
+        Ok(body) => json(StatusCode::OK, body.to_string()),
+        Err((status, e)) => json_error(status_code(status), &e),
+    }
+}
+
+async fn record(State(app): State<AppHandle>, Path((id, action)): Path<(String, String)>) -> Response {
+    let state = app.state::<AppState>();
+    let result = match action.as_str() {
+        "start" => crate::start_camera_recording(&state, &id),
+        "stop" => crate::stop_camera_recording(&state, &id),
+        _ => Err("Expected /api/record/:id/start or /api/record/:id/stop".to_string()),
+    };
+    crate::refresh_stream_choice(&app, &id);
+    value(result, StatusCode::BAD_REQUEST)
+}
+
+async fn replay_all(State(app): State<AppHandle>, Path(seconds): Path<String>) -> Response {
+    match seconds.parse::<u64>() {
+        Ok(seconds) if seconds >= 1 => value(crate::export_replay_all_async(&app, seconds).await, StatusCode::CONFLICT),
+        _ => json_error(StatusCode::BAD_REQUEST, "seconds must be a number >= 1"),
+    }
+}
+
+/// `/api/clip/:id?duration=30`: save the last `duration` seconds of one camera as an MP4.
+async fn clip(State(app): State<AppHandle>, Path(id): Path<String>, RawQuery(query): RawQuery) -> Response {
+    let duration = match crate::query_param(query.as_deref().unwrap_or(""), "duration") {
+        None => 30,
+        Some(d) => match d.parse::<u64>() {
+            Ok(d) if d >= 1 => d,
+            _ => return json_error(StatusCode::BAD_REQUEST, "duration must be a number >= 1"),
+        },
+    };
+    value(crate::save_replay_clip(&app, &id, duration).await, StatusCode::CONFLICT)
+}
+
+/// `GET /api/diagnose/:id`: step-by-step network check of one camera. Takes
+/// up to ~30s when a step has to time out.
+async fn diagnose(State(app): State<AppHandle>, Path(id): Path<String>) -> Response {
+    reply("report", crate::diagnose::diagnose(&app, &id).await)
+}
+
+/// `GET /api/health/:id?from=2026-10-01&to=2026-10-03`: the camera's recorded
+/// health as a CSV download.
+async fn health_csv(State(app): State<AppHandle>, Path(id): Path<String>, RawQuery(query): RawQuery) -> Response {
+    let query = query.unwrap_or_default();
+    let from = crate::query_param(&query, "from").map(crate::percent_decode);
+    let to = crate::query_param(&query, "to").map(crate::percent_decode);
+    let known = app.state::<AppState>().config.lock().map(|c| c.cameras.iter().any(|c| c.id == id)).unwrap_or(false);
+    if !known {
+        return json_error(StatusCode::NOT_FOUND, &format!("Unknown camera: {}", id));
+    }
+    match crate::health_log::export_csv(&id, from.as_deref(), to.as_deref()) {
+        Ok(csv) => {
+            let disposition = format!("attachment; filename=\"health-{}.csv\"", id.replace(['"', '\\'], ""));
+            ([(header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()), (header::CONTENT_DISPOSITION, disposition)], csv).into_response()
+        }
+        Err(e) => json_error(StatusCode::BAD_REQUEST, &e),
+    }
+}
+
+/// `POST /api/capture/:id?seconds=30`: offer a packet capture of one camera;
+/// repeat with `&confirm=<token>` from the reply to start it. Only with the
+/// API token set, since the caller confirms its own request.
+async fn capture(State(app): State<AppHandle>, Path(id): Path<String>, RawQuery(query): RawQuery) -> Response {
+    if !trusted(&app) {
+        return json_error(StatusCode::FORBIDDEN, "Packet capture over the API needs the API token; set api_token, or capture from the wall");
+    }
+    let query = query.unwrap_or_default();
+    let seconds = crate::query_param(&query, "seconds").and_then(|s| s.parse().ok()).unwrap_or(30);
+    let confirm = crate::query_param(&query, "confirm");
+    reply("capture", crate::capture::capture(&app, &id, seconds, confirm).await)
+}
+
+/// `GET /api/capture`: the capture in progress, or how the last one ended.
+async fn capture_status(State(app): State<AppHandle>) -> Response {
+    reply("capture", Ok(crate::capture::status(&app)))
+}
+
+/// `POST /api/sync?seconds=15&reference=cam9&apply=1`: time every camera
+/// against the `test://sync` reference; `apply=1` sets their `delay_ms`.
+async fn sync_check(State(app): State<AppHandle>, RawQuery(query): RawQuery) -> Response {
+    let query = query.unwrap_or_default();
+    let seconds = crate::query_param(&query, "seconds").and_then(|s| s.parse().ok()).unwrap_or(15);
+    let reference = crate::query_param(&query, "reference").map(crate::percent_decode);
+    let apply = crate::query_param(&query, "apply") == Some("1");
+    reply("sync", crate::sync::measure(&app, reference.as_deref(), seconds, apply).await)
+}
+
+async fn profiles(State(app): State<AppHandle>) -> Response {
+    reply("profiles", crate::list_profile_files(&app.state::<AppState>()))
+}
+
+/// `/api/profile/:name/activate` switches to a saved profile; `POST
+/// /api/profile/:name/save` saves the running config under that name.
+async fn profile(State(app): State<AppHandle>, method: Method, Path((name, action)): Path<(String, String)>) -> Response {
+    match (action.as_str(), method) {
+        ("activate", _) => value(crate::activate_profile(&app, &name).map(|change| serde_json::json!({
+            "ok": true,
+            "action": "profile",
+            "profile": name.trim(),
+            "restarted": change.restarted,
+            "removed": change.removed,
+        })), StatusCode::BAD_REQUEST),
+        ("save", Method::POST) => reply("profile", crate::save_profile_file(&app, &name)),
+        ("save", _) => json_error(StatusCode::METHOD_NOT_ALLOWED, "Use POST to save a profile"),
+        _ => json_error(StatusCode::NOT_FOUND, "Expected /api/profile/:name/activate or /api/profile/:name/save"),
+    }
+}
+
+/// `GET /api/replication`: config and wall state for a hot spare to mirror.
+/// The config carries keys and secrets, so it is only served with the token.
+async fn replication(State(app): State<AppHandle>) -> Response {
+    if !trusted(&app) {
+        return json_error(StatusCode::FORBIDDEN, "Replication needs the API token; set api_token here and spare.token on the spare");
+    }
+    reply("snapshot", crate::spare::snapshot(&app))
+}
+
+/// `GET /api/spare`: whether this machine is mirroring a primary.
+async fn spare(State(app): State<AppHandle>) -> Response {
+    reply("spare", Ok(crate::spare::status(&app)))
+}
+
+/// `POST /api/spare/promote`: stop mirroring and take over as the primary.
+async fn promote_spare(State(app): State<AppHandle>) -> Response {
+    reply("spare", crate::spare::promote(&app))
+}
+
+/// Replay clip playback (decrypted on the fly when stored encrypted)
+async fn replay_file(State(app): State<AppHandle>, Path(relative): Path<String>) -> Response {
+    match crate::read_replay_clip(&app.state::<AppState>(), &relative) {
+        Ok(data) => ([(header::CONTENT_TYPE, "video/mp4")], data).into_response(),
+        Err(e) => json_error(StatusCode::NOT_FOUND, &e),
+    }
+}
@@ -0,0 +1,425 @@
+//! Config bundles, for cloning a setup onto other venue machines. `export`
+//! wraps the config as config.json has it (`${...}` references as written,
+//! not their values) with the app version and time, optionally with
+//! credentials replaced by a placeholder. `import` checks a bundle field by
+//! field before anything is applied: settings that don't parse, stream URLs
+//! that can't work, references this machine can't resolve, and lint errors.
+//! A bundle that passes replaces the config, keeping this machine's own
+//! settings the way switching profiles does. Redacted credentials are filled
+//! in from this machine's config where it has the same camera or setting.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+/// Bundle format written by `export`; newer bundles are refused.
+const FORMAT_VERSION: u32 = 1;
+
+/// Stands in for a credential in a redacted bundle.
+const REDACTED: &str = "REDACTED";
+
+/// Settings whose value is a credential wherever they appear.
+const SECRET_KEYS: &[&str] = &["password", "passphrase", "api_token", "recording_key", "community"];
+
+/// Sections where `token` is another wall's API token (elsewhere, e.g. a PTZ
+/// preset, it is just an identifier).
+const TOKEN_SECTIONS: &[&str] = &["spare", "follower"];
+
+/// URL query parameters that carry credentials.
+const SECRET_PARAMS: &[&str] = &["token", "password", "passphrase"];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bundle {
+    pub stageview_bundle: u32, // Format version
+    #[serde(default)]
+    pub app_version: String,
+    #[serde(default)]
+    pub exported_at: String, // ISO 8601 UTC
+    #[serde(default)]
+    pub redacted: usize, // Credentials replaced by the placeholder
+    pub config: Value,
+}
+
+/// A problem with one setting; `field` is a path like `cameras[2].url` ("" =
+/// the config as a whole).
+#[derive(Serialize, Clone, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ImportReport {
+    pub ok: bool, // No errors
+    pub applied: bool,
+    pub dry_run: bool,
+    pub cameras: usize,
+    pub errors: Vec<FieldError>, // Any error blocks the import
+    pub warnings: Vec<FieldError>,
+    pub restarted: Vec<String>, // Cameras whose pipelines were cycled
+}
+
+impl ImportReport {
+    fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError { field: field.into(), message: message.into() });
+    }
+
+    fn warning(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.warnings.push(FieldError { field: field.into(), message: message.into() });
+    }
+}
+
+fn child(path: &str, key: &str) -> String {
+    if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) }
+}
+
+/// The running config as config.json would have it.
+fn saved_form(app: &AppHandle) -> Result<Value, String> {
+    let state = app.state::<crate::AppState>();
+    let config = state.config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?
+        .clone();
+    let mut value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    if let Ok(references) = state.env_references.lock() {
+        crate::restore_env_references(&mut value, &references);
+    }
+    Ok(value)
+}
+
+/// Bundle the running config, with credentials replaced when `redact` is set.
+/// Only the desktop app and API clients holding the token get a full export.
+pub fn export(app: &AppHandle, redact: bool) -> Result<Bundle, String> {
+    let mut config = saved_form(app)?;
+    let mut redacted = 0;
+    if redact {
+        redact_value(&mut config, false, "", &mut redacted);
+    } else {
+        // Passwords are kept out of the saved form; a full export carries them
+        let passwords = app.state::<crate::AppState>().config.lock()
+            .map(|c| crate::credentials::passwords(&c))
+            .unwrap_or_default();
+        for camera in config["cameras"].as_array_mut().into_iter().flatten() {
+            if let Some(password) = camera["id"].as_str().and_then(|id| passwords.get(id)) {
+                camera["password"] = Value::String(password.clone());
+            }
+        }
+    }
+    info!("Exported the config{}", if redact { format!(" ({} credential(s) redacted)", redacted) } else { String::new() });
+    Ok(Bundle {
+        stageview_bundle: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: crate::iso8601_utc(crate::unix_millis() / 1000),
+        redacted,
+        config,
+    })
+}
+
+// ── Redaction ────────────────────────────────────────────────────────────────
+
+/// A credential worth hiding: set, not already hidden, and not a `${...}`
+/// reference (which names a secret rather than holding it).
+fn is_secret_value(value: &str) -> bool {
+    !value.is_empty() && value != REDACTED && !value.contains("${")
+}
+
+/// `url` with its password and credential query parameters replaced, if it
+/// has any. Composite URLs are handled part by part.
+fn redact_url(url: &str) -> Option<String> {
+    if url.contains('|') {
+        let parts: Vec<Option<String>> = url.split('|').map(redact_url).collect();
+        if parts.iter().all(Option::is_none) {
+            return None;
+        }
+        let joined = url.split('|').zip(parts).map(|(part, redacted)| redacted.unwrap_or_else(|| part.to_string()));
+        return Some(joined.collect::<Vec<_>>().join("|"));
+    }
+    let (scheme, rest) = url.split_once("://")?;
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, tail) = rest.split_at(authority_end);
+    let mut changed = false;
+
+    let authority = match authority.rsplit_once('@').and_then(|(userinfo, host)| Some((userinfo.split_once(':')?, host))) {
+        Some(((user, password), host)) if is_secret_value(password) => {
+            changed = true;
+            format!("{}:{}@{}", user, REDACTED, host)
+        }
+        _ => authority.to_string(),
+    };
+    let tail = match tail.split_once('?') {
+        Some((path, query)) => {
+            let params: Vec<String> = query.split('&').map(|param| match param.split_once('=') {
+                Some((key, value)) if SECRET_PARAMS.contains(&key) && is_secret_value(value) => {
+                    changed = true;
+                    format!("{}={}", key, REDACTED)
+                }
+                _ => param.to_string(),
+            }).collect();
+            format!("{}?{}", path, params.join("&"))
+        }
+        None => tail.to_string(),
+    };
+    changed.then(|| format!("{}://{}{}", scheme, authority, tail))
+}
+
+/// What a string becomes when redacted: the placeholder for a credential
+/// setting, or the URL with its credentials hidden.
+fn redact_string(value: &str, secret: bool) -> Option<String> {
+    if secret {
+        is_secret_value(value).then(|| REDACTED.to_string())
+    } else {
+        redact_url(value)
+    }
+}
+
+fn is_secret_key(key: &str, section: &str) -> bool {
+    SECRET_KEYS.contains(&key) || (key == "token" && TOKEN_SECTIONS.contains(&section))
+}
+
+fn redact_value(value: &mut Value, secret: bool, section: &str, count: &mut usize) {
+    match value {
+        Value::String(s) => {
+            if let Some(redacted) = redact_string(s, secret) {
+                *s = redacted;
+                *count += 1;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| redact_value(v, secret, section, count)),
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                redact_value(v, is_secret_key(key, section), key, count);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Put this machine's credentials back where a bundle has the placeholder,
+/// matching array entries by `id` where they have one. Placeholders with
+/// nothing to fill them are warned about and left in place.
+fn fill_redacted(value: &mut Value, current: Option<&Value>, secret: bool, section: &str, path: &str, report: &mut ImportReport) {
+    match value {
+        Value::String(s) if s.contains(REDACTED) => {
+            let own = current.and_then(Value::as_str).filter(|c| redact_string(c, secret).as_deref() == Some(s.as_str()));
+            match own {
+                Some(own) => *s = own.to_string(),
+                None => report.warning(path, "Credential was redacted on export and this machine has none to keep; set it after importing"),
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                let id = item.get("id").and_then(Value::as_str).map(str::to_string);
+                let own = match &id {
+                    Some(id) => current.and_then(Value::as_array)
+                        .and_then(|list| list.iter().find(|c| c.get("id").and_then(Value::as_str) == Some(id))),
+                    None => current.and_then(|c| c.get(i)),
+                };
+                fill_redacted(item, own, secret, section, &format!("{}[{}]", path, i), report);
+            }
+        }
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                fill_redacted(v, current.and_then(|c| c.get(key)), is_secret_key(key, section), key, &child(path, key), report);
+            }
+        }
+        _ => {}
+    }
+}
+
+// ── Validation ───────────────────────────────────────────────────────────────
+
+/// Resolve `${...}` references against this machine, reporting each one that
+/// can't be resolved at the setting that uses it. A reference could read any
+/// variable or file into a setting the API hands back, so only ones this
+/// machine's config.json already has at that setting (`known`) are taken
+/// from a `remote` bundle, and new `file:` references never are.
+fn resolve_references(value: &mut Value, path: &str, env_path: &str, known: &[crate::EnvReference], remote: bool, references: &mut Vec<crate::EnvReference>, report: &mut ImportReport) {
+    match value {
+        Value::String(s) if s.contains("${") => {
+            let names = crate::env_reference_names(s);
+            if !known.iter().any(|r| r.path == env_path && r.template == *s) {
+                if let Some(name) = names.iter().find(|n| n.starts_with("file:")) {
+                    report.error(path, format!("${{{}}}: file references can't be imported; add them to config.json on this machine", name));
+                    return;
+                }
+                if remote && !names.is_empty() {
+                    report.error(path, "${...} references can't be imported over the API; import the bundle in the app, or add them to config.json on this machine");
+                    return;
+                }
+            }
+            let mut missing = Vec::new();
+            let resolved = crate::expand_env(s, &mut missing);
+            for name in missing {
+                report.error(path, format!("${{{}}} is not set on this machine", name));
+            }
+            references.push(crate::EnvReference { path: env_path.to_string(), template: s.clone(), resolved: resolved.clone() });
+            *s = resolved;
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                let env_path = crate::env_child_path(env_path, &i.to_string(), Some(item));
+                resolve_references(item, &format!("{}[{}]", path, i), &env_path, known, remote, references, report);
+            }
+        }
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                resolve_references(v, &child(path, key), &crate::env_child_path(env_path, key, None), known, remote, references, report);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Why a camera URL can't work, if it can't.
+fn check_camera_url(url: &str) -> Result<(), String> {
+    let url = url.trim();
+    let local = [crate::DEVICE_PREFIX, crate::SCREEN_PREFIX, crate::NDI_PREFIX, "file://"];
+    if crate::TEST_PATTERN_PREFIXES.iter().chain(&local).any(|prefix| url.starts_with(prefix)) {
+        return Ok(());
+    }
+    if let Some(inner) = url.strip_prefix(crate::DATA_TILE_PREFIX) {
+        return check_network_url(inner, &["http", "https"]);
+    }
+    if let Some(sources) = url.strip_prefix(crate::COMPOSITE_PREFIX) {
+        return sources.split('|').map(str::trim).filter(|s| !s.is_empty()).try_for_each(check_camera_url);
+    }
+    if !url.contains("://") {
+        let drive_path = url.len() > 2 && url.as_bytes()[0].is_ascii_alphabetic() && &url[1..3] == ":\\";
+        return if std::path::Path::new(url).is_absolute() || drive_path {
+            Ok(())
+        } else {
+            Err(format!("{:?} is not a stream URL or an absolute file path", url))
+        };
+    }
+    check_network_url(url, &["rtsp", "rtsps", "rtmp", "rtmps", "srt", "udp", "rtp", "http", "https"])
+}
+
+fn check_network_url(url: &str, schemes: &[&str]) -> Result<(), String> {
+    let parts = crate::UrlParts::parse(url.trim()).ok_or_else(|| format!("{:?} has no host", url))?;
+    if !schemes.iter().any(|s| parts.scheme.eq_ignore_ascii_case(s)) {
+        return Err(format!("Unsupported scheme {:?}; expected {}", parts.scheme, schemes.join(", ")));
+    }
+    Ok(())
+}
+
+/// Check a resolved config value field by field, and return the config if it
+/// parses.
+fn validate(value: &Value, report: &mut ImportReport) -> Option<crate::AppConfig> {
+    let Some(map) = value.as_object() else {
+        report.error("", "The config must be a JSON object");
+        return None;
+    };
+    let defaults = serde_json::to_value(crate::AppConfig::default()).unwrap_or_default();
+    for (key, setting) in map {
+        if key == "cameras" {
+            continue;
+        }
+        if defaults.get(key).is_none() && key != "profile" {
+            report.warning(key, "Unknown setting; ignored");
+            continue;
+        }
+        // Parse each setting on its own, so every bad one is reported
+        let mut probe = defaults.clone();
+        probe[key] = setting.clone();
+        if let Err(e) = serde_json::from_value::<crate::AppConfig>(probe) {
+            report.error(key, e.to_string());
+        }
+    }
+
+    match map.get("cameras") {
+        None => report.error("cameras", "Missing; a config needs a camera list, even an empty one"),
+        Some(Value::Array(cameras)) => {
+            for (i, camera) in cameras.iter().enumerate() {
+                let path = format!("cameras[{}]", i);
+                match serde_json::from_value::<crate::Camera>(camera.clone()) {
+                    Err(e) => report.error(path, e.to_string()),
+                    Ok(camera) => {
+                        if !camera.url.trim().is_empty() {
+                            if let Err(e) = check_camera_url(&camera.url) {
+                                report.error(child(&path, "url"), e);
+                            }
+                        }
+                        if let Some(Err(e)) = camera.sub_url.as_deref().filter(|u| !u.trim().is_empty()).map(check_camera_url) {
+                            report.error(child(&path, "sub_url"), e);
+                        }
+                        if let Some(Err(e)) = camera.backup_url.as_deref().filter(|u| !u.trim().is_empty()).map(check_camera_url) {
+                            report.error(child(&path, "backup_url"), e);
+                        }
+                        if let Some(Err(e)) = camera.onvif.as_ref().and_then(|o| o.url.as_deref()).map(|u| check_network_url(u, &["http", "https"])) {
+                            report.error(child(&path, "onvif.url"), e);
+                        }
+                    }
+                }
+            }
+        }
+        Some(_) => report.error("cameras", "Expected a list of cameras"),
+    }
+    if !report.errors.is_empty() {
+        return None;
+    }
+
+    let config = match serde_json::from_value::<crate::AppConfig>(value.clone()) {
+        Ok(config) => config,
+        Err(e) => {
+            report.error("", e.to_string());
+            return None;
+        }
+    };
+    for lint in crate::lint::lint(&config).into_iter().filter(|l| l.severity == crate::lint::Severity::Error) {
+        let field = lint.camera_id.as_ref()
+            .and_then(|id| config.cameras.iter().position(|c| &c.id == id))
+            .map(|i| format!("cameras[{}]", i))
+            .unwrap_or_default();
+        report.error(field, lint.message);
+    }
+    Some(config)
+}
+
+/// Check a bundle (or a bare config.json) and, unless `dry_run` or it has
+/// errors, make it this machine's config. Cameras whose stream settings
+/// differ reconnect; the others keep running. `remote` is a bundle that came
+/// over the API rather than from the desktop app.
+pub fn import(app: &AppHandle, bundle: Value, dry_run: bool, remote: bool) -> Result<ImportReport, String> {
+    let mut report = ImportReport { dry_run, ..Default::default() };
+    let mut value = if bundle.get("stageview_bundle").is_some() {
+        let bundle: Bundle = serde_json::from_value(bundle).map_err(|e| format!("Invalid bundle: {}", e))?;
+        if bundle.stageview_bundle > FORMAT_VERSION {
+            return Err(format!(
+                "This bundle is format {} (StageView {}); this version reads format {} and older",
+                bundle.stageview_bundle, bundle.app_version, FORMAT_VERSION
+            ));
+        }
+        bundle.config
+    } else {
+        bundle
+    };
+
+    // Held from reading the running config until the import is applied, like edit_config
+    let state = app.state::<crate::AppState>();
+    let _edits = state.config_edits.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?;
+    let current = saved_form(app)?;
+    fill_redacted(&mut value, Some(&current), false, "", "", &mut report);
+    let known = state.env_references.lock().map(|r| r.clone()).unwrap_or_default();
+    let mut references = Vec::new();
+    resolve_references(&mut value, "", "", &known, remote, &mut references, &mut report);
+    let config = validate(&value, &mut report);
+    report.cameras = value.get("cameras").and_then(Value::as_array).map_or(0, Vec::len);
+    report.ok = report.errors.is_empty();
+    let Some(mut config) = config.filter(|_| report.ok && !dry_run) else {
+        return Ok(report);
+    };
+
+    {
+        let current = state.config.lock()
+            .map_err(|_| "Config mutex poisoned - please restart application".to_string())?;
+        crate::keep_machine_settings(&mut config, &current);
+    }
+    // The bundle's profile names a file on the machine it came from
+    config.profile = None;
+    let change = crate::replace_config(app, config, references)?;
+    info!("Imported a config bundle: {} camera(s), cycled {} pipeline(s)", report.cameras, change.restarted.len() + change.removed.len());
+    report.applied = true;
+    report.restarted = change.restarted;
+    Ok(report)
+}
@@ -0,0 +1,127 @@
+//! Burn-in mitigation for OLED and plasma walls. Tile borders, labels, and a
+//! station logo sit on the same pixels for weeks on a 24/7 wall; the backend
+//! walks the whole layout around a small spiral of pixel offsets, and dims an
+//! overlay graphic (`/api/overlay`) once it has been up for a while. Both go
+//! out as `burn-in` events, which `/ws` also forwards to remote displays.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::debug;
+
+/// How often shift and dim state is re-evaluated; also how soon config changes apply.
+const TICK: Duration = Duration::from_secs(10);
+
+/// Upper bound for `shift_max_px`; more than this is visible as movement.
+const MAX_SHIFT_PX: u32 = 16;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BurnInConfig {
+    #[serde(default)]
+    pub pixel_shift: bool,
+    #[serde(default = "default_shift_interval_secs")]
+    pub shift_interval_secs: u64, // Seconds between one-pixel steps
+    #[serde(default = "default_shift_max_px")]
+    pub shift_max_px: u32, // Largest offset from the home position, in pixels (1-16)
+    #[serde(default)]
+    pub logo_dim: bool,
+    #[serde(default = "default_logo_dim_after_mins")]
+    pub logo_dim_after_mins: u64, // Minutes an overlay stays at full brightness
+    #[serde(default = "default_logo_dim_opacity")]
+    pub logo_dim_opacity: f32, // Opacity once dimmed (0-1)
+}
+
+fn default_shift_interval_secs() -> u64 { 180 }
+fn default_shift_max_px() -> u32 { 3 }
+fn default_logo_dim_after_mins() -> u64 { 10 }
+fn default_logo_dim_opacity() -> f32 { 0.4 }
+
+impl Default for BurnInConfig {
+    fn default() -> Self {
+        Self {
+            pixel_shift: false,
+            shift_interval_secs: default_shift_interval_secs(),
+            shift_max_px: default_shift_max_px(),
+            logo_dim: false,
+            logo_dim_after_mins: default_logo_dim_after_mins(),
+            logo_dim_opacity: default_logo_dim_opacity(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BurnInEvent {
+    pub x: i32, // Layout offset in pixels
+    pub y: i32,
+    pub pixel_shift: bool, // The wall leaves shifting to these offsets and skips its own solo-mode shift
+    pub overlay_opacity: f32, // 1 = full brightness
+}
+
+impl Default for BurnInEvent {
+    fn default() -> Self {
+        Self { x: 0, y: 0, pixel_shift: false, overlay_opacity: 1.0 }
+    }
+}
+
+/// Offset for `step` on a square spiral out to `max_px`: home, then each ring
+/// in turn, so every pixel gets time at every nearby position.
+fn offset(step: usize, max_px: u32) -> (i32, i32) {
+    let mut positions = vec![(0, 0)];
+    for r in 1..=max_px.clamp(1, MAX_SHIFT_PX) as i32 {
+        positions.extend((-r..r).map(|x| (x, -r)));
+        positions.extend((-r..r).map(|y| (r, y)));
+        positions.extend((-r..r).map(|x| (-x, r)));
+        positions.extend((-r..r).map(|y| (-r, -y)));
+    }
+    positions[step % positions.len()]
+}
+
+/// Opacity for the current overlay: dimmed once it has been shown longer than
+/// `logo_dim_after_mins`. Full-wall notices are never dimmed.
+fn overlay_opacity(app: &AppHandle, config: &BurnInConfig) -> f32 {
+    if !config.logo_dim {
+        return 1.0;
+    }
+    let shown_at = app.state::<crate::AppState>().overlay.lock().ok().and_then(|o| {
+        o.as_ref().filter(|o| o.position != crate::OverlayPosition::Fill).map(|o| o.id)
+    });
+    match shown_at {
+        Some(id) if crate::unix_millis().saturating_sub(id) >= config.logo_dim_after_mins * 60_000 => {
+            config.logo_dim_opacity.clamp(0.0, 1.0)
+        }
+        _ => 1.0,
+    }
+}
+
+/// Current offset and overlay opacity, for the frontend on load.
+pub fn current(app: &AppHandle) -> BurnInEvent {
+    app.state::<crate::AppState>().burn_in.lock().map(|b| b.clone()).unwrap_or_default()
+}
+
+/// Step the shift and re-check the overlay until the app exits; only changes
+/// are emitted. Turning `pixel_shift` off sends the layout home.
+pub async fn run(app: AppHandle) {
+    let mut step = 0;
+    let mut last_shift = Instant::now();
+    loop {
+        tokio::time::sleep(TICK).await;
+        let Ok(config) = app.state::<crate::AppState>().config.lock().map(|c| c.burn_in.clone()) else { continue };
+        if !config.pixel_shift {
+            step = 0;
+        } else if last_shift.elapsed() >= Duration::from_secs(config.shift_interval_secs.max(1)) {
+            step += 1;
+            last_shift = Instant::now();
+        }
+        let (x, y) = offset(step, config.shift_max_px);
+        let event = BurnInEvent { x, y, pixel_shift: config.pixel_shift, overlay_opacity: overlay_opacity(&app, &config) };
+
+        let state = app.state::<crate::AppState>();
+        let Ok(mut current) = state.burn_in.lock() else { continue };
+        if *current != event {
+            debug!("Burn-in: layout offset ({}, {}), overlay opacity {}", x, y, event.overlay_opacity);
+            *current = event.clone();
+            drop(current);
+            let _ = app.emit("burn-in", event);
+        }
+    }
+}
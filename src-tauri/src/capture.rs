@@ -0,0 +1,277 @@
+//! Packet capture for support tickets. `capture_traffic` records the traffic
+//! to and from one camera's host as a pcap, using Wireshark's `dumpcap` or
+//! `tcpdump` when either is installed, on the interface the camera is reached
+//! through. Captures can hold credentials and other traffic, so over the API
+//! they need the API token, and each one takes two calls: the first returns
+//! what would be captured and a confirmation token, and only a second call
+//! with that token starts it. The capture then runs in the background;
+//! `status` reports it. Captures are written under `<config dir>/captures`.
+
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncReadExt;
+use tracing::{info, warn};
+
+/// A confirmation token is only good for this long.
+const CONFIRM_TTL: Duration = Duration::from_secs(60);
+
+/// Longest capture allowed.
+const MAX_SECS: u64 = 300;
+
+/// A capture stops early once the file reaches this size.
+const MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+/// How often the running capture's size is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A capture offered by the first call, waiting for its confirmation.
+struct Pending {
+    token: String,
+    camera_id: String,
+    seconds: u64,
+    offered: Instant,
+}
+
+#[derive(Default)]
+pub struct CaptureState {
+    pending: Option<Pending>,
+    running: Option<CaptureResult>, // `Started` for the capture in progress
+    last: Option<CaptureResult>, // How the previous capture ended
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum CaptureResult {
+    /// Nothing captured yet; call again with `confirm` set to `token`.
+    Confirm {
+        token: String,
+        camera_id: String,
+        host: String,
+        filter: String,
+        seconds: u64,
+        max_mb: u64,
+        tool: String,
+        interface: String,
+        message: String,
+    },
+    /// Running in the background until `until_ms`; `status` has the outcome.
+    Started {
+        camera_id: String,
+        path: String,
+        interface: String,
+        seconds: u64,
+        until_ms: u64,
+    },
+    Done {
+        camera_id: String,
+        path: String,
+        bytes: u64,
+        seconds: u64,
+        truncated: bool, // Stopped at the size limit
+    },
+    Failed {
+        camera_id: String,
+        error: String,
+    },
+}
+
+/// The capture program, if one is installed: dumpcap first (it needs no root
+/// once Wireshark's installer has set it up), then tcpdump.
+fn find_tool() -> Option<PathBuf> {
+    let names: &[&str] = if cfg!(windows) { &["dumpcap.exe"] } else { &["dumpcap", "tcpdump"] };
+    let mut dirs: Vec<PathBuf> = std::env::var_os("PATH").map(|p| std::env::split_paths(&p).collect()).unwrap_or_default();
+    if cfg!(windows) {
+        dirs.push(PathBuf::from(r"C:\Program Files\Wireshark"));
+    } else {
+        dirs.extend(["/usr/sbin", "/usr/bin", "/Applications/Wireshark.app/Contents/MacOS"].map(PathBuf::from));
+    }
+    names.iter().find_map(|name| dirs.iter().map(|d| d.join(name)).find(|p| p.is_file()))
+}
+
+/// Arguments for `tool` writing packets on `interface` matching `filter` to `path`.
+fn tool_args(tool: &std::path::Path, interface: &str, filter: &str, seconds: u64, path: &std::path::Path) -> Vec<String> {
+    let path = path.to_string_lossy().to_string();
+    let is_tcpdump = tool.file_stem().is_some_and(|s| s == "tcpdump");
+    if is_tcpdump {
+        // -U writes each packet out as it arrives, so killing tcpdump loses nothing
+        vec![
+            "-n".to_string(), "-s".to_string(), "0".to_string(), "-U".to_string(),
+            "-i".to_string(), interface.to_string(), "-w".to_string(), path, filter.to_string(),
+        ]
+    } else {
+        vec![
+            "-q".to_string(), "-i".to_string(), interface.to_string(), "-f".to_string(), filter.to_string(), "-w".to_string(), path,
+            "-a".to_string(), format!("duration:{}", seconds),
+            "-a".to_string(), format!("filesize:{}", MAX_BYTES / 1024),
+        ]
+    }
+}
+
+/// The interface traffic to `camera` goes out on: the one holding the local
+/// address the OS picks to reach it. Named the way the capture tools take
+/// it (`\Device\NPF_{GUID}` on Windows).
+async fn capture_interface(camera: IpAddr) -> Result<String, String> {
+    let unspecified: IpAddr = if camera.is_ipv4() { Ipv4Addr::UNSPECIFIED.into() } else { Ipv6Addr::UNSPECIFIED.into() };
+    let socket = tokio::net::UdpSocket::bind((unspecified, 0)).await.map_err(|e| e.to_string())?;
+    // Connecting a UDP socket sends nothing; it only picks the route
+    socket.connect((camera, 9)).await.map_err(|e| format!("No route to {}: {}", camera, e))?;
+    let local = socket.local_addr().map_err(|e| e.to_string())?.ip();
+    let interface = if_addrs::get_if_addrs()
+        .map_err(|e| format!("Can't list network interfaces: {}", e))?
+        .into_iter()
+        .find(|i| i.ip() == local)
+        .ok_or_else(|| format!("No interface holds {}, the address used to reach {}", local, camera))?;
+    #[cfg(windows)]
+    return Ok(format!(r"\Device\NPF_{}", interface.adapter_name));
+    #[cfg(not(windows))]
+    Ok(interface.name)
+}
+
+/// The camera's host, resolved to an address for the capture filter.
+async fn camera_host(app: &AppHandle, camera_id: &str) -> Result<IpAddr, String> {
+    let url = app.state::<crate::AppState>().config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?
+        .cameras.iter().find(|c| c.id == camera_id).map(|c| c.url.clone())
+        .ok_or_else(|| format!("Unknown camera {}", camera_id))?;
+    let host = crate::UrlParts::parse(&url)
+        .map(|p| p.host)
+        .filter(|h| !h.is_empty() && h != "0.0.0.0")
+        .ok_or("This camera has no remote host to capture")?;
+    let addr = tokio::net::lookup_host((host.as_str(), 0)).await
+        .map_err(|e| format!("Can't look up {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("{} has no address", host))?;
+    Ok(addr.ip())
+}
+
+/// Offer a capture (no `confirm`), or start the one offered (with its token)
+/// and return without waiting for it.
+pub async fn capture(app: &AppHandle, camera_id: &str, seconds: u64, confirm: Option<&str>) -> Result<CaptureResult, String> {
+    let seconds = seconds.clamp(1, MAX_SECS);
+    let tool = find_tool().ok_or("No capture tool found; install Wireshark (dumpcap) or tcpdump")?;
+    let host = camera_host(app, camera_id).await?;
+    let interface = capture_interface(host).await?;
+    let filter = format!("host {}", host);
+    let dir = crate::config_dir().join("captures");
+    let path = dir.join(format!("{}-{}.pcap", crate::iso8601_utc(crate::unix_millis() / 1000).replace(':', "-"), camera_id));
+    let state = app.state::<crate::AppState>();
+
+    let started = {
+        let mut capture = state.capture.lock().map_err(|_| "Capture mutex poisoned".to_string())?;
+        if let Some(CaptureResult::Started { camera_id, .. }) = &capture.running {
+            return Err(format!("A capture of camera {} is already running", camera_id));
+        }
+        let confirmed = capture.pending.as_ref().is_some_and(|p| {
+            Some(p.token.as_str()) == confirm && p.camera_id == camera_id && p.seconds == seconds && p.offered.elapsed() < CONFIRM_TTL
+        });
+        if !confirmed {
+            if confirm.is_some() {
+                capture.pending = None;
+                return Err("Confirmation token is invalid or expired; request the capture again".to_string());
+            }
+            let token = format!("{:016x}", rand::random::<u64>());
+            capture.pending = Some(Pending { token: token.clone(), camera_id: camera_id.to_string(), seconds, offered: Instant::now() });
+            return Ok(CaptureResult::Confirm {
+                token,
+                camera_id: camera_id.to_string(),
+                message: format!(
+                    "This records all traffic to and from {} on {} for {}s (up to {} MB), which can include passwords sent in the clear. Confirm within {}s to start",
+                    host, interface, seconds, MAX_BYTES / 1024 / 1024, CONFIRM_TTL.as_secs()
+                ),
+                host: host.to_string(),
+                filter,
+                seconds,
+                max_mb: MAX_BYTES / 1024 / 1024,
+                tool: tool.display().to_string(),
+                interface,
+            });
+        }
+        capture.pending = None;
+        let started = CaptureResult::Started {
+            camera_id: camera_id.to_string(),
+            path: path.to_string_lossy().to_string(),
+            interface: interface.clone(),
+            seconds,
+            until_ms: crate::unix_millis() + seconds * 1000,
+        };
+        capture.running = Some(started.clone());
+        started
+    };
+
+    let app = app.clone();
+    let camera_id = camera_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let result = match run(&tool, &camera_id, &interface, &filter, seconds, &dir, &path).await {
+            Ok(done) => done,
+            Err(error) => CaptureResult::Failed { camera_id, error },
+        };
+        if let Ok(mut capture) = app.state::<crate::AppState>().capture.lock() {
+            capture.running = None;
+            capture.last = Some(result);
+        }
+    });
+    Ok(started)
+}
+
+/// The capture in progress, or else how the last one ended.
+pub fn status(app: &AppHandle) -> Option<CaptureResult> {
+    let capture = app.state::<crate::AppState>().capture.lock().ok()?;
+    capture.running.clone().or_else(|| capture.last.clone())
+}
+
+async fn run(tool: &std::path::Path, camera_id: &str, interface: &str, filter: &str, seconds: u64, dir: &std::path::Path, path: &std::path::Path) -> Result<CaptureResult, String> {
+    tokio::fs::create_dir_all(dir).await.map_err(|e| e.to_string())?;
+
+    let mut cmd = crate::hidden_command(tool);
+    cmd.args(tool_args(tool, interface, filter, seconds, path))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to start {}: {}", tool.display(), e))?;
+    info!("Capturing '{}' on {} for camera {} to {}", filter, interface, camera_id, path.display());
+    let started = Instant::now();
+    let deadline = Duration::from_secs(seconds);
+    let mut truncated = false;
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if let Ok(Some(status)) = child.try_wait() {
+            // dumpcap stops itself at its limits; a failed exit means it couldn't capture
+            if !status.success() {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = child.stderr.take() {
+                    let _ = pipe.read_to_string(&mut stderr).await;
+                }
+                let detail = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("no output").trim().to_string();
+                warn!("Capture for camera {} failed: {}", camera_id, detail);
+                return Err(format!("{} exited: {}. Capturing usually needs admin rights or Wireshark's capture permissions", tool.display(), detail));
+            }
+            break;
+        }
+        let bytes = tokio::fs::metadata(path).await.map(|m| m.len()).unwrap_or(0);
+        if bytes >= MAX_BYTES {
+            truncated = true;
+            break;
+        }
+        if started.elapsed() >= deadline {
+            break;
+        }
+    }
+    let _ = child.kill().await;
+
+    let bytes = tokio::fs::metadata(path).await.map(|m| m.len()).unwrap_or(0);
+    truncated |= bytes >= MAX_BYTES;
+    info!("Capture for camera {} done: {} bytes{}", camera_id, bytes, if truncated { " (size limit)" } else { "" });
+    Ok(CaptureResult::Done {
+        camera_id: camera_id.to_string(),
+        path: path.to_string_lossy().to_string(),
+        bytes,
+        seconds: started.elapsed().as_secs(),
+        truncated,
+    })
+}
@@ -0,0 +1,223 @@
+//! Bitfocus Companion satellite client. StageView registers itself as a
+//! surface with one key per camera plus a trailing "grid" key: pressing a key
+//! in Companion solos that camera, and each camera's status is published as a
+//! surface variable (`cam_<n>_status`) for use on physical Stream Deck buttons.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+/// Seconds between reconnect attempts, and between status/keepalive ticks.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const TICK: Duration = Duration::from_secs(1);
+
+/// Satellite API version that introduced surface variables.
+const VARIABLES_API: (u32, u32) = (1, 7);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CompanionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_device_id")]
+    pub device_id: String, // Serial shown in Companion's surface list
+}
+
+fn default_port() -> u16 { 16622 }
+fn default_device_id() -> String { "stageview".to_string() }
+
+impl Default for CompanionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: default_port(),
+            device_id: default_device_id(),
+        }
+    }
+}
+
+/// Split a protocol line into its command and `KEY=value` parameters.
+/// Values may be double-quoted; a bare key is treated as `true`.
+fn parse_line(line: &str) -> (&str, HashMap<String, String>) {
+    let line = line.trim();
+    let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let mut params = HashMap::new();
+    let mut chars = rest.chars().peekable();
+    loop {
+        while chars.next_if(|c| *c == ' ').is_some() {}
+        let key: String = std::iter::from_fn(|| chars.next_if(|c| *c != '=' && *c != ' ')).collect();
+        if key.is_empty() {
+            break;
+        }
+        if chars.next_if_eq(&'=').is_none() {
+            params.insert(key, "true".to_string());
+            continue;
+        }
+        let value: String = if chars.next_if_eq(&'"').is_some() {
+            let v = std::iter::from_fn(|| chars.next_if(|c| *c != '"')).collect();
+            chars.next();
+            v
+        } else {
+            std::iter::from_fn(|| chars.next_if(|c| *c != ' ')).collect()
+        };
+        params.insert(key, value);
+    }
+    (command, params)
+}
+
+/// "1.7.0" → (1, 7)
+fn parse_api_version(version: &str) -> (u32, u32) {
+    let mut parts = version.split('.').map(|p| p.parse().unwrap_or(0));
+    (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+}
+
+fn b64(s: &str) -> String {
+    base64::engine::general_purpose::STANDARD.encode(s)
+}
+
+fn add_device_line(config: &CompanionConfig, camera_names: &[String], variables: bool) -> String {
+    let keys = camera_names.len() + 1;
+    let mut line = format!(
+        "ADD-DEVICE DEVICEID={} PRODUCT_NAME=\"StageView\" KEYS_TOTAL={} KEYS_PER_ROW={} BITMAPS=0 COLORS=hex TEXT=true",
+        config.device_id,
+        keys,
+        keys.min(8),
+    );
+    if variables {
+        let defs: Vec<serde_json::Value> = camera_names.iter().enumerate().map(|(i, name)| {
+            serde_json::json!({"id": format!("cam_{}_status", i + 1), "type": "output", "name": format!("{} status", name)})
+        }).collect();
+        line.push_str(&format!(" VARIABLES={}", b64(&serde_json::Value::Array(defs).to_string())));
+    }
+    line.push('\n');
+    line
+}
+
+/// Connect to Companion and keep the surface registered, reconnecting forever.
+pub async fn run(app: AppHandle, config: CompanionConfig) {
+    if config.host.is_empty() {
+        warn!("Companion: enabled but no host configured");
+        return;
+    }
+    loop {
+        match TcpStream::connect((config.host.as_str(), config.port)).await {
+            Ok(stream) => {
+                info!("Companion: connected to {}:{}", config.host, config.port);
+                if let Err(e) = run_session(&app, &config, stream).await {
+                    warn!("Companion: session ended: {}", e);
+                }
+            }
+            Err(e) => debug!("Companion: connect to {}:{} failed: {}", config.host, config.port, e),
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn run_session(app: &AppHandle, config: &CompanionConfig, stream: TcpStream) -> Result<(), String> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut ticker = tokio::time::interval(TICK);
+
+    let mut registered = false;
+    let mut registered_cameras: Vec<String> = Vec::new(); // camera ids the surface was added with
+    let mut variables = false;
+    let mut published: HashMap<String, String> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let line = line.map_err(|e| e.to_string())?.ok_or("connection closed")?;
+                let (command, params) = parse_line(&line);
+                match command {
+                    "BEGIN" => {
+                        let api = params.get("ApiVersion").map(|v| parse_api_version(v)).unwrap_or((1, 0));
+                        variables = api >= VARIABLES_API;
+                        let cameras = current_cameras(app);
+                        let names: Vec<String> = cameras.iter().map(|(_, name)| name.clone()).collect();
+                        writer.write_all(add_device_line(config, &names, variables).as_bytes()).await.map_err(|e| e.to_string())?;
+                        registered_cameras = cameras.into_iter().map(|(id, _)| id).collect();
+                        registered = true;
+                        published.clear();
+                    }
+                    "PING" => {
+                        let reply = format!("PONG {}\n", line.trim().trim_start_matches("PING").trim());
+                        writer.write_all(reply.as_bytes()).await.map_err(|e| e.to_string())?;
+                    }
+                    "ADD-DEVICE" if params.contains_key("ERROR") => {
+                        return Err(format!("surface rejected: {}", params.get("MESSAGE").map(String::as_str).unwrap_or("unknown error")));
+                    }
+                    // KEY-STATE only redraws a key (it arrives whenever its look changes); act on presses
+                    "KEY-PRESS" if params.get("PRESSED").is_some_and(|v| v == "true" || v == "1") => {
+                        let Some(key) = params.get("KEY").and_then(|k| k.parse::<usize>().ok()) else { continue };
+                        let (command, index) = if key < registered_cameras.len() {
+                            ("solo", Some(key + 1))
+                        } else {
+                            ("grid", None)
+                        };
+                        crate::emit_remote_command(app, command, index);
+                    }
+                    _ => {}
+                }
+            }
+            _ = ticker.tick() => {
+                if !registered {
+                    continue; // Waiting for BEGIN
+                }
+                let cameras = current_cameras(app);
+                if cameras.len() != registered_cameras.len() {
+                    // Key count changed: re-register the surface with the new layout
+                    let names: Vec<String> = cameras.iter().map(|(_, name)| name.clone()).collect();
+                    let remove = format!("REMOVE-DEVICE DEVICEID={}\n", config.device_id);
+                    writer.write_all(remove.as_bytes()).await.map_err(|e| e.to_string())?;
+                    writer.write_all(add_device_line(config, &names, variables).as_bytes()).await.map_err(|e| e.to_string())?;
+                    registered_cameras = cameras.iter().map(|(id, _)| id.clone()).collect();
+                    published.clear();
+                }
+                if variables {
+                    for update in status_updates(app, config, &cameras, &mut published) {
+                        writer.write_all(update.as_bytes()).await.map_err(|e| e.to_string())?;
+                    }
+                }
+                writer.write_all(b"PING\n").await.map_err(|e| e.to_string())?;
+            }
+        }
+    }
+}
+
+/// (id, name) of each configured camera, in grid order.
+fn current_cameras(app: &AppHandle) -> Vec<(String, String)> {
+    app.state::<crate::AppState>().config.lock()
+        .map(|c| c.cameras.iter().map(|cam| (cam.id.clone(), cam.name.clone())).collect())
+        .unwrap_or_default()
+}
+
+/// SET-VARIABLE-VALUE lines for cameras whose status changed since last published.
+fn status_updates(app: &AppHandle, config: &CompanionConfig, cameras: &[(String, String)], published: &mut HashMap<String, String>) -> Vec<String> {
+    let statuses = app.state::<crate::AppState>().camera_statuses.lock()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    let mut updates = Vec::new();
+    for (i, (id, _)) in cameras.iter().enumerate() {
+        let status = statuses.get(id).cloned().unwrap_or_else(|| "offline".to_string());
+        if published.get(id) == Some(&status) {
+            continue;
+        }
+        updates.push(format!(
+            "SET-VARIABLE-VALUE DEVICEID={} VARIABLE=cam_{}_status VALUE={}\n",
+            config.device_id,
+            i + 1,
+            b64(&status),
+        ));
+        published.insert(id.clone(), status);
+    }
+    updates
+}
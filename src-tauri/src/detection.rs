@@ -0,0 +1,210 @@
+//! Detection events from Frigate or Home Assistant, shown on the wall. Frigate
+//! publishes object events over MQTT (`frigate/events`); Home Assistant
+//! automations can publish `{"camera": ..., "label": ...}` to a topic or POST
+//! the same to `/api/detection`. Each event is mapped to a camera and emitted
+//! as `detection`, so its tile flashes; with `auto_solo` the camera is also
+//! soloed, e.g. the stage door camera when someone badges in.
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, warn};
+
+/// Seconds between MQTT reconnect attempts.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MqttSource {
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_topics")]
+    pub topics: Vec<String>, // MQTT wildcards allowed, e.g. "home/doors/+"
+}
+
+fn default_mqtt_port() -> u16 { 1883 }
+fn default_topics() -> Vec<String> { vec!["frigate/events".to_string()] }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DetectionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub mqtt: Option<MqttSource>, // None = only `/api/detection` reports
+    /// Source camera name (Frigate camera, HA entity) → StageView camera id or
+    /// name. Unmapped names match a camera id, or a name ignoring case and
+    /// punctuation ("front_door" matches "Front Door").
+    #[serde(default)]
+    pub map: BTreeMap<String, String>,
+    #[serde(default = "default_labels")]
+    pub labels: Vec<String>, // Object labels that count, from any source; empty = all
+    #[serde(default)]
+    pub auto_solo: bool,
+    #[serde(default)]
+    pub auto_solo_secs: u64, // Back to the grid after this long, unless the view changed; 0 = stay
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64, // Further events for the same camera are ignored this long
+}
+
+fn default_labels() -> Vec<String> { vec!["person".to_string()] }
+fn default_cooldown_secs() -> u64 { 10 }
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mqtt: None,
+            map: BTreeMap::new(),
+            labels: default_labels(),
+            auto_solo: false,
+            auto_solo_secs: 0,
+            cooldown_secs: default_cooldown_secs(),
+        }
+    }
+}
+
+/// A detection as reported by a source, before it is mapped to a camera.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Detection {
+    pub camera: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub score: Option<f64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DetectionEvent {
+    pub camera_id: String,
+    pub index: usize, // 1-based
+    pub name: String,
+    pub label: Option<String>,
+    pub score: Option<f64>,
+    pub source: &'static str, // "mqtt" or "http"
+}
+
+/// "Front Door" and "front_door" both become "frontdoor".
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// 1-based index and id of the camera a source name refers to.
+fn resolve_camera(config: &crate::AppConfig, source_name: &str) -> Option<(usize, String, String)> {
+    let target = config.detections.map.get(source_name).map(String::as_str).unwrap_or(source_name);
+    let wanted = normalize(target);
+    config.cameras.iter()
+        .position(|c| c.id == target)
+        .or_else(|| config.cameras.iter().position(|c| normalize(&c.name) == wanted))
+        .map(|i| (i + 1, config.cameras[i].id.clone(), config.cameras[i].name.clone()))
+}
+
+/// Map a detection to its camera, emit `detection`, and solo the camera if
+/// configured. Returns the event, or None if its label isn't in `labels`, it
+/// matched no camera, or the camera is cooling down.
+pub fn report(app: &AppHandle, detection: Detection, source: &'static str) -> Option<DetectionEvent> {
+    let state = app.state::<crate::AppState>();
+    let (config, resolved) = {
+        let config = state.config.lock().ok()?;
+        let resolved = resolve_camera(&config, &detection.camera);
+        (config.detections.clone(), resolved)
+    };
+    if detection.label.as_ref().is_some_and(|label| !config.labels.is_empty() && !config.labels.contains(label)) {
+        debug!("Detection label {:?} is not in labels; ignored", detection.label);
+        return None;
+    }
+    let Some((index, camera_id, name)) = resolved else {
+        debug!("Detection for {:?} matches no camera", detection.camera);
+        return None;
+    };
+    if let Ok(mut last) = state.detection_last.lock() {
+        let cooldown = Duration::from_secs(config.cooldown_secs);
+        if last.get(&camera_id).is_some_and(|t| t.elapsed() < cooldown) {
+            return None;
+        }
+        last.insert(camera_id.clone(), Instant::now());
+    }
+
+    let event = DetectionEvent { camera_id: camera_id.clone(), index, name, label: detection.label, score: detection.score, source };
+    info!("Detection on camera {} ({}): {}", index, event.name, event.label.as_deref().unwrap_or("event"));
+    crate::log_event(app, "detection", Some(&camera_id), format!("{} ({})", event.label.as_deref().unwrap_or("event"), source));
+    let _ = app.emit("detection", event.clone());
+    crate::rules::fire(app, crate::rules::Trigger::Detection, &camera_id);
+
+    if config.auto_solo {
+        crate::emit_remote_command(app, "solo", Some(index));
+        // Each trigger restarts the revert timer; earlier timers see a newer token and stand down
+        let token = state.detection_solo_token.fetch_add(1, Ordering::Relaxed) + 1;
+        if config.auto_solo_secs > 0 {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(config.auto_solo_secs)).await;
+                if app.state::<crate::AppState>().detection_solo_token.load(Ordering::Relaxed) != token {
+                    return;
+                }
+                // Only undo our own solo; an operator may have moved on since
+                if crate::view_state(&app).camera_id.as_deref() == Some(camera_id.as_str()) {
+                    crate::emit_remote_command(&app, "grid", None);
+                }
+            });
+        }
+    }
+    Some(event)
+}
+
+/// A Frigate event (`{"type": "new", "after": {"camera", "label", "score"}}`)
+/// or a plain `{"camera", "label"}` payload. Frigate updates and ends are
+/// skipped so each object counts once.
+fn parse_payload(payload: &[u8]) -> Option<Detection> {
+    let value: serde_json::Value = serde_json::from_slice(payload).ok()?;
+    if let Some(after) = value.get("after") {
+        if value.get("type").and_then(|t| t.as_str()) != Some("new") {
+            return None;
+        }
+        return Some(Detection {
+            camera: after.get("camera")?.as_str()?.to_string(),
+            label: after.get("label").and_then(|l| l.as_str()).map(str::to_string),
+            score: after.get("top_score").or(after.get("score")).and_then(|s| s.as_f64()),
+        });
+    }
+    serde_json::from_value(value).ok()
+}
+
+/// Subscribe to the configured topics and report events until the app exits.
+pub async fn run_mqtt(app: AppHandle, source: MqttSource) {
+    let client_id = format!("stageview-{:08x}", rand::random::<u32>());
+    let mut options = MqttOptions::new(client_id, source.host.clone(), source.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some(user) = &source.username {
+        options.set_credentials(user.clone(), source.password.clone().unwrap_or_default());
+    }
+    let (client, mut events) = AsyncClient::new(options, 16);
+    loop {
+        match events.poll().await {
+            // Subscriptions don't survive a reconnect with a clean session, so renew them on every ConnAck
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("Detections: connected to MQTT {}:{}", source.host, source.port);
+                for topic in &source.topics {
+                    if let Err(e) = client.try_subscribe(topic.clone(), QoS::AtMostOnce) {
+                        warn!("Detections: subscribing to {} failed: {}", topic, e);
+                    }
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let Some(detection) = parse_payload(&publish.payload) else { continue };
+                report(&app, detection, "mqtt");
+            }
+            Ok(_) => {}
+            Err(e) => {
+                debug!("Detections: MQTT {}:{} error: {}", source.host, source.port, e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
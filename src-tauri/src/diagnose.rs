@@ -0,0 +1,301 @@
+//! Guided network diagnostics for one camera. `diagnose_camera` walks the
+//! chain from the wall to the camera — name lookup, ping, TCP port or
+//! multicast group, RTSP DESCRIBE, and a short FFmpeg probe — and reports
+//! each step, so "it says reconnecting" comes with the first link that broke.
+//! The checks open their own connections; the live pipeline is left alone.
+
+use serde::Serialize;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncBufReadExt;
+use tracing::info;
+
+/// Per-step limit for lookups, ping, and connects.
+const STEP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long to wait for a multicast or UDP packet.
+const MULTICAST_WAIT: Duration = Duration::from_secs(5);
+
+/// How long FFmpeg gets to open the source and find a video stream.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Pass,
+    Warn, // Suspicious, but not necessarily the break (e.g. ICMP blocked)
+    Fail,
+    Skipped, // Not applicable to this source
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiagnosticStep {
+    pub step: &'static str, // "stream", "file", "resolve", "ping", "port", "multicast", "rtsp", "ffmpeg"
+    pub status: StepStatus,
+    pub detail: String,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiagnosticReport {
+    pub camera_id: String,
+    pub url: String, // Without credentials
+    pub steps: Vec<DiagnosticStep>,
+    pub broken_at: Option<&'static str>, // First failed step
+    pub summary: String,
+}
+
+struct Steps(Vec<DiagnosticStep>);
+
+impl Steps {
+    fn push(&mut self, step: &'static str, started: Instant, status: StepStatus, detail: impl Into<String>) -> StepStatus {
+        self.0.push(DiagnosticStep { step, status, detail: detail.into(), elapsed_ms: started.elapsed().as_millis() as u64 });
+        status
+    }
+}
+
+/// Default port for a TCP-based scheme, or None for UDP-based ones.
+fn tcp_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "rtsp" => Some(554),
+        "rtsps" => Some(322),
+        "http" => Some(80),
+        "https" => Some(443),
+        "rtmp" => Some(1935),
+        _ => None,
+    }
+}
+
+/// One ICMP echo through the system `ping`, which needs no raw-socket rights.
+/// Takes the resolved address, so nothing from the camera URL reaches the
+/// command line.
+async fn ping(addr: std::net::IpAddr) -> Result<(), String> {
+    let addr = addr.to_string();
+    let mut cmd = crate::hidden_command("ping");
+    if cfg!(windows) {
+        cmd.args(["-n", "1", "-w", "2000", &addr]);
+    } else {
+        cmd.args(["-c", "1", "-W", "2", "--", &addr]);
+    }
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).kill_on_drop(true);
+
+    let status = tokio::time::timeout(STEP_TIMEOUT, cmd.status()).await
+        .map_err(|_| "no reply".to_string())?
+        .map_err(|e| format!("couldn't run ping: {}", e))?;
+    if status.success() { Ok(()) } else { Err("no reply".to_string()) }
+}
+
+/// Join the group (or listen on the port, for unicast UDP) and wait for one packet.
+async fn multicast(host: &str, port: u16) -> (StepStatus, String) {
+    let socket = match tokio::net::UdpSocket::bind(("0.0.0.0", port)).await {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            return (StepStatus::Skipped, format!("Port {} is held by the running stream; see the stream step", port));
+        }
+        Err(e) => return (StepStatus::Fail, format!("Can't listen on UDP port {}: {}", port, e)),
+    };
+    let group = host.parse::<std::net::Ipv4Addr>().ok().filter(|ip| ip.is_multicast());
+    if let Some(group) = group {
+        if let Err(e) = socket.join_multicast_v4(group, std::net::Ipv4Addr::UNSPECIFIED) {
+            return (StepStatus::Fail, format!("Joining {} failed: {}", group, e));
+        }
+    }
+    let mut buf = [0u8; 2048];
+    match tokio::time::timeout(MULTICAST_WAIT, socket.recv_from(&mut buf)).await {
+        Ok(Ok((n, from))) => (StepStatus::Pass, format!("Received {} bytes from {}", n, from)),
+        Ok(Err(e)) => (StepStatus::Fail, format!("Receive failed: {}", e)),
+        Err(_) => (StepStatus::Fail, match group {
+            Some(group) => format!(
+                "Joined {} but no packets in {}s. Check IGMP snooping/querier on the switch, the VLAN, and the encoder's destination",
+                group, MULTICAST_WAIT.as_secs()
+            ),
+            None => format!("No packets on UDP port {} in {}s. Check the sender's destination and the firewall", port, MULTICAST_WAIT.as_secs()),
+        }),
+    }
+}
+
+/// Run FFmpeg just long enough to find a video stream, returning its
+/// description (codec, resolution) or FFmpeg's last error line.
+async fn probe(ffmpeg_path: &std::path::Path, input_args: Vec<String>) -> Result<String, String> {
+    let mut cmd = crate::hidden_command(ffmpeg_path);
+    cmd.args(["-hide_banner", "-loglevel", "info"])
+        .args(input_args)
+        .args(["-map", "0:v:0", "-c", "copy", "-frames:v", "1", "-f", "null", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+    let stderr = child.stderr.take().ok_or("FFmpeg stderr unavailable")?;
+    let read = async {
+        let mut lines = tokio::io::BufReader::new(stderr).lines();
+        let mut last = String::new();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let line = line.trim();
+            if let Some((_, video)) = line.strip_prefix("Stream #0").and_then(|l| l.split_once("Video: ")) {
+                return Ok(video.to_string());
+            }
+            if !line.is_empty() {
+                last = line.to_string();
+            }
+        }
+        Err(if last.is_empty() { "FFmpeg exited without finding a video stream".to_string() } else { last })
+    };
+    let result = tokio::time::timeout(PROBE_TIMEOUT, read).await
+        .unwrap_or_else(|_| Err(format!("No video stream found within {}s", PROBE_TIMEOUT.as_secs())));
+    let _ = child.kill().await;
+    result
+}
+
+/// Diagnose a camera step by step. Steps after a failure that makes them
+/// pointless are skipped.
+pub async fn diagnose(app: &AppHandle, camera_id: &str) -> Result<DiagnosticReport, String> {
+    let state = app.state::<crate::AppState>();
+    let camera = state.config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?
+        .cameras.iter().find(|c| c.id == camera_id).cloned()
+        .ok_or_else(|| format!("Unknown camera {}", camera_id))?;
+    if crate::composite_sources(&camera.url).is_some() || camera.url.starts_with(crate::DATA_TILE_PREFIX) {
+        return Err("Diagnostics need a single-source camera; add each composite source as its own camera to check it".to_string());
+    }
+    info!("Diagnosing camera {}", camera_id);
+    let parts = crate::UrlParts::parse(&camera.url);
+    let mut steps = Steps(Vec::new());
+
+    // What the live pipeline sees right now, for context
+    let started = Instant::now();
+    let status = state.camera_statuses.lock().ok().and_then(|s| s.get(camera_id).cloned());
+    let fps = state.stream_health.lock().ok().and_then(|h| h.get(camera_id).map(|h| h.fps));
+    match (status.as_deref(), fps) {
+        (_, _) if !camera.enabled => steps.push("stream", started, StepStatus::Skipped, "Camera is disabled"),
+        (Some("online"), Some(fps)) => steps.push("stream", started, StepStatus::Pass, format!("Online at {:.1} fps", fps)),
+        (Some(status), _) => steps.push("stream", started, StepStatus::Warn, format!("Live stream is {}", status)),
+        (None, _) => steps.push("stream", started, StepStatus::Warn, "Live stream hasn't reported yet"),
+    };
+
+    let file = crate::file_source(&camera.url);
+    if let Some(path) = &file {
+        let started = Instant::now();
+        match tokio::fs::metadata(path).await {
+            Ok(meta) if meta.is_file() => steps.push("file", started, StepStatus::Pass, format!("{} ({} MB)", path, meta.len() / 1_000_000)),
+            Ok(_) => steps.push("file", started, StepStatus::Fail, format!("{} is not a file", path)),
+            Err(e) => steps.push("file", started, StepStatus::Fail, format!("Can't open {}: {}", path, e)),
+        };
+    }
+
+    // Files, devices, screens, generated, and NDI sources have no host to reach; only the probe applies
+    let local = file.is_some() || camera.url.starts_with(crate::DEVICE_PREFIX) || camera.url.starts_with(crate::SCREEN_PREFIX)
+        || crate::is_test_pattern(&camera.url);
+    let network = parts.as_ref().filter(|_| !local && crate::ndi_source(&camera.url).is_none());
+    if let Some(parts) = network {
+        let scheme = parts.scheme.to_ascii_lowercase();
+        let host = parts.host.clone();
+        let udp = matches!(scheme.as_str(), "udp" | "rtp");
+        let listening = udp && (host.is_empty() || host == "0.0.0.0");
+
+        let started = Instant::now();
+        let mut address = None;
+        let resolved = if listening {
+            steps.push("resolve", started, StepStatus::Skipped, "Listening for incoming packets; no host to look up")
+        } else {
+            match tokio::time::timeout(STEP_TIMEOUT, tokio::net::lookup_host((host.as_str(), 0))).await {
+                Ok(Ok(mut addrs)) => match addrs.next() {
+                    Some(addr) => {
+                        address = Some(addr.ip());
+                        steps.push("resolve", started, StepStatus::Pass, format!("{} is {}", host, addr.ip()))
+                    }
+                    None => steps.push("resolve", started, StepStatus::Fail, format!("{} has no address", host)),
+                },
+                Ok(Err(e)) => steps.push("resolve", started, StepStatus::Fail, format!("Can't look up {}: {}", host, e)),
+                Err(_) => steps.push("resolve", started, StepStatus::Fail, format!("Looking up {} timed out; check DNS", host)),
+            }
+        };
+
+        if let Some(addr) = address.filter(|_| !udp) {
+            let started = Instant::now();
+            match ping(addr).await {
+                Ok(()) => steps.push("ping", started, StepStatus::Pass, format!("{} answers ping", host)),
+                // Plenty of cameras and firewalls drop ICMP, so this alone isn't the break
+                Err(e) => steps.push("ping", started, StepStatus::Warn, format!("{}: {}. If the port check also fails, check power, cabling, VLAN, and IP address", host, e)),
+            };
+        }
+
+        if let Some(default_port) = tcp_port(&scheme).filter(|_| resolved == StepStatus::Pass) {
+            let port = parts.port.unwrap_or(default_port);
+            let started = Instant::now();
+            let status = match tokio::time::timeout(STEP_TIMEOUT, tokio::net::TcpStream::connect((host.as_str(), port))).await {
+                Ok(Ok(_)) => steps.push("port", started, StepStatus::Pass, format!("TCP {} is open", port)),
+                Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => steps.push("port", started, StepStatus::Fail,
+                    format!("TCP {} refused: the device is up but nothing listens there. Check the port and that the stream service is enabled", port)),
+                Ok(Err(e)) => steps.push("port", started, StepStatus::Fail, format!("TCP {}: {}", port, e)),
+                Err(_) => steps.push("port", started, StepStatus::Fail,
+                    format!("TCP {} timed out: a firewall or routing problem between here and the camera", port)),
+            };
+
+            if scheme == "rtsp" && status == StepStatus::Pass {
+                let started = Instant::now();
+                match crate::rtsp::describe(&crate::credentials::connect_url(&camera), STEP_TIMEOUT).await {
+                    Ok(r) if r.status == 200 => {
+                        let video = r.body.lines().filter(|l| l.starts_with("m=video")).count();
+                        if video == 0 {
+                            steps.push("rtsp", started, StepStatus::Fail, "DESCRIBE succeeded but the stream has no video track")
+                        } else {
+                            steps.push("rtsp", started, StepStatus::Pass, format!("DESCRIBE OK, {} video track(s)", video))
+                        }
+                    }
+                    Ok(r) if r.status == 401 => steps.push("rtsp", started, StepStatus::Fail, "401 Unauthorized: wrong username or password in the URL"),
+                    Ok(r) if r.status == 404 => steps.push("rtsp", started, StepStatus::Fail, "404 Not Found: wrong stream path; check the camera's RTSP URL format"),
+                    Ok(r) => steps.push("rtsp", started, StepStatus::Fail, format!("DESCRIBE returned {}", r.status)),
+                    Err(e) => steps.push("rtsp", started, StepStatus::Fail, e),
+                };
+            }
+        } else if udp {
+            let started = Instant::now();
+            match parts.port {
+                Some(port) => {
+                    let (status, detail) = multicast(&host, port).await;
+                    steps.push("multicast", started, status, detail)
+                }
+                None => steps.push("multicast", started, StepStatus::Fail, "The URL has no port"),
+            };
+        } else if resolved == StepStatus::Pass {
+            let detail = if scheme == "srt" {
+                "SRT runs over UDP; the FFmpeg probe checks it".to_string()
+            } else {
+                format!("No port check for {}:// sources", scheme)
+            };
+            steps.push("port", Instant::now(), StepStatus::Skipped, detail);
+        }
+    }
+
+    // FFmpeg opens the source the way the pipeline does, unless something above already failed
+    let started = Instant::now();
+    if steps.0.iter().any(|s| s.status == StepStatus::Fail) {
+        steps.push("ffmpeg", started, StepStatus::Skipped, "Skipped until the earlier failure is fixed");
+    } else {
+        let input = crate::srt_input_url(&crate::credentials::connect_url(&camera), camera.srt.as_ref());
+        let format = camera.learned_input.as_ref().and_then(|l| l.format.clone());
+        let probe_settings = camera.probe.or(camera.learned_input.as_ref().and_then(|l| l.probe));
+        match probe(&state.ffmpeg_path, crate::build_input_args(&input, probe_settings, format.as_deref(), camera.audio_enabled)).await {
+            Ok(video) => steps.push("ffmpeg", started, StepStatus::Pass, format!("Video: {}", video)),
+            Err(e) => steps.push("ffmpeg", started, StepStatus::Fail, e),
+        };
+    }
+
+    let broken = steps.0.iter().find(|s| s.status == StepStatus::Fail);
+    let broken_at = broken.map(|s| s.step);
+    let summary = match broken {
+        Some(step) => format!("Breaks at {}: {}", step.step, step.detail),
+        None => "Every check passed. If the tile still reconnects, look at the stream's exit history and health".to_string(),
+    };
+    info!("Diagnosis for camera {}: {}", camera_id, summary);
+    Ok(DiagnosticReport {
+        camera_id: camera_id.to_string(),
+        url: parts.map(|p| p.without_credentials()).unwrap_or(camera.url),
+        steps: steps.0,
+        broken_at,
+        summary,
+    })
+}
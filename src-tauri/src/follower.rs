@@ -0,0 +1,152 @@
+//! Follower wall: a second StageView (e.g. a lobby wall) that takes its
+//! camera list and streams from a leader instead of from the cameras. Each
+//! of the leader's cameras becomes a camera here that pulls the leader's
+//! `/camera/:id/stream`, so the cameras serve only the leader. Layout, paging,
+//! solo, and everything else about the wall stay this machine's own. Cameras
+//! created this way are tagged with `leader_camera`; they are added, renamed,
+//! enabled, disabled, and removed as the leader's list changes, and cameras
+//! configured by hand are never touched. The leader's token goes on each
+//! camera as its login, so it's kept in the keychain rather than in the URL.
+
+use crate::managed::SyncReport;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// Longest a camera list request may take.
+const API_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// User name of the login follower cameras present to the leader, which
+/// checks only the password (its `api_token`).
+const LOGIN_NAME: &str = "follower";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FollowerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub leader_url: String, // The leader's API, e.g. "http://10.0.0.5:8090"
+    #[serde(default)]
+    pub token: Option<String>, // The leader's api_token, if it has one
+    #[serde(default = "default_sync_interval_secs")]
+    pub sync_interval_secs: u64, // 0 = sync once at startup only
+}
+
+fn default_sync_interval_secs() -> u64 { 30 }
+
+impl Default for FollowerConfig {
+    fn default() -> Self {
+        Self { enabled: false, leader_url: String::new(), token: None, sync_interval_secs: default_sync_interval_secs() }
+    }
+}
+
+/// The parts of the leader's `/api/cameras` entries a follower uses.
+#[derive(Deserialize, Clone)]
+struct LeaderCamera {
+    id: String,
+    name: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default = "crate::default_true")]
+    enabled: bool,
+    #[serde(default)]
+    audio_enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct CameraList {
+    cameras: Vec<LeaderCamera>,
+}
+
+fn leader_base(config: &FollowerConfig) -> Result<&str, String> {
+    let base = config.leader_url.trim().trim_end_matches('/');
+    if base.is_empty() {
+        return Err("follower.leader_url is not set".to_string());
+    }
+    Ok(base)
+}
+
+/// The leader's cameras that have a stream to follow, in its order, disabled
+/// ones included so their follower cameras keep their ids and tiles.
+async fn fetch_cameras(config: &FollowerConfig) -> Result<Vec<LeaderCamera>, String> {
+    let base = leader_base(config)?;
+    let client = reqwest::Client::builder()
+        .timeout(API_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.get(format!("{}/api/cameras", base));
+    if let Some(token) = config.token.as_deref().filter(|t| !t.is_empty()) {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|e| format!("Leader {} unreachable: {}", base, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Leader {} answered {}", base, response.status()));
+    }
+    let list: CameraList = response.json().await.map_err(|e| format!("Unexpected reply from {}: {}", base, e))?;
+    // Data tiles have no stream to pull
+    Ok(list.cameras.into_iter().filter(|c| !c.url.starts_with(crate::DATA_TILE_PREFIX)).collect())
+}
+
+/// Where this machine pulls one of the leader's cameras. The token isn't
+/// part of it; the leader takes it as the password of a Basic login.
+fn stream_url(base: &str, camera_id: &str) -> String {
+    format!("{}/camera/{}/stream", base, crate::percent_encode(camera_id))
+}
+
+/// Bring the leader's cameras in `config` in line with `leader`.
+fn reconcile(config: &mut crate::AppConfig, follower: &FollowerConfig, leader: &[LeaderCamera]) -> Result<SyncReport, String> {
+    let base = leader_base(follower)?;
+    let password = follower.token.clone().filter(|t| !t.is_empty());
+    let username = password.as_ref().map(|_| LOGIN_NAME.to_string());
+    crate::managed::reconcile(
+        config,
+        leader,
+        |source| source.id.as_str(),
+        |camera| camera.leader_camera.as_deref(),
+        |camera, source| {
+            let url = stream_url(base, &source.id);
+            if camera.url == url
+                && camera.name == source.name
+                && camera.audio_enabled == source.audio_enabled
+                && camera.enabled == source.enabled
+                && camera.username == username
+                && camera.password == password
+            {
+                return false;
+            }
+            camera.url = url;
+            camera.name = source.name.clone();
+            camera.audio_enabled = source.audio_enabled;
+            camera.enabled = source.enabled;
+            camera.username = username.clone();
+            camera.password = password.clone();
+            true
+        },
+        |source| {
+            let mut camera: crate::Camera = serde_json::from_value(serde_json::json!({
+                "id": crate::new_camera_id(),
+                "name": source.name,
+                "url": stream_url(base, &source.id),
+                "username": username,
+                "enabled": source.enabled,
+                "audio_enabled": source.audio_enabled,
+                "leader_camera": source.id,
+            })).map_err(|e| format!("Invalid camera: {}", e))?;
+            camera.password = password.clone();
+            Ok(camera)
+        },
+    )
+}
+
+/// Fetch the leader's camera list and update the config; only saved (and
+/// only the affected pipelines cycled) when something changed.
+pub async fn sync(app: &AppHandle, config: &FollowerConfig) -> Result<SyncReport, String> {
+    let leader = fetch_cameras(config).await?;
+    crate::managed::apply(app, "Follower", leader.len(), |cfg| reconcile(cfg, config, &leader))
+}
+
+/// Sync at startup, then every `sync_interval_secs`.
+pub async fn run(app: AppHandle, config: FollowerConfig) {
+    let interval_secs = config.sync_interval_secs;
+    crate::managed::run("Follower", interval_secs, || sync(&app, &config)).await;
+}
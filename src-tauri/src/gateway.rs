@@ -0,0 +1,208 @@
+//! Stream gateway integration. When go2rtc or MediaMTX already pulls the
+//! cameras, StageView reads the gateway's stream list from its API and keeps
+//! one camera per stream, pointed at the gateway's RTSP restream. Cameras it
+//! creates are tagged with `gateway_stream`; they are added, re-pointed, and
+//! removed as the gateway's list changes. Renames and other edits to them are
+//! kept, and cameras configured by hand are never touched.
+
+use crate::managed::SyncReport;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Longest a stream list request may take.
+const API_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// RTSP port both gateways restream on by default.
+const DEFAULT_RTSP_PORT: u16 = 8554;
+
+/// MediaMTX pages its path list; ask for large pages to keep requests few.
+const MEDIAMTX_PAGE_SIZE: usize = 500;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GatewayKind {
+    #[default]
+    Go2rtc,
+    Mediamtx,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GatewayConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub kind: GatewayKind,
+    #[serde(default)]
+    pub api_url: String, // e.g. "http://192.168.1.20:1984" (go2rtc) or "http://192.168.1.20:9997" (MediaMTX)
+    #[serde(default)]
+    pub rtsp_url: Option<String>, // Restream base; defaults to rtsp://<api host>:8554
+    #[serde(default)]
+    pub username: Option<String>, // Used for both the API and the RTSP URLs
+    /// Held in memory only, like a camera's: saved to the keychain and never
+    /// serialized (see `credentials`).
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    #[serde(default = "default_sync_interval_secs")]
+    pub sync_interval_secs: u64, // 0 = sync once at startup and on request only
+}
+
+fn default_sync_interval_secs() -> u64 { 60 }
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: GatewayKind::default(),
+            api_url: String::new(),
+            rtsp_url: None,
+            username: None,
+            password: None,
+            sync_interval_secs: default_sync_interval_secs(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MediamtxPathList {
+    #[serde(rename = "pageCount", default)]
+    page_count: usize,
+    #[serde(default)]
+    items: Vec<MediamtxPath>,
+}
+
+#[derive(Deserialize)]
+struct MediamtxPath {
+    name: String,
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(client: &reqwest::Client, config: &GatewayConfig, url: &str) -> Result<T, String> {
+    let mut request = client.get(url);
+    if let Some(user) = &config.username {
+        request = request.basic_auth(user, config.password.as_deref());
+    }
+    let response = request.send().await.map_err(|e| format!("Gateway request to {} failed: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Gateway returned HTTP {} for {}", response.status(), url));
+    }
+    response.json().await.map_err(|e| format!("Unexpected gateway response from {}: {}", url, e))
+}
+
+/// Stream names the gateway serves, sorted.
+async fn fetch_streams(config: &GatewayConfig) -> Result<Vec<String>, String> {
+    let api = config.api_url.trim().trim_end_matches('/');
+    if api.is_empty() {
+        return Err("gateway.api_url is not set".to_string());
+    }
+    let client = reqwest::Client::builder()
+        .timeout(API_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut streams: Vec<String> = match config.kind {
+        // {"<name>": {"producers": [...], "consumers": [...]}, ...}
+        GatewayKind::Go2rtc => get_json::<serde_json::Map<String, serde_json::Value>>(&client, config, &format!("{}/api/streams", api))
+            .await?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect(),
+        GatewayKind::Mediamtx => {
+            let mut names = Vec::new();
+            let mut page = 0;
+            loop {
+                let url = format!("{}/v3/paths/list?page={}&itemsPerPage={}", api, page, MEDIAMTX_PAGE_SIZE);
+                let list: MediamtxPathList = get_json(&client, config, &url).await?;
+                names.extend(list.items.into_iter().map(|p| p.name));
+                page += 1;
+                if page >= list.page_count {
+                    break names;
+                }
+            }
+        }
+    };
+    streams.sort();
+    Ok(streams)
+}
+
+/// `rtsp://host:8554`, from `rtsp_url` or the API URL's host. The login
+/// goes on each camera rather than into its URL.
+fn rtsp_base(config: &GatewayConfig) -> Result<String, String> {
+    let base = match config.rtsp_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        Some(url) => url.trim_end_matches('/').to_string(),
+        None => {
+            let authority = config.api_url.trim()
+                .split_once("://").map(|(_, rest)| rest).unwrap_or(config.api_url.trim())
+                .split('/').next().unwrap_or("");
+            // A login left in the URL (no keychain) isn't part of the host
+            let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+            let host = match authority.strip_prefix('[') {
+                Some(v6) => v6.split(']').next().map(|h| format!("[{}]", h)),
+                None => authority.split(':').next().map(str::to_string),
+            }
+            .filter(|h| !h.is_empty())
+            .ok_or("Can't derive the RTSP address from gateway.api_url; set gateway.rtsp_url")?;
+            format!("rtsp://{}:{}", host, DEFAULT_RTSP_PORT)
+        }
+    };
+    if !base.contains("://") {
+        return Err(format!("Invalid gateway.rtsp_url: {}", base));
+    }
+    Ok(base)
+}
+
+/// Bring the gateway's cameras in `config` in line with `streams`.
+fn reconcile(config: &mut crate::AppConfig, streams: &[String], base: &str, gateway: &GatewayConfig) -> Result<SyncReport, String> {
+    // MediaMTX paths can be nested; keep their slashes and escape the rest
+    let url_for = |stream: &str| format!("{}/{}", base, stream.split('/').map(crate::percent_encode).collect::<Vec<_>>().join("/"));
+    let username = gateway.username.clone().filter(|u| !u.is_empty());
+    let password = username.as_ref().and(gateway.password.clone());
+    crate::managed::reconcile(
+        config,
+        streams,
+        |stream| stream.as_str(),
+        |camera| camera.gateway_stream.as_deref(),
+        |camera, stream| {
+            if camera.url == url_for(stream) && camera.username == username && camera.password == password {
+                return false;
+            }
+            camera.url = url_for(stream);
+            camera.username = username.clone();
+            camera.password = password.clone();
+            true
+        },
+        |stream| {
+            let mut camera: crate::Camera = serde_json::from_value(serde_json::json!({
+                "id": crate::new_camera_id(),
+                "name": stream,
+                "url": url_for(stream),
+                "username": username,
+                "gateway_stream": stream,
+            })).map_err(|e| format!("Invalid camera: {}", e))?;
+            camera.password = password.clone();
+            Ok(camera)
+        },
+    )
+}
+
+/// Fetch the gateway's stream list and update the config; only saved (and
+/// only the affected pipelines cycled) when something changed.
+pub async fn sync(app: &AppHandle, config: &GatewayConfig) -> Result<SyncReport, String> {
+    let streams = fetch_streams(config).await?;
+    // A gateway that has just restarted can list nothing for a moment; don't
+    // take every camera off the wall for it
+    if streams.is_empty() {
+        let managed = app.state::<crate::AppState>().config.lock()
+            .map_err(|_| "Config mutex poisoned - please restart application".to_string())?
+            .cameras.iter().filter(|c| c.gateway_stream.is_some()).count();
+        if managed > 0 {
+            return Err(format!("Gateway listed no streams; keeping its {} cameras. Remove them by hand if the gateway is really empty", managed));
+        }
+    }
+    let base = rtsp_base(config)?;
+    crate::managed::apply(app, "Gateway", streams.len(), |cfg| reconcile(cfg, &streams, &base, config))
+}
+
+/// Sync at startup, then every `sync_interval_secs`.
+pub async fn run(app: AppHandle, config: GatewayConfig) {
+    let interval_secs = config.sync_interval_secs;
+    crate::managed::run("Gateway", interval_secs, || sync(&app, &config)).await;
+}
@@ -0,0 +1,120 @@
+//! Health history on disk, for reports after a show: while a camera streams,
+//! one of its health samples is appended every `health.history_secs` to
+//! `<config dir>/health/<camera_id>/<YYYY-MM-DD>.csv` (UTC days). The files
+//! are already CSV, so an export is the rows of the days asked for, trimmed
+//! to the time range. Gaps are times the camera wasn't streaming. Old days
+//! are removed by the camera's `retention.health_days` (30 by default).
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Column names, the first line of every file and export.
+const HEADER: &str = "time,camera_id,fps,bitrate_kbps,health_score,resolution,codec,uptime_secs,frame_count,keyframe_interval_ms,gop_frames,clock_drift_ms,speed,dropped_frames,duplicated_frames,last_exit";
+
+pub fn camera_dir(camera_id: &str) -> PathBuf {
+    crate::config_dir().join("health").join(camera_id)
+}
+
+/// Quote a field that would otherwise split or end the row.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn row(unix_secs: u64, health: &crate::StreamHealth) -> String {
+    [
+        crate::iso8601_utc(unix_secs),
+        field(&health.camera_id),
+        format!("{:.1}", health.fps),
+        format!("{:.0}", health.bitrate_kbps),
+        health.health_score.to_string(),
+        field(health.resolution.as_deref().unwrap_or("")),
+        field(&health.codec),
+        health.uptime_secs.to_string(),
+        health.frame_count.to_string(),
+        optional(health.keyframe_interval_ms),
+        optional(health.gop_frames),
+        optional(health.clock_drift_ms.map(|ms| format!("{:.1}", ms))),
+        optional(health.speed.map(|s| format!("{:.2}", s))),
+        optional(health.dropped_frames),
+        optional(health.duplicated_frames),
+        field(&optional(health.last_exit.as_ref())),
+    ]
+    .join(",")
+}
+
+/// Append one sample to the camera's file for today.
+pub fn record(health: &crate::StreamHealth) -> Result<(), String> {
+    let now = crate::unix_millis() / 1000;
+    let dir = camera_dir(&health.camera_id);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.csv", crate::civil_date(now)));
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let new = file.metadata().map(|m| m.len() == 0).unwrap_or(false);
+    let text = if new { format!("{}\n{}\n", HEADER, row(now, health)) } else { format!("{}\n", row(now, health)) };
+    file.write_all(text.as_bytes()).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// A bound as the ISO 8601 UTC time rows are compared against. A date on
+/// its own starts the day for `from` and ends it for `to`.
+fn bound(value: &str, end_of_day: bool) -> Result<String, String> {
+    let value = value.trim();
+    // Digits everywhere but the separators of 2026-10-16T18:04:05Z
+    let shaped = |pattern: &str| value.len() == pattern.len()
+        && value.chars().zip(pattern.chars()).all(|(c, p)| if p == '0' { c.is_ascii_digit() } else { c == p });
+    if shaped("0000-00-00") {
+        return Ok(format!("{}T{}Z", value, if end_of_day { "23:59:59" } else { "00:00:00" }));
+    }
+    if shaped("0000-00-00T00:00:00Z") {
+        return Ok(value.to_string());
+    }
+    Err(format!("{:?} is not a date; use YYYY-MM-DD or YYYY-MM-DDTHH:MM:SSZ (UTC)", value))
+}
+
+/// The camera's recorded health between `from` and `to` (inclusive, UTC) as
+/// CSV with a header row. Either bound can be left out.
+pub fn export_csv(camera_id: &str, from: Option<&str>, to: Option<&str>) -> Result<String, String> {
+    let from = from.filter(|f| !f.trim().is_empty()).map(|f| bound(f, false)).transpose()?;
+    let to = to.filter(|t| !t.trim().is_empty()).map(|t| bound(t, true)).transpose()?;
+    if let (Some(from), Some(to)) = (&from, &to) {
+        if from > to {
+            return Err("from is after to".to_string());
+        }
+    }
+    // ISO 8601 UTC times (and their dates) sort as text
+    let after_from = |time: &str| !from.as_deref().is_some_and(|f| time < &f[..time.len().min(f.len())]);
+    let before_to = |time: &str| !to.as_deref().is_some_and(|t| time > &t[..time.len().min(t.len())]);
+
+    let mut days: Vec<(String, PathBuf)> = std::fs::read_dir(camera_dir(camera_id))
+        .map(|entries| entries.flatten().map(|e| e.path()).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| Some((path.file_name()?.to_str()?.strip_suffix(".csv")?.to_string(), path)))
+        .filter(|(day, _)| after_from(day) && before_to(day))
+        .collect();
+    days.sort();
+
+    let mut csv = format!("{}\n", HEADER);
+    for (_, path) in days {
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for line in text.lines().skip(1) {
+            let time = line.split(',').next().unwrap_or("");
+            if !line.is_empty() && after_from(time) && before_to(time) {
+                csv.push_str(line);
+                csv.push('\n');
+            }
+        }
+    }
+    Ok(csv)
+}
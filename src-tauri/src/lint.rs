@@ -0,0 +1,135 @@
+//! Config linting: setups that work but are likely to bite during a show,
+//! such as the same camera pulled twice or a control API anyone on the network
+//! can drive. Warnings are logged at startup, toasted by the frontend, and
+//! served at `/api/lint`; nothing here stops the config from loading.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Tokens shorter than this are flagged as guessable.
+const MIN_TOKEN_LEN: usize = 16;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error, // The setup can't work as written
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LintWarning {
+    pub severity: Severity,
+    pub code: &'static str, // Stable identifier, e.g. "duplicate_url"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_id: Option<String>,
+    pub message: String,
+}
+
+fn warning(severity: Severity, code: &'static str, camera_id: Option<&str>, message: String) -> LintWarning {
+    LintWarning { severity, code, camera_id: camera_id.map(str::to_string), message }
+}
+
+/// Whether an address is reachable only over the internet. Private,
+/// loopback, link-local, and CGNAT (Tailscale) ranges count as local.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified()
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            !(v6.is_loopback() || v6.is_unspecified() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80)
+        }
+    }
+}
+
+/// Check a config. Results are ordered most severe first.
+pub fn lint(config: &crate::AppConfig) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut urls: HashMap<&str, &str> = HashMap::new();
+
+    for camera in &config.cameras {
+        let id = camera.id.as_str();
+        *ids.entry(id).or_default() += 1;
+        if ids[id] == 2 {
+            warnings.push(warning(Severity::Error, "duplicate_id", Some(id),
+                format!("Camera id {:?} is used more than once; only one of them can be addressed", id)));
+        }
+        if !crate::valid_camera_id(id) {
+            warnings.push(warning(Severity::Error, "invalid_id", Some(id), format!(
+                "Camera id {:?} may only contain letters, digits, '-', and '_'; it names the camera's files and folders", id
+            )));
+        }
+        let url = camera.url.trim();
+        if url.is_empty() {
+            warnings.push(warning(Severity::Error, "empty_url", Some(id), format!("{} has no stream URL", camera.name)));
+            continue;
+        }
+        let passphrase = camera.srt.as_ref().and_then(|s| s.passphrase.as_deref()).filter(|p| !p.is_empty());
+        if passphrase.is_some_and(|p| !crate::SRT_PASSPHRASE_LEN.contains(&p.len())) {
+            warnings.push(warning(Severity::Error, "srt_passphrase_length", Some(id), format!(
+                "{} has an SRT passphrase that isn't {}-{} characters; the encoder will refuse the connection",
+                camera.name, crate::SRT_PASSPHRASE_LEN.start(), crate::SRT_PASSPHRASE_LEN.end()
+            )));
+        }
+        if !camera.enabled {
+            continue;
+        }
+        if let Some(first) = urls.insert(url, id) {
+            let key = crate::pipeline_key(camera);
+            let differs = key.is_some()
+                && !config.cameras.iter().find(|c| c.id == first).is_some_and(|c| crate::pipeline_key(c) == key);
+            if !differs {
+                warnings.push(warning(Severity::Warning, "duplicate_url", Some(id), format!(
+                    "{} pulls the same URL as camera {}; the camera serves both streams. Use restream_urls or one tile instead",
+                    camera.name, first
+                )));
+            } else {
+                warnings.push(warning(Severity::Warning, "duplicate_url_settings", Some(id), format!(
+                    "{} pulls the same URL as camera {} with different stream settings (login, sub_url, audio, transcode, delay, SRT, backup, ...), so it opens a second connection instead of sharing that stream",
+                    camera.name, first
+                )));
+            }
+            urls.insert(url, first);
+        }
+        if camera.priority && !camera.backup_url.as_deref().is_some_and(|b| !b.trim().is_empty()) {
+            warnings.push(warning(Severity::Warning, "priority_no_backup", Some(id), format!(
+                "{} is marked priority but has no backup_url; if its source drops, the tile stays dark until it returns",
+                camera.name
+            )));
+        }
+        if camera.sub_url.as_deref().is_some_and(|sub| sub.trim() == url) {
+            warnings.push(warning(Severity::Info, "sub_url_same", Some(id),
+                format!("{} has sub_url set to its main URL, so the grid gets no lighter stream", camera.name)));
+        }
+        let wan = crate::UrlParts::parse(url)
+            .filter(|p| p.scheme.eq_ignore_ascii_case("rtsp"))
+            .and_then(|p| p.host.trim_matches(['[', ']']).parse::<IpAddr>().ok())
+            .is_some_and(is_public);
+        if wan {
+            warnings.push(warning(Severity::Warning, "rtsp_wan", Some(id), format!(
+                "{} pulls RTSP over TCP from a public address: one lost packet stalls the stream, and the login is sent in the clear. Prefer SRT or a gateway near the camera",
+                camera.name
+            )));
+        }
+    }
+
+    match config.api_token.as_deref() {
+        None | Some("") => warnings.push(warning(Severity::Warning, "api_no_token", None, format!(
+            "The API on port {} has no token; anyone on the network can control the wall{}",
+            config.api_port,
+            if config.failover_api.enabled { " (and the failover API)" } else { "" }
+        ))),
+        Some(token) if token.len() < MIN_TOKEN_LEN => warnings.push(warning(Severity::Info, "api_weak_token", None,
+            format!("The API token is shorter than {} characters; generate a new one in Settings", MIN_TOKEN_LEN))),
+        _ => {}
+    }
+
+    warnings.sort_by(|a, b| b.severity.cmp(&a.severity));
+    warnings
+}
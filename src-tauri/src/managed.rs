@@ -0,0 +1,114 @@
+//! Cameras kept in line with a list read from somewhere else: a gateway's
+//! streams (`gateway`) or a leader wall's cameras (`follower`). Each camera
+//! a sync creates is tagged with its source's key; later syncs update the
+//! tagged cameras and remove those whose key is no longer listed. Cameras
+//! configured by hand are never touched.
+
+use serde::Serialize;
+use std::future::Future;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// What a sync changed, by source key.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct SyncReport {
+    pub listed: usize, // Sources on the list (gateway streams, leader cameras)
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl SyncReport {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Bring the cameras tagged by `tag` in line with `sources`. `update` brings
+/// one managed camera in line with its source and says whether it changed;
+/// `create` makes the camera for a new source, tagged with its key.
+pub fn reconcile<S>(
+    config: &mut crate::AppConfig,
+    sources: &[S],
+    key: impl Fn(&S) -> &str,
+    tag: impl Fn(&crate::Camera) -> Option<&str>,
+    update: impl Fn(&mut crate::Camera, &S) -> bool,
+    create: impl Fn(&S) -> Result<crate::Camera, String>,
+) -> Result<SyncReport, String> {
+    let mut report = SyncReport { listed: sources.len(), ..Default::default() };
+
+    let mut removed_ids = Vec::new();
+    for camera in &mut config.cameras {
+        let Some(camera_key) = tag(camera).map(str::to_string) else { continue };
+        match sources.iter().find(|s| key(s) == camera_key) {
+            None => {
+                removed_ids.push(camera.id.clone());
+                report.removed.push(camera_key);
+            }
+            Some(source) => {
+                if update(camera, source) {
+                    report.updated.push(camera_key);
+                }
+            }
+        }
+    }
+    config.cameras.retain(|c| !removed_ids.contains(&c.id));
+    config.tile_order.retain(|t| !removed_ids.contains(t));
+
+    for source in sources {
+        if config.cameras.iter().any(|c| tag(c) == Some(key(source))) {
+            continue;
+        }
+        config.cameras.push(create(source)?);
+        report.added.push(key(source).to_string());
+    }
+    Ok(report)
+}
+
+/// Run `reconcile` against the config; only saved (and only the affected
+/// pipelines cycled) when something changed. `name` labels the log line.
+pub fn apply(
+    app: &AppHandle,
+    name: &str,
+    listed: usize,
+    reconcile: impl Fn(&mut crate::AppConfig) -> Result<SyncReport, String>,
+) -> Result<SyncReport, String> {
+    let mut preview = app.state::<crate::AppState>().config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?
+        .clone();
+    if reconcile(&mut preview)?.is_empty() {
+        return Ok(SyncReport { listed, ..Default::default() });
+    }
+    let (report, _) = crate::edit_config(app, |cfg| reconcile(cfg).map_err(|e| ("500 Internal Server Error", e)))
+        .map_err(|(_, e)| e)?;
+    info!(
+        "{} sync: {} listed, added {}, updated {}, removed {}",
+        name, report.listed, report.added.len(), report.updated.len(), report.removed.len()
+    );
+    Ok(report)
+}
+
+/// Call `sync` at startup, then every `interval_secs` (0 = once). A failure
+/// is logged once, until a sync succeeds again.
+pub async fn run<F, Fut>(name: &str, interval_secs: u64, mut sync: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<SyncReport, String>>,
+{
+    let mut failing = false;
+    loop {
+        match sync().await {
+            Ok(_) => failing = false,
+            Err(e) if !failing => {
+                warn!("{} sync failed: {}", name, e);
+                failing = true;
+            }
+            Err(_) => {} // Already reported; stay quiet until it recovers
+        }
+        if interval_secs == 0 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
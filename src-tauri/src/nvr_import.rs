@@ -0,0 +1,265 @@
+//! Camera lists exported from other systems, turned into StageView cameras.
+//! Hikvision (iVMS-4200) and Dahua (ConfigTool/SmartPSS) device lists are CSV
+//! with one row per device; each channel becomes a camera, with RTSP URLs built
+//! from the vendor's path scheme for the main stream and substream. Frigate's
+//! YAML already has stream URLs: the `record` input is the main stream and the
+//! `detect` input the substream, with go2rtc restreams resolved to their source.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Hikvision,
+    Dahua,
+    Frigate,
+}
+
+impl ImportFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "hikvision" | "hik" => Some(ImportFormat::Hikvision),
+            "dahua" => Some(ImportFormat::Dahua),
+            "frigate" => Some(ImportFormat::Frigate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportedCamera {
+    pub name: String,
+    pub url: String,
+    pub sub_url: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ImportResult {
+    pub cameras: Vec<ImportedCamera>,
+    pub warnings: Vec<String>, // Rows that couldn't be converted, and imported cameras that need attention
+}
+
+/// RTSP port when an export has no RTSP port column (its "Port" is the SDK port).
+const DEFAULT_RTSP_PORT: u16 = 554;
+
+/// go2rtc's RTSP restream port, as referenced from Frigate inputs.
+const GO2RTC_RTSP_PORT: &str = "8554";
+
+pub fn parse(format: ImportFormat, content: &str) -> Result<ImportResult, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    match format {
+        ImportFormat::Hikvision | ImportFormat::Dahua => parse_device_csv(format, content),
+        ImportFormat::Frigate => parse_frigate(content),
+    }
+}
+
+// ── Hikvision / Dahua ────────────────────────────────────────────────────────
+
+/// Split one CSV line; fields may be double-quoted with `""` as an escaped quote.
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.next_if_eq(&'"').is_some() => field.push('"'),
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// "IP/Domain Name" → "ipdomainname", so headers match regardless of punctuation.
+fn normalize_header(header: &str) -> String {
+    header.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_lowercase()
+}
+
+fn stream_urls(format: ImportFormat, host: &str, port: u16, credentials: &str, channel: u32) -> (String, String) {
+    let base = format!("rtsp://{}{}:{}", credentials, host, port);
+    match format {
+        ImportFormat::Dahua => (
+            format!("{}/cam/realmonitor?channel={}&subtype=0", base, channel),
+            format!("{}/cam/realmonitor?channel={}&subtype=1", base, channel),
+        ),
+        _ => (
+            format!("{}/Streaming/Channels/{}01", base, channel),
+            format!("{}/Streaming/Channels/{}02", base, channel),
+        ),
+    }
+}
+
+fn parse_device_csv(format: ImportFormat, content: &str) -> Result<ImportResult, String> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next().ok_or("Export is empty")?;
+    let delimiter = [',', ';', '\t'].into_iter()
+        .max_by_key(|d| header.matches(*d).count())
+        .unwrap_or(',');
+    let headers: Vec<String> = split_csv_line(header, delimiter).iter().map(|h| normalize_header(h)).collect();
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+
+    let name_col = column(&["name", "devicename", "cameraname", "channelname"]);
+    let host_col = column(&["ip", "ipaddress", "address", "ipdomain", "ipdomainname", "deviceaddress", "host"])
+        .ok_or("No IP address column found (expected \"IP Address\" or \"IP/Domain Name\")")?;
+    let port_col = column(&["rtspport"]);
+    let user_col = column(&["username", "user"]);
+    let password_col = column(&["password"]);
+    let channel_col = column(&["channel", "channelno", "channelnumber"]);
+    let count_col = column(&["channelcount", "channels", "numberofchannels", "analogchannels", "ipchannels"]);
+
+    let mut result = ImportResult::default();
+    for (row, line) in lines.enumerate() {
+        let fields = split_csv_line(line, delimiter);
+        let field = |col: Option<usize>| col.and_then(|c| fields.get(c)).map(String::as_str).filter(|f| !f.is_empty());
+        let row = row + 2; // 1-based, after the header
+        let Some(host) = field(Some(host_col)) else {
+            result.warnings.push(format!("row {}: no IP address", row));
+            continue;
+        };
+        let Ok(port) = field(port_col).map(str::parse::<u16>).unwrap_or(Ok(DEFAULT_RTSP_PORT)) else {
+            result.warnings.push(format!("row {}: invalid RTSP port", row));
+            continue;
+        };
+        let credentials = match (field(user_col), field(password_col)) {
+            (Some(user), Some(password)) => format!("{}:{}@", crate::percent_encode(user), crate::percent_encode(password)),
+            (Some(user), None) => format!("{}@", crate::percent_encode(user)),
+            _ => String::new(),
+        };
+        let name = field(name_col).unwrap_or(host);
+
+        // A channel column means one row per camera; otherwise a device row
+        // with a channel count expands into one camera per channel
+        let (channels, per_channel_names) = match field(channel_col).and_then(|c| c.parse::<u32>().ok()) {
+            Some(channel) => (channel..=channel, false),
+            None => {
+                let count = field(count_col).and_then(|c| c.parse::<u32>().ok()).unwrap_or(1).max(1);
+                (1..=count, count > 1)
+            }
+        };
+        for channel in channels {
+            let (url, sub_url) = stream_urls(format, host, port, &credentials, channel);
+            result.cameras.push(ImportedCamera {
+                name: if per_channel_names { format!("{} Ch{}", name, channel) } else { name.to_string() },
+                url,
+                sub_url: Some(sub_url),
+                enabled: true,
+            });
+        }
+    }
+    Ok(result)
+}
+
+// ── Frigate ──────────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+struct FrigateConfig {
+    #[serde(default)]
+    cameras: serde_yaml::Mapping,
+    #[serde(default)]
+    go2rtc: Option<Go2rtcConfig>,
+}
+
+#[derive(Deserialize)]
+struct Go2rtcConfig {
+    #[serde(default)]
+    streams: HashMap<String, serde_yaml::Value>,
+}
+
+#[derive(Deserialize)]
+struct FrigateCamera {
+    #[serde(default)]
+    ffmpeg: Option<FrigateFfmpeg>,
+    #[serde(default)]
+    enabled: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct FrigateFfmpeg {
+    #[serde(default)]
+    inputs: Vec<FrigateInput>,
+}
+
+#[derive(Deserialize)]
+struct FrigateInput {
+    path: String,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// "front_door" → "Front Door"
+fn display_name(key: &str) -> String {
+    key.split(['_', '-'])
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// An input pointing at go2rtc's local restream (`rtsp://127.0.0.1:8554/<name>`)
+/// is replaced by the source URL go2rtc pulls, since StageView won't be
+/// running next to go2rtc. Sources go2rtc transcodes (`ffmpeg:` prefix) are
+/// taken without their `#` options.
+fn resolve_go2rtc(path: &str, streams: &HashMap<String, serde_yaml::Value>) -> String {
+    let Some(rest) = path.strip_prefix("rtsp://") else { return path.to_string() };
+    let Some((host, stream)) = rest.split_once('/') else { return path.to_string() };
+    let host = host.rsplit('@').next().unwrap_or(host);
+    let local = [format!("127.0.0.1:{}", GO2RTC_RTSP_PORT), format!("localhost:{}", GO2RTC_RTSP_PORT)];
+    if !local.iter().any(|l| l == host) {
+        return path.to_string();
+    }
+    let stream = stream.split('?').next().unwrap_or(stream);
+    let sources = match streams.get(stream) {
+        Some(serde_yaml::Value::Sequence(items)) => items.iter().filter_map(|v| v.as_str()).collect(),
+        Some(serde_yaml::Value::String(s)) => vec![s.as_str()],
+        _ => vec![],
+    };
+    sources.into_iter()
+        .map(|s| s.trim_start_matches("ffmpeg:").split('#').next().unwrap_or(s))
+        .find(|s| s.starts_with("rtsp://") || s.starts_with("rtsps://"))
+        .map(str::to_string)
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn parse_frigate(content: &str) -> Result<ImportResult, String> {
+    let config: FrigateConfig = serde_yaml::from_str(content).map_err(|e| format!("Invalid Frigate YAML: {}", e))?;
+    let streams = config.go2rtc.map(|g| g.streams).unwrap_or_default();
+    let mut result = ImportResult::default();
+    for (key, value) in config.cameras {
+        let key = key.as_str().unwrap_or_default().to_string();
+        let camera: FrigateCamera = match serde_yaml::from_value(value) {
+            Ok(camera) => camera,
+            Err(e) => {
+                result.warnings.push(format!("{}: {}", key, e));
+                continue;
+            }
+        };
+        let inputs = camera.ffmpeg.map(|f| f.inputs).unwrap_or_default();
+        let with_role = |role: &str| inputs.iter().find(|i| i.roles.iter().any(|r| r == role));
+        let Some(main) = with_role("record").or(inputs.first()) else {
+            result.warnings.push(format!("{}: no ffmpeg inputs", key));
+            continue;
+        };
+        let url = resolve_go2rtc(&main.path, &streams);
+        let sub_url = with_role("detect")
+            .map(|i| resolve_go2rtc(&i.path, &streams))
+            .filter(|sub| *sub != url);
+        if url.contains("{FRIGATE_") {
+            result.warnings.push(format!("{}: imported, but its URL uses a Frigate environment variable; fill in the credentials", key));
+        }
+        result.cameras.push(ImportedCamera {
+            name: display_name(&key),
+            url,
+            sub_url,
+            enabled: camera.enabled.unwrap_or(true),
+        });
+    }
+    Ok(result)
+}
@@ -0,0 +1,299 @@
+//! ONVIF SOAP helpers. Requests are authenticated with a WS-Security
+//! UsernameToken (PasswordDigest), which every Profile S/T device accepts.
+
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use std::time::Duration;
+
+/// Default device service endpoint for a camera host.
+pub fn device_service_url(host: &str, port: Option<u16>) -> String {
+    match port {
+        Some(p) if p != 80 => format!("http://{}:{}/onvif/device_service", host, p),
+        _ => format!("http://{}/onvif/device_service", host),
+    }
+}
+
+/// WS-Security PasswordDigest UsernameToken, for the SOAP header.
+fn security_header(user: &str, pass: &str) -> String {
+    let nonce: [u8; 16] = rand::random();
+    let created = crate::iso8601_utc(crate::unix_millis() / 1000);
+    let mut hasher = Sha1::new();
+    hasher.update(nonce);
+    hasher.update(created.as_bytes());
+    hasher.update(pass.as_bytes());
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let digest = b64.encode(hasher.finalize());
+    format!(
+        r#"<Security s:mustUnderstand="1" xmlns="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd"><UsernameToken><Username>{}</Username><Password Type="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0#PasswordDigest">{}</Password><Nonce EncodingType="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-soap-message-security-1.0#Base64Binary">{}</Nonce><Created xmlns="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd">{}</Created></UsernameToken></Security>"#,
+        xml_escape(user), digest, b64.encode(nonce), created
+    )
+}
+
+/// POST a SOAP 1.2 request body to an ONVIF endpoint and return the response XML.
+pub async fn soap_call(
+    endpoint: &str,
+    user: Option<&str>,
+    pass: Option<&str>,
+    body: &str,
+    timeout: Duration,
+) -> Result<String, String> {
+    post_envelope(endpoint, user, pass, "", body, timeout).await
+}
+
+/// `soap_call` with WS-Addressing `Action` and `To` headers, which event
+/// subscription endpoints (WS-BaseNotification) route on.
+pub async fn soap_call_with_action(
+    endpoint: &str,
+    user: Option<&str>,
+    pass: Option<&str>,
+    action: &str,
+    body: &str,
+    timeout: Duration,
+) -> Result<String, String> {
+    let addressing = format!(
+        r#"<a:Action s:mustUnderstand="1">{}</a:Action><a:To s:mustUnderstand="1">{}</a:To>"#,
+        action, xml_escape(endpoint)
+    );
+    post_envelope(endpoint, user, pass, &addressing, body, timeout).await
+}
+
+async fn post_envelope(
+    endpoint: &str,
+    user: Option<&str>,
+    pass: Option<&str>,
+    extra_headers: &str,
+    body: &str,
+    timeout: Duration,
+) -> Result<String, String> {
+    let security = match (user, pass) {
+        (Some(u), Some(p)) => security_header(u, p),
+        _ => String::new(),
+    };
+    let header = if security.is_empty() && extra_headers.is_empty() {
+        String::new()
+    } else {
+        format!("<s:Header>{}{}</s:Header>", extra_headers, security)
+    };
+    let envelope = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:a="http://www.w3.org/2005/08/addressing">{}<s:Body>{}</s:Body></s:Envelope>"#,
+        header, body
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client.post(endpoint)
+        .header("Content-Type", "application/soap+xml; charset=utf-8")
+        .body(envelope)
+        .send()
+        .await
+        .map_err(|e| format!("ONVIF request to {} failed: {}", endpoint, e))?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        let reason = xml_text(&text, "Text").unwrap_or_else(|| status.to_string());
+        return Err(format!("ONVIF fault: {}", reason));
+    }
+    Ok(text)
+}
+
+#[derive(Debug, Default)]
+pub struct DeviceInformation {
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub firmware: Option<String>,
+    pub serial: Option<String>,
+}
+
+pub async fn get_device_information(
+    endpoint: &str,
+    user: Option<&str>,
+    pass: Option<&str>,
+    timeout: Duration,
+) -> Result<DeviceInformation, String> {
+    let body = r#"<GetDeviceInformation xmlns="http://www.onvif.org/ver10/device/wsdl"/>"#;
+    let xml = soap_call(endpoint, user, pass, body, timeout).await?;
+    Ok(DeviceInformation {
+        manufacturer: xml_text(&xml, "Manufacturer"),
+        model: xml_text(&xml, "Model"),
+        firmware: xml_text(&xml, "FirmwareVersion"),
+        serial: xml_text(&xml, "SerialNumber"),
+    })
+}
+
+// ── Events ───────────────────────────────────────────────────────────────────
+
+const EVENTS_NS: &str = "http://www.onvif.org/ver10/events/wsdl";
+const NOTIFICATION_NS: &str = "http://docs.oasis-open.org/wsn/b-2";
+
+/// One notification from a PullMessages response, e.g. topic
+/// `tns1:RuleEngine/CellMotionDetector/Motion` with data `IsMotion=true`.
+#[derive(Debug, Default, Clone)]
+pub struct Notification {
+    pub topic: String,
+    pub operation: Option<String>, // "Initialized" (current state on subscribe), "Changed", or "Deleted"
+    pub source: Vec<(String, String)>, // SimpleItem name/value pairs, e.g. VideoSourceConfigurationToken
+    pub data: Vec<(String, String)>, // e.g. IsMotion=true, LogicalState=false
+}
+
+/// Event service address from the device's capabilities.
+pub async fn get_event_service_url(
+    device_endpoint: &str,
+    user: Option<&str>,
+    pass: Option<&str>,
+    timeout: Duration,
+) -> Result<String, String> {
+    let body = r#"<GetCapabilities xmlns="http://www.onvif.org/ver10/device/wsdl"><Category>Events</Category></GetCapabilities>"#;
+    let xml = soap_call(device_endpoint, user, pass, body, timeout).await?;
+    xml_text(&xml, "XAddr").ok_or_else(|| "Device has no event service".to_string())
+}
+
+/// Create a PullPoint subscription that lapses after `termination_secs`
+/// unless renewed, and return its address.
+pub async fn create_pull_point(
+    events_endpoint: &str,
+    user: Option<&str>,
+    pass: Option<&str>,
+    termination_secs: u64,
+    timeout: Duration,
+) -> Result<String, String> {
+    let body = format!(
+        r#"<CreatePullPointSubscription xmlns="{}"><InitialTerminationTime>PT{}S</InitialTerminationTime></CreatePullPointSubscription>"#,
+        EVENTS_NS, termination_secs
+    );
+    let action = format!("{}/EventPortType/CreatePullPointSubscriptionRequest", EVENTS_NS);
+    let xml = soap_call_with_action(events_endpoint, user, pass, &action, &body, timeout).await?;
+    let reference = xml_elements(&xml, "SubscriptionReference").into_iter().next().map(|(_, inner)| inner).unwrap_or("");
+    xml_text(reference, "Address").ok_or_else(|| "No subscription address in response".to_string())
+}
+
+/// Wait up to `wait_secs` for notifications on a PullPoint subscription.
+/// The camera answers as soon as it has any, so `timeout` must exceed the wait.
+pub async fn pull_messages(
+    subscription: &str,
+    user: Option<&str>,
+    pass: Option<&str>,
+    wait_secs: u64,
+    timeout: Duration,
+) -> Result<Vec<Notification>, String> {
+    let body = format!(
+        r#"<PullMessages xmlns="{}"><Timeout>PT{}S</Timeout><MessageLimit>64</MessageLimit></PullMessages>"#,
+        EVENTS_NS, wait_secs
+    );
+    let action = format!("{}/PullPointSubscription/PullMessagesRequest", EVENTS_NS);
+    let xml = soap_call_with_action(subscription, user, pass, &action, &body, timeout).await?;
+    Ok(xml_elements(&xml, "NotificationMessage").into_iter().map(|(_, message)| parse_notification(message)).collect())
+}
+
+/// Push a subscription's termination time `termination_secs` into the future.
+pub async fn renew(
+    subscription: &str,
+    user: Option<&str>,
+    pass: Option<&str>,
+    termination_secs: u64,
+    timeout: Duration,
+) -> Result<(), String> {
+    let body = format!(r#"<Renew xmlns="{}"><TerminationTime>PT{}S</TerminationTime></Renew>"#, NOTIFICATION_NS, termination_secs);
+    let action = "http://docs.oasis-open.org/wsn/bw-2/SubscriptionManager/RenewRequest";
+    soap_call_with_action(subscription, user, pass, action, &body, timeout).await.map(|_| ())
+}
+
+pub async fn unsubscribe(subscription: &str, user: Option<&str>, pass: Option<&str>, timeout: Duration) -> Result<(), String> {
+    let body = format!(r#"<Unsubscribe xmlns="{}"/>"#, NOTIFICATION_NS);
+    let action = "http://docs.oasis-open.org/wsn/bw-2/SubscriptionManager/UnsubscribeRequest";
+    soap_call_with_action(subscription, user, pass, action, &body, timeout).await.map(|_| ())
+}
+
+fn parse_notification(message: &str) -> Notification {
+    let simple_items = |section: &str| -> Vec<(String, String)> {
+        xml_elements(message, section).into_iter().next()
+            .map(|(_, inner)| {
+                xml_elements(inner, "SimpleItem").into_iter()
+                    .filter_map(|(attrs, _)| Some((xml_attr(attrs, "Name")?, xml_attr(attrs, "Value")?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let operation = xml_elements(message, "Message").into_iter()
+        .find_map(|(attrs, _)| xml_attr(attrs, "PropertyOperation"));
+    Notification {
+        topic: xml_text(message, "Topic").unwrap_or_default(),
+        operation,
+        source: simple_items("Source"),
+        data: simple_items("Data"),
+    }
+}
+
+/// Attributes and content of every element whose local name is `tag`, in
+/// document order. Self-closing elements have empty content; elements of the
+/// same name nested inside each other are not supported.
+pub fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let mut found = Vec::new();
+    let mut search = 0;
+    while let Some(rel) = xml[search..].find('<') {
+        let start = search + rel + 1;
+        let Some(end) = xml[start..].find('>').map(|e| start + e) else { break };
+        let open = &xml[start..end];
+        let name = open.split_whitespace().next().unwrap_or("");
+        let local = name.rsplit(':').next().unwrap_or(name);
+        search = end + 1;
+        if local != tag || name.starts_with('/') {
+            continue;
+        }
+        let attrs = &open[name.len()..];
+        if let Some(attrs) = attrs.strip_suffix('/') {
+            found.push((attrs, ""));
+            continue;
+        }
+        let close = format!("</{}>", name);
+        let Some(inner_len) = xml[search..].find(&close) else { break };
+        found.push((attrs, &xml[search..search + inner_len]));
+        search += inner_len + close.len();
+    }
+    found
+}
+
+/// Value of attribute `name` in an element's attribute text.
+pub fn xml_attr(attrs: &str, name: &str) -> Option<String> {
+    let mut rest = attrs;
+    while let Some(pos) = rest.find(name) {
+        let preceded = pos == 0 || rest[..pos].ends_with(char::is_whitespace);
+        let after = rest[pos + name.len()..].trim_start();
+        if let (true, Some(after)) = (preceded, after.strip_prefix('=')) {
+            let after = after.trim_start();
+            let quote = after.chars().next()?;
+            if quote == '"' || quote == '\'' {
+                let value = &after[1..];
+                let end = value.find(quote)?;
+                return Some(value[..end].replace("&quot;", "\"").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&"));
+            }
+        }
+        rest = &rest[pos + name.len()..];
+    }
+    None
+}
+
+/// Text content of the first element whose local name is `tag`, ignoring
+/// namespace prefixes (`<tds:Model>` and `<Model>` both match). ONVIF
+/// responses are small and flat enough that this beats pulling in an XML parser.
+pub fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let mut search = 0;
+    while let Some(rel) = xml[search..].find('<') {
+        let start = search + rel + 1;
+        let end = start + xml[start..].find('>')?;
+        let name = xml[start..end].split_whitespace().next().unwrap_or("");
+        let local = name.rsplit(':').next().unwrap_or(name);
+        if local == tag && !name.starts_with('/') && !xml[start..end].ends_with('/') {
+            let close = xml[end + 1..].find("</")?;
+            let text = xml[end + 1..end + 1 + close].trim();
+            return (!text.is_empty()).then(|| text.to_string());
+        }
+        search = end + 1;
+    }
+    None
+}
+
+pub fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,206 @@
+//! ONVIF event subscriptions. Cameras with `onvif.events` set are followed
+//! through a PullPoint subscription (no callback port needed, so it works
+//! through NAT and firewalls). Motion, tamper, and digital input changes are
+//! emitted as `onvif-event`, written to the event log, and fire the matching
+//! auto-solo rules, so the wall reacts to camera analytics without a VMS.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, warn};
+
+/// How often the camera list is re-read for subscriptions to start or stop.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Subscriptions lapse on the camera this long after the last renewal, so a
+/// crashed StageView doesn't leave them behind.
+const SUBSCRIPTION_SECS: u64 = 60;
+
+/// Renewed well before it would lapse.
+const RENEW_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Longest a PullMessages request waits for events before returning empty.
+const PULL_WAIT_SECS: u64 = 10;
+
+/// Timeout for requests other than PullMessages.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay before resubscribing after the camera drops or refuses a subscription.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Motion,
+    Tamper, // Camera moved, covered, defocused, or the scene changed
+    Input, // Digital input (door contact, PIR, button)
+}
+
+impl EventKind {
+    fn trigger(self) -> crate::rules::Trigger {
+        match self {
+            EventKind::Motion => crate::rules::Trigger::Motion,
+            EventKind::Tamper => crate::rules::Trigger::Tamper,
+            EventKind::Input => crate::rules::Trigger::Input,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct OnvifEvent {
+    pub camera_id: String,
+    pub index: usize, // 1-based
+    pub name: String,
+    pub kind: EventKind,
+    pub active: bool,
+    pub topic: String, // e.g. "tns1:RuleEngine/CellMotionDetector/Motion"
+    pub source: Option<String>, // Video source or input token, for multi-channel devices
+}
+
+/// Topics vary by vendor, so match on the parts they share.
+fn classify(topic: &str) -> Option<EventKind> {
+    let topic = topic.to_ascii_lowercase();
+    if topic.contains("tamper") || topic.contains("globalscenechange") || topic.contains("imagetooblurry")
+        || topic.contains("imagetoodark") || topic.contains("imagetoobright")
+    {
+        Some(EventKind::Tamper)
+    } else if topic.contains("motion") {
+        Some(EventKind::Motion)
+    } else if topic.contains("digitalinput") || topic.contains("trigger/relay") || topic.contains("/io/") {
+        Some(EventKind::Input)
+    } else {
+        None
+    }
+}
+
+/// The on/off state carried in a notification's data, e.g. `IsMotion=true`
+/// or `LogicalState=1`.
+fn is_active(data: &[(String, String)]) -> Option<bool> {
+    data.iter().find_map(|(_, value)| match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "active" => Some(true),
+        "false" | "0" | "inactive" => Some(false),
+        _ => None,
+    })
+}
+
+/// Keep one subscription task per camera with `onvif.events` set, starting
+/// and stopping them as cameras are added, changed, or removed.
+pub async fn run(app: AppHandle) {
+    let mut tasks: HashMap<String, (crate::OnvifSettings, String, JoinHandle<()>)> = HashMap::new();
+    loop {
+        let wanted: Vec<(String, crate::OnvifSettings, String)> = app.state::<crate::AppState>().config.lock()
+            .map(|c| {
+                c.cameras.iter()
+                    .filter(|cam| cam.enabled && cam.onvif.as_ref().is_some_and(|o| o.events))
+                    .map(|cam| (cam.id.clone(), cam.onvif.clone().unwrap_or_default(), cam.url.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        tasks.retain(|id, (settings, url, task)| {
+            let keep = wanted.iter().any(|(w, s, u)| w == id && s == settings && u == url);
+            if !keep {
+                info!("ONVIF events: unsubscribing camera {}", id);
+                task.abort();
+            }
+            keep
+        });
+        for (id, settings, url) in wanted {
+            if tasks.contains_key(&id) {
+                continue;
+            }
+            let task = tauri::async_runtime::spawn(follow(app.clone(), id.clone()));
+            tasks.insert(id, (settings, url, task));
+        }
+        tokio::time::sleep(RECONCILE_INTERVAL).await;
+    }
+}
+
+/// Subscribe to one camera and resubscribe whenever the session ends.
+async fn follow(app: AppHandle, camera_id: String) {
+    loop {
+        let camera = app.state::<crate::AppState>().config.lock().ok()
+            .and_then(|c| c.cameras.iter().find(|cam| cam.id == camera_id).cloned());
+        let Some(target) = camera.as_ref().and_then(crate::onvif_target) else {
+            warn!("ONVIF events: camera {} has no ONVIF address; set onvif.url", camera_id);
+            return;
+        };
+        if let Err(e) = session(&app, &camera_id, &target).await {
+            warn!("ONVIF events: camera {}: {}; retrying in {}s", camera_id, e, RETRY_DELAY.as_secs());
+        }
+        tokio::time::sleep(RETRY_DELAY).await;
+    }
+}
+
+/// One PullPoint subscription, pulled until a request fails.
+async fn session(app: &AppHandle, camera_id: &str, target: &crate::OnvifTarget) -> Result<(), String> {
+    let (user, pass) = (target.username.as_deref(), target.password.as_deref());
+    let events_url = crate::onvif::get_event_service_url(&target.endpoint, user, pass, REQUEST_TIMEOUT).await?;
+    let subscription = crate::onvif::create_pull_point(&events_url, user, pass, SUBSCRIPTION_SECS, REQUEST_TIMEOUT).await?;
+    info!("ONVIF events: subscribed to camera {}", camera_id);
+
+    let pull_timeout = Duration::from_secs(PULL_WAIT_SECS) + REQUEST_TIMEOUT;
+    let mut states: HashMap<(EventKind, Option<String>), bool> = HashMap::new();
+    let mut renewed = Instant::now();
+    let result = loop {
+        if renewed.elapsed() >= RENEW_INTERVAL {
+            if let Err(e) = crate::onvif::renew(&subscription, user, pass, SUBSCRIPTION_SECS, REQUEST_TIMEOUT).await {
+                break Err(e);
+            }
+            renewed = Instant::now();
+        }
+        match crate::onvif::pull_messages(&subscription, user, pass, PULL_WAIT_SECS, pull_timeout).await {
+            Ok(notifications) => {
+                for notification in notifications {
+                    handle(app, camera_id, &mut states, notification);
+                }
+            }
+            Err(e) => break Err(e),
+        }
+    };
+    let _ = crate::onvif::unsubscribe(&subscription, user, pass, REQUEST_TIMEOUT).await;
+    result
+}
+
+/// Emit and log state changes. `Initialized` messages only report the state
+/// at subscribe time, so they are recorded without firing anything.
+fn handle(
+    app: &AppHandle,
+    camera_id: &str,
+    states: &mut HashMap<(EventKind, Option<String>), bool>,
+    notification: crate::onvif::Notification,
+) {
+    let Some(kind) = classify(&notification.topic) else {
+        debug!("ONVIF events: camera {} ignoring topic {}", camera_id, notification.topic);
+        return;
+    };
+    let Some(active) = is_active(&notification.data) else { return };
+    let source = notification.source.first().map(|(_, value)| value.clone());
+    let previous = states.insert((kind, source.clone()), active);
+    if notification.operation.as_deref() == Some("Initialized") || previous == Some(active) {
+        return;
+    }
+
+    let Some(index) = crate::camera_index(app, camera_id) else { return };
+    let name = app.state::<crate::AppState>().config.lock().ok()
+        .and_then(|c| c.cameras.get(index - 1).map(|cam| cam.name.clone()))
+        .unwrap_or_default();
+    let event = OnvifEvent {
+        camera_id: camera_id.to_string(),
+        index,
+        name,
+        kind,
+        active,
+        topic: notification.topic,
+        source,
+    };
+    let state = if active { "started" } else { "ended" };
+    info!("ONVIF {:?} {} on camera {} ({})", kind, state, index, event.name);
+    crate::log_event(app, "onvif", Some(camera_id), format!("{:?} {} ({})", kind, state, event.topic));
+    let _ = app.emit("onvif-event", event);
+    if active {
+        crate::rules::fire(app, kind.trigger(), camera_id);
+    }
+}
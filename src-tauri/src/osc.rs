@@ -0,0 +1,213 @@
+//! OSC control over UDP, for TouchOSC, QLab, and lighting desks:
+//! `/stageview/solo 3` (or `/stageview/solo/3`), `/stageview/grid`, and
+//! `/stageview/fullscreen`. Bundles are unpacked and run immediately; their
+//! timetags are ignored. A button that sends `0` on release is ignored on
+//! release, so a press acts once.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::net::UdpSocket;
+use tracing::{debug, error, info};
+
+/// Every address StageView answers starts with this.
+const ADDRESS_PREFIX: &str = "/stageview/";
+
+/// Nesting limit for bundles inside bundles.
+const MAX_BUNDLE_DEPTH: usize = 4;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OscConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_bind")]
+    pub bind: String, // Loopback unless set; OSC has no authentication
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+fn default_bind() -> String { "127.0.0.1".to_string() }
+fn default_port() -> u16 { 8010 }
+
+impl Default for OscConfig {
+    fn default() -> Self {
+        Self { enabled: false, bind: default_bind(), port: default_port() }
+    }
+}
+
+/// The argument types StageView acts on; others are skipped over.
+#[derive(Debug, Clone, PartialEq)]
+enum Arg {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+}
+
+impl Arg {
+    fn as_index(&self) -> Option<usize> {
+        match self {
+            Arg::Int(i) => usize::try_from(*i).ok(),
+            Arg::Float(f) if *f >= 0.0 && f.fract() == 0.0 => Some(*f as usize),
+            Arg::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Buttons send 1 on press and 0 on release.
+    fn is_release(&self) -> bool {
+        match self {
+            Arg::Int(i) => *i == 0,
+            Arg::Float(f) => *f == 0.0,
+            Arg::Bool(b) => !b,
+            Arg::Str(_) => false,
+        }
+    }
+}
+
+struct Message {
+    address: String,
+    args: Vec<Arg>,
+}
+
+/// A null-terminated string padded to a multiple of 4 bytes; returns it and the bytes consumed.
+fn read_string(data: &[u8]) -> Option<(String, usize)> {
+    let end = data.iter().position(|&b| b == 0)?;
+    let s = std::str::from_utf8(&data[..end]).ok()?.to_string();
+    let padded = (end + 4) & !3;
+    (padded <= data.len()).then_some((s, padded))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    data.get(pos..pos + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64(data: &[u8], pos: usize) -> Option<u64> {
+    Some((read_u32(data, pos)? as u64) << 32 | read_u32(data, pos + 4)? as u64)
+}
+
+fn parse_message(data: &[u8]) -> Option<Message> {
+    let (address, mut pos) = read_string(data)?;
+    let mut args = Vec::new();
+    let Some((tags, used)) = data.get(pos..).and_then(read_string) else {
+        return Some(Message { address, args }); // Very old senders omit the type tags
+    };
+    pos += used;
+    for tag in tags.strip_prefix(',')?.chars() {
+        match tag {
+            'i' => {
+                args.push(Arg::Int(read_u32(data, pos)? as i32 as i64));
+                pos += 4;
+            }
+            'f' => {
+                args.push(Arg::Float(f32::from_bits(read_u32(data, pos)?) as f64));
+                pos += 4;
+            }
+            'h' => {
+                args.push(Arg::Int(read_u64(data, pos)? as i64));
+                pos += 8;
+            }
+            'd' => {
+                args.push(Arg::Float(f64::from_bits(read_u64(data, pos)?)));
+                pos += 8;
+            }
+            's' | 'S' => {
+                let (s, used) = read_string(data.get(pos..)?)?;
+                args.push(Arg::Str(s));
+                pos += used;
+            }
+            'b' => {
+                let len = read_u32(data, pos)? as usize;
+                pos += 4 + ((len + 3) & !3); // Blobs are skipped
+            }
+            'T' => args.push(Arg::Bool(true)),
+            'F' => args.push(Arg::Bool(false)),
+            't' => pos += 8, // Timetag argument
+            'c' | 'r' | 'm' => pos += 4, // Char, RGBA, MIDI
+            'N' | 'I' | '[' | ']' => {}
+            _ => return None, // Unknown type: the rest can't be located
+        }
+    }
+    Some(Message { address, args })
+}
+
+/// Messages in a packet: a single message, or a `#bundle` of them.
+fn parse_packet(data: &[u8], depth: usize, out: &mut Vec<Message>) {
+    if !data.starts_with(b"#bundle\0") {
+        out.extend(parse_message(data));
+        return;
+    }
+    if depth >= MAX_BUNDLE_DEPTH {
+        return;
+    }
+    let mut pos = 16; // "#bundle\0" plus the timetag
+    while let Some(size) = read_u32(data, pos) {
+        let start = pos + 4;
+        let Some(element) = data.get(start..start + size as usize) else { return };
+        parse_packet(element, depth + 1, out);
+        pos = start + size as usize;
+    }
+}
+
+async fn handle_message(app: &AppHandle, message: Message) {
+    let Some(command) = message.address.strip_prefix(ADDRESS_PREFIX) else {
+        debug!("OSC: ignoring {}", message.address);
+        return;
+    };
+    // `/stageview/solo/3` carries the index in the address, leaving the argument
+    // for the button state; `/stageview/solo 3` has no button state
+    let (command, index_in_address) = match command.split_once('/') {
+        Some((command, index)) => (command, index.parse::<usize>().ok()),
+        None => (command, None),
+    };
+    let button = if command == "solo" && index_in_address.is_none() { None } else { message.args.first() };
+    if button.map(Arg::is_release).unwrap_or(false) {
+        return;
+    }
+    match command {
+        "solo" => {
+            let count = app.state::<crate::AppState>().config.lock().map(|c| c.cameras.len()).unwrap_or(0);
+            match index_in_address.or_else(|| message.args.first().and_then(Arg::as_index)) {
+                Some(index) if index >= 1 && index <= count => {
+                    crate::emit_remote_command(app, "solo", Some(index));
+                }
+                _ => debug!("OSC: {} needs a camera index 1-{}", message.address, count),
+            }
+        }
+        "grid" => {
+            crate::emit_remote_command(app, "grid", None);
+        }
+        "fullscreen" => {
+            if let Err(e) = crate::api_fullscreen(app.clone()).await {
+                debug!("OSC: fullscreen failed: {}", e);
+            }
+        }
+        _ => debug!("OSC: unknown address {}", message.address),
+    }
+}
+
+/// Listen for OSC packets on the configured address and UDP port until the
+/// app exits.
+pub async fn run(app: AppHandle, config: OscConfig) {
+    let socket = match UdpSocket::bind((config.bind.as_str(), config.port)).await {
+        Ok(s) => {
+            info!("OSC: listening on {} udp/{}", config.bind, config.port);
+            s
+        }
+        Err(e) => {
+            error!("OSC: failed to bind {} udp/{}: {}", config.bind, config.port, e);
+            return;
+        }
+    };
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let Ok((n, peer)) = socket.recv_from(&mut buf).await else { continue };
+        let mut messages = Vec::new();
+        parse_packet(&buf[..n], 0, &mut messages);
+        if messages.is_empty() {
+            debug!("OSC: unparseable packet from {}", peer);
+        }
+        for message in messages {
+            handle_message(&app, message).await;
+        }
+    }
+}
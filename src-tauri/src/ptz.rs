@@ -0,0 +1,198 @@
+//! ONVIF PTZ control, so Stream Deck buttons can steer cameras from the same
+//! app that displays them. The PTZ service address and media profile are
+//! looked up once per camera and cached; a failed request or a change to the
+//! camera's URL or ONVIF settings drops the cache so the next one looks them
+//! up again (e.g. after a camera firmware update). Logins aren't cached; each
+//! request takes them from the config.
+//! Cameras with `visca` set are handed to the VISCA backend instead.
+
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info};
+
+const PTZ_NS: &str = "http://www.onvif.org/ver20/ptz/wsdl";
+const SCHEMA_NS: &str = "http://www.onvif.org/ver10/schema";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A continuous move stops on its own after this long unless `timeout_ms`
+/// says otherwise, so a lost "stop" (button release) can't leave a camera
+/// spinning.
+const DEFAULT_MOVE_TIMEOUT_MS: u64 = 1000;
+
+/// Where to send PTZ requests for one camera.
+#[derive(Clone, Debug)]
+pub struct PtzProfile {
+    endpoint: String, // PTZ service address
+    profile: String, // Media profile token with a PTZ configuration
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Preset {
+    pub token: String,
+    pub name: Option<String>,
+}
+
+/// Look up (or reuse) the camera's PTZ service and profile, with the ONVIF
+/// target whose login the requests use.
+async fn profile(app: &AppHandle, camera_id: &str) -> Result<(PtzProfile, crate::OnvifTarget), String> {
+    let state = app.state::<crate::AppState>();
+    let camera = state.config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?
+        .cameras.iter().find(|c| c.id == camera_id).cloned()
+        .ok_or_else(|| format!("Unknown camera {}", camera_id))?;
+    let target = crate::onvif_target(&camera).ok_or("Camera has no ONVIF address; set onvif.url")?;
+    if let Some(cached) = state.ptz_profiles.lock().ok().and_then(|p| p.get(camera_id).cloned()) {
+        return Ok((cached, target));
+    }
+    let (user, pass) = (target.username.as_deref(), target.password.as_deref());
+
+    let body = r#"<GetCapabilities xmlns="http://www.onvif.org/ver10/device/wsdl"><Category>All</Category></GetCapabilities>"#;
+    let capabilities = crate::onvif::soap_call(&target.endpoint, user, pass, body, REQUEST_TIMEOUT).await?;
+    let service = |name: &str| {
+        crate::onvif::xml_elements(&capabilities, name).into_iter().next()
+            .and_then(|(_, inner)| crate::onvif::xml_text(inner, "XAddr"))
+    };
+    let endpoint = service("PTZ").ok_or("Camera has no PTZ service")?;
+    let media = service("Media").ok_or("Camera has no media service")?;
+
+    let token = match camera.onvif.as_ref().and_then(|o| o.profile.clone()) {
+        Some(token) => token,
+        None => {
+            let body = r#"<GetProfiles xmlns="http://www.onvif.org/ver10/media/wsdl"/>"#;
+            let profiles = crate::onvif::soap_call(&media, user, pass, body, REQUEST_TIMEOUT).await?;
+            crate::onvif::xml_elements(&profiles, "Profiles").into_iter()
+                .find(|(_, inner)| inner.contains("PTZConfiguration"))
+                .and_then(|(attrs, _)| crate::onvif::xml_attr(attrs, "token"))
+                .ok_or("No media profile with a PTZ configuration")?
+        }
+    };
+    debug!("PTZ for camera {}: {} profile {}", camera_id, endpoint, token);
+    let resolved = PtzProfile { endpoint, profile: token };
+    if let Ok(mut profiles) = state.ptz_profiles.lock() {
+        profiles.insert(camera_id.to_string(), resolved.clone());
+    }
+    Ok((resolved, target))
+}
+
+/// Drop the cached lookups of cameras removed, or whose URL or ONVIF settings
+/// changed, in a config change.
+pub fn config_changed(state: &crate::AppState, old: &[crate::Camera], new: &[crate::Camera]) {
+    let settings = |cameras: &[crate::Camera], id: &str| {
+        cameras.iter().find(|c| c.id == id).map(|c| (c.url.clone(), c.onvif.clone()))
+    };
+    if let Ok(mut profiles) = state.ptz_profiles.lock() {
+        profiles.retain(|id, _| settings(new, id).is_some() && settings(old, id) == settings(new, id));
+    }
+}
+
+/// The camera's VISCA target, when it is steered over VISCA instead of ONVIF.
+fn visca_target(app: &AppHandle, camera_id: &str) -> Result<Option<crate::visca::Target>, String> {
+    let config = app.state::<crate::AppState>().config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?;
+    let camera = config.cameras.iter().find(|c| c.id == camera_id)
+        .ok_or_else(|| format!("Unknown camera {}", camera_id))?;
+    crate::visca::target(camera).transpose()
+}
+
+/// Send one PTZ request, forgetting the cached profile if it fails.
+async fn call(app: &AppHandle, camera_id: &str, body: impl Fn(&PtzProfile) -> String) -> Result<String, String> {
+    let (ptz, target) = profile(app, camera_id).await?;
+    let result = crate::onvif::soap_call(
+        &ptz.endpoint, target.username.as_deref(), target.password.as_deref(), &body(&ptz), REQUEST_TIMEOUT,
+    ).await;
+    if result.is_err() {
+        if let Ok(mut profiles) = app.state::<crate::AppState>().ptz_profiles.lock() {
+            profiles.remove(camera_id);
+        }
+    }
+    result
+}
+
+/// Pan, tilt, and zoom at the given speeds (-1 to 1; pan right, tilt up, and
+/// zoom in are positive) until `stop` or the timeout.
+pub async fn continuous_move(app: &AppHandle, camera_id: &str, pan: f32, tilt: f32, zoom: f32, timeout_ms: Option<u64>) -> Result<(), String> {
+    let (pan, tilt, zoom) = (pan.clamp(-1.0, 1.0), tilt.clamp(-1.0, 1.0), zoom.clamp(-1.0, 1.0));
+    let timeout_ms = timeout_ms.unwrap_or(DEFAULT_MOVE_TIMEOUT_MS).max(100);
+    if let Some(target) = visca_target(app, camera_id)? {
+        return crate::visca::continuous_move(app, camera_id, &target, pan, tilt, zoom, timeout_ms).await;
+    }
+    call(app, camera_id, |ptz| format!(
+        r#"<ContinuousMove xmlns="{}"><ProfileToken>{}</ProfileToken><Velocity><PanTilt xmlns="{}" x="{}" y="{}"/><Zoom xmlns="{}" x="{}"/></Velocity><Timeout>PT{}.{:03}S</Timeout></ContinuousMove>"#,
+        PTZ_NS, crate::onvif::xml_escape(&ptz.profile), SCHEMA_NS, pan, tilt, SCHEMA_NS, zoom, timeout_ms / 1000, timeout_ms % 1000
+    )).await?;
+    debug!("PTZ move on camera {}: pan {} tilt {} zoom {}", camera_id, pan, tilt, zoom);
+    Ok(())
+}
+
+pub async fn stop(app: &AppHandle, camera_id: &str) -> Result<(), String> {
+    if let Some(target) = visca_target(app, camera_id)? {
+        return crate::visca::stop(app, camera_id, &target).await;
+    }
+    call(app, camera_id, |ptz| format!(
+        r#"<Stop xmlns="{}"><ProfileToken>{}</ProfileToken><PanTilt>true</PanTilt><Zoom>true</Zoom></Stop>"#,
+        PTZ_NS, crate::onvif::xml_escape(&ptz.profile)
+    )).await.map(|_| ())
+}
+
+pub async fn presets(app: &AppHandle, camera_id: &str) -> Result<Vec<Preset>, String> {
+    if visca_target(app, camera_id)?.is_some() {
+        return Err("VISCA cameras can't list their presets".to_string());
+    }
+    let xml = call(app, camera_id, |ptz| format!(
+        r#"<GetPresets xmlns="{}"><ProfileToken>{}</ProfileToken></GetPresets>"#,
+        PTZ_NS, crate::onvif::xml_escape(&ptz.profile)
+    )).await?;
+    Ok(crate::onvif::xml_elements(&xml, "Preset").into_iter()
+        .filter_map(|(attrs, inner)| Some(Preset {
+            token: crate::onvif::xml_attr(attrs, "token")?,
+            name: crate::onvif::xml_text(inner, "Name"),
+        }))
+        .collect())
+}
+
+/// The camera's token for preset `n`. Vendors number tokens differently
+/// ("1", "Preset1", "000001"), so a token equal to `n` wins, then a token or
+/// name whose number is `n`. With `by_position`, the n-th preset in the
+/// camera's list is the last resort.
+fn preset_token(presets: &[Preset], n: u32, by_position: bool) -> Option<String> {
+    let numbered = |s: &str| s.trim_start_matches(|c: char| !c.is_ascii_digit()).parse::<u32>().ok() == Some(n);
+    presets.iter().find(|p| p.token == n.to_string())
+        .or_else(|| presets.iter().find(|p| numbered(&p.token) || p.name.as_deref().is_some_and(numbered)))
+        .or_else(|| presets.get((n as usize).checked_sub(1)?).filter(|_| by_position))
+        .map(|p| p.token.clone())
+}
+
+/// Recall preset `n` (1-based).
+pub async fn goto_preset(app: &AppHandle, camera_id: &str, n: u32) -> Result<String, String> {
+    if let Some(target) = visca_target(app, camera_id)? {
+        return crate::visca::goto_preset(camera_id, &target, n).await;
+    }
+    let token = preset_token(&presets(app, camera_id).await?, n, true)
+        .ok_or_else(|| format!("Camera {} has no preset {}", camera_id, n))?;
+    call(app, camera_id, |ptz| format!(
+        r#"<GotoPreset xmlns="{}"><ProfileToken>{}</ProfileToken><PresetToken>{}</PresetToken></GotoPreset>"#,
+        PTZ_NS, crate::onvif::xml_escape(&ptz.profile), crate::onvif::xml_escape(&token)
+    )).await?;
+    info!("PTZ: camera {} to preset {} ({})", camera_id, n, token);
+    Ok(token)
+}
+
+/// Store the current position as preset `n`, overwriting it if it exists.
+pub async fn set_preset(app: &AppHandle, camera_id: &str, n: u32) -> Result<String, String> {
+    if let Some(target) = visca_target(app, camera_id)? {
+        return crate::visca::set_preset(camera_id, &target, n).await;
+    }
+    let existing = preset_token(&presets(app, camera_id).await?, n, false);
+    let token_element = existing.as_deref()
+        .map(|t| format!("<PresetToken>{}</PresetToken>", crate::onvif::xml_escape(t)))
+        .unwrap_or_default();
+    let xml = call(app, camera_id, |ptz| format!(
+        r#"<SetPreset xmlns="{}"><ProfileToken>{}</ProfileToken><PresetName>Preset {}</PresetName>{}</SetPreset>"#,
+        PTZ_NS, crate::onvif::xml_escape(&ptz.profile), n, token_element
+    )).await?;
+    let token = crate::onvif::xml_text(&xml, "PresetToken").or(existing).unwrap_or_default();
+    info!("PTZ: camera {} saved preset {} ({})", camera_id, n, token);
+    Ok(token)
+}
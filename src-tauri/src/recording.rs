@@ -0,0 +1,183 @@
+//! Rolling segmented MP4 recording. `process_fmp4_stream` tees each camera's
+//! init segment and fragments into a `Recorder`, whose writer task cuts a new
+//! self-contained file (init segment + fragments) at the first keyframe after
+//! every `segment_secs`. The stream loop holds a `RecorderTap` rather than
+//! looking the recorder up for every fragment.
+
+use bytes::Bytes;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Fragments queued for the writer before new ones are dropped (~12s at 50 ms).
+const QUEUE_DEPTH: usize = 256;
+
+pub struct RecordingSettings {
+    pub dir: PathBuf, // Directory for this camera's segments
+    pub segment_secs: u64,
+    pub key: Option<[u8; 32]>, // Encrypt segments at rest (see `recording_key`)
+}
+
+enum Message {
+    Init(Bytes),
+    Fragment { keyframe: bool, data: Bytes },
+}
+
+/// Handle to a running recording. Dropping it closes the current segment.
+pub struct Recorder {
+    tx: mpsc::Sender<Message>,
+    writer: tauri::async_runtime::JoinHandle<()>,
+    pub dir: PathBuf,
+    pub started_at: u64, // Unix ms
+}
+
+/// What the stream loop writes through. It doesn't keep the writer alive:
+/// once the `Recorder` is dropped or finished, writes are discarded.
+#[derive(Clone)]
+pub struct RecorderTap {
+    tx: mpsc::WeakSender<Message>,
+    dir: PathBuf,
+}
+
+impl RecorderTap {
+    /// A new init segment (FFmpeg restarted); the next segment file uses it.
+    pub fn write_init(&self, init_segment: Bytes) {
+        if let Some(tx) = self.tx.upgrade() {
+            let _ = tx.try_send(Message::Init(init_segment));
+        }
+    }
+
+    pub fn write_fragment(&self, keyframe: bool, data: Bytes) {
+        let Some(tx) = self.tx.upgrade() else { return };
+        if tx.try_send(Message::Fragment { keyframe, data }).is_err() {
+            debug!("Recording queue full for {}, dropping fragment", self.dir.display());
+        }
+    }
+}
+
+impl Recorder {
+    /// Start recording `camera_id`. `init_segment` is the pipeline's cached
+    /// ftyp+moov, so recording can begin mid-stream at the next keyframe.
+    pub fn start(camera_id: &str, settings: RecordingSettings, init_segment: Option<Bytes>) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_DEPTH);
+        let dir = settings.dir.clone();
+        if let Some(init) = init_segment {
+            let _ = tx.try_send(Message::Init(init));
+        }
+        info!("Recording {} to {}", camera_id, dir.display());
+        let writer = tauri::async_runtime::spawn(run_writer(camera_id.to_string(), dir.clone(), settings, rx));
+        Self { tx, writer, dir, started_at: crate::unix_millis() }
+    }
+
+    /// Stop recording and wait until the queued fragments are written and the
+    /// current segment is closed on disk.
+    pub async fn finish(self) {
+        let Recorder { tx, writer, .. } = self;
+        drop(tx);
+        let _ = writer.await;
+    }
+
+    pub fn tap(&self) -> RecorderTap {
+        RecorderTap { tx: self.tx.downgrade(), dir: self.dir.clone() }
+    }
+}
+
+/// The segment file currently being written.
+struct Segment {
+    path: PathBuf,
+    opened: Instant,
+    file: Option<tokio::fs::File>, // Plain segments stream straight to disk
+    buffer: Vec<u8>, // Encrypted segments are sealed as a whole when closed
+}
+
+async fn run_writer(camera_id: String, dir: PathBuf, settings: RecordingSettings, mut rx: mpsc::Receiver<Message>) {
+    if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+        warn!("Recording {}: cannot create {}: {}", camera_id, dir.display(), e);
+        return;
+    }
+    let segment_len = Duration::from_secs(settings.segment_secs.max(1));
+    let mut init: Option<Bytes> = None;
+    let mut segment: Option<Segment> = None;
+
+    while let Some(message) = rx.recv().await {
+        match message {
+            Message::Init(data) => {
+                // Stream parameters may have changed: close out the old segment
+                close_segment(&camera_id, segment.take(), settings.key.as_ref()).await;
+                init = Some(data);
+            }
+            Message::Fragment { keyframe, data } => {
+                let rotate = keyframe && segment.as_ref().map(|s| s.opened.elapsed() >= segment_len).unwrap_or(true);
+                if rotate {
+                    close_segment(&camera_id, segment.take(), settings.key.as_ref()).await;
+                    if let Some(ref init) = init {
+                        segment = open_segment(&camera_id, &dir, init, settings.key.is_some()).await;
+                    }
+                }
+                // Before the first keyframe there's nothing decodable to write
+                let Some(ref mut current) = segment else { continue };
+                let failed = match current.file {
+                    Some(ref mut file) => match file.write_all(&data).await {
+                        Ok(()) => false,
+                        Err(e) => {
+                            warn!("Recording {}: write to {} failed: {}", camera_id, current.path.display(), e);
+                            true
+                        }
+                    },
+                    None => {
+                        current.buffer.extend_from_slice(&data);
+                        false
+                    }
+                };
+                if failed {
+                    // Start over with a fresh file at the next keyframe
+                    segment = None;
+                }
+            }
+        }
+    }
+
+    close_segment(&camera_id, segment.take(), settings.key.as_ref()).await;
+    info!("Recording {} stopped", camera_id);
+}
+
+async fn open_segment(camera_id: &str, dir: &Path, init: &[u8], encrypted: bool) -> Option<Segment> {
+    // Millisecond names: a restart can close one segment and open the next
+    // within the same second
+    let now = crate::unix_millis();
+    let stamp = crate::iso8601_utc(now / 1000).replace(':', "-");
+    let stamp = format!("{}.{:03}Z", stamp.trim_end_matches('Z'), now % 1000);
+    let extension = if encrypted { crate::ENCRYPTED_EXTENSION } else { "mp4" };
+    let path = dir.join(format!("{}.{}", stamp, extension));
+
+    if encrypted {
+        return Some(Segment { path, opened: Instant::now(), file: None, buffer: init.to_vec() });
+    }
+    let mut file = match tokio::fs::File::create(&path).await {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("Recording {}: cannot create {}: {}", camera_id, path.display(), e);
+            return None;
+        }
+    };
+    if let Err(e) = file.write_all(init).await {
+        warn!("Recording {}: write to {} failed: {}", camera_id, path.display(), e);
+        return None;
+    }
+    debug!("Recording {}: new segment {}", camera_id, path.display());
+    Some(Segment { path, opened: Instant::now(), file: Some(file), buffer: Vec::new() })
+}
+
+async fn close_segment(camera_id: &str, segment: Option<Segment>, key: Option<&[u8; 32]>) {
+    let Some(segment) = segment else { return };
+    let result = match (segment.file, key) {
+        (Some(mut file), _) => file.flush().await.map_err(|e| e.to_string()),
+        (None, Some(key)) => crate::write_recording_file(&segment.path, &segment.buffer, Some(key)),
+        (None, None) => Ok(()),
+    };
+    if let Err(e) = result {
+        warn!("Recording {}: failed to finish {}: {}", camera_id, segment.path.display(), e);
+    }
+}
@@ -0,0 +1,308 @@
+//! Minimal RTSP client: just enough of RTSP/1.0 to issue a DESCRIBE and read
+//! the SDP back, with Basic and Digest authentication. Used for metadata
+//! probing (camera naming) and for the talkback backchannel, which FFmpeg
+//! can't set up — streaming itself always goes through FFmpeg.
+
+use crate::UrlParts;
+use base64::Engine;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Parsed RTSP response: status code, lower-cased header map, and body.
+pub struct RtspResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// Send DESCRIBE for `url` and return the response. If the server answers 401
+/// and the URL carries credentials, retry once with Basic or Digest auth.
+pub async fn describe(url: &str, timeout: Duration) -> Result<RtspResponse, String> {
+    let parts = UrlParts::parse(url).ok_or("Invalid RTSP URL")?;
+    let port = parts.port.unwrap_or(554);
+    let addr = format!("{}:{}", parts.host, port);
+    let request_url = parts.without_credentials();
+
+    let mut stream = tokio::time::timeout(timeout, TcpStream::connect(&addr))
+        .await
+        .map_err(|_| format!("Timed out connecting to {}", addr))?
+        .map_err(|e| format!("Connect to {} failed: {}", addr, e))?;
+
+    let first = send_request(&mut stream, "DESCRIBE", &request_url, 1, None, &[], timeout).await?;
+    if first.status != 401 {
+        return Ok(first);
+    }
+
+    let (user, pass) = match (&parts.username, &parts.password) {
+        (Some(u), Some(p)) => (u.clone(), p.clone()),
+        _ => return Ok(first), // No credentials to offer
+    };
+    let challenge = first.headers.get("www-authenticate").cloned().unwrap_or_default();
+    let auth = authorization(&challenge, &user, &pass, "DESCRIBE", &request_url);
+    send_request(&mut stream, "DESCRIBE", &request_url, 2, Some(&auth), &[], timeout).await
+}
+
+/// An RTSP connection that keeps its credentials, authentication challenge,
+/// and session id across requests.
+pub struct RtspConnection {
+    stream: TcpStream,
+    url: String, // Without credentials
+    credentials: Option<(String, String)>,
+    challenge: Option<String>, // Last WWW-Authenticate, answered on every later request
+    cseq: u32,
+    session: Option<String>,
+    timeout: Duration,
+}
+
+impl RtspConnection {
+    pub async fn connect(url: &str, timeout: Duration) -> Result<Self, String> {
+        let parts = UrlParts::parse(url).ok_or("Invalid RTSP URL")?;
+        let addr = format!("{}:{}", parts.host, parts.port.unwrap_or(554));
+        let stream = tokio::time::timeout(timeout, TcpStream::connect(&addr))
+            .await
+            .map_err(|_| format!("Timed out connecting to {}", addr))?
+            .map_err(|e| format!("Connect to {} failed: {}", addr, e))?;
+        Ok(Self {
+            stream,
+            url: parts.without_credentials(),
+            credentials: parts.username.clone().zip(parts.password.clone()),
+            challenge: None,
+            cseq: 0,
+            session: None,
+            timeout,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn next_request(&mut self, method: &str, uri: &str, headers: &[(&str, &str)]) -> (u32, Option<String>, Vec<(String, String)>) {
+        self.cseq += 1;
+        let auth = match (&self.challenge, &self.credentials) {
+            (Some(challenge), Some((user, pass))) => Some(authorization(challenge, user, pass, method, uri)),
+            _ => None,
+        };
+        let mut headers: Vec<(String, String)> = headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        if let Some(session) = &self.session {
+            headers.push(("Session".to_string(), session.clone()));
+        }
+        (self.cseq, auth, headers)
+    }
+
+    /// Send a request and read its response, answering one 401 with the URL's
+    /// credentials. The `Session` a response sets is sent on later requests.
+    pub async fn request(&mut self, method: &str, uri: &str, headers: &[(&str, &str)]) -> Result<RtspResponse, String> {
+        for _ in 0..2 {
+            let (cseq, auth, extra) = self.next_request(method, uri, headers);
+            let extra: Vec<(&str, &str)> = extra.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            let response = send_request(&mut self.stream, method, uri, cseq, auth.as_deref(), &extra, self.timeout).await?;
+            if response.status == 401 && self.credentials.is_some() && auth.is_none() {
+                self.challenge = response.headers.get("www-authenticate").cloned();
+                continue;
+            }
+            if let Some(session) = response.headers.get("session") {
+                // "12345678;timeout=60" — only the id is echoed back
+                self.session = session.split(';').next().map(|s| s.trim().to_string());
+            }
+            return Ok(response);
+        }
+        Err(format!("{} {} was refused: check the camera credentials", method, uri))
+    }
+
+    /// Send a request without waiting for the response, for keepalives and
+    /// TEARDOWN once the connection is carrying interleaved media.
+    pub async fn send(&mut self, method: &str, uri: &str) -> Result<(), String> {
+        let (cseq, auth, extra) = self.next_request(method, uri, &[]);
+        let extra: Vec<(&str, &str)> = extra.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        let request = format_request(method, uri, cseq, auth.as_deref(), &extra);
+        self.stream.write_all(request.as_bytes()).await.map_err(|e| e.to_string())
+    }
+
+    /// Read whatever the server sends while media flows (keepalive responses,
+    /// RTCP) so it never backs up; the caller drops it. 0 means the server
+    /// closed the connection.
+    pub async fn read_incoming(&mut self, buf: &mut [u8]) -> Result<usize, String> {
+        self.stream.read(buf).await.map_err(|e| e.to_string())
+    }
+
+    /// Write one `$`-framed interleaved packet (RFC 2326 §10.12).
+    pub async fn write_interleaved(&mut self, channel: u8, packet: &[u8]) -> Result<(), String> {
+        let mut frame = Vec::with_capacity(4 + packet.len());
+        frame.extend_from_slice(&[b'$', channel]);
+        frame.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+        frame.extend_from_slice(packet);
+        self.stream.write_all(&frame).await.map_err(|e| e.to_string())
+    }
+}
+
+fn authorization(challenge: &str, user: &str, pass: &str, method: &str, uri: &str) -> String {
+    if challenge.to_ascii_lowercase().starts_with("digest") {
+        digest_authorization(challenge, user, pass, method, uri)
+    } else {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, pass));
+        format!("Basic {}", token)
+    }
+}
+
+fn format_request(method: &str, url: &str, cseq: u32, authorization: Option<&str>, headers: &[(&str, &str)]) -> String {
+    let mut request = format!(
+        "{} {} RTSP/1.0\r\nCSeq: {}\r\nAccept: application/sdp\r\nUser-Agent: StageView\r\n",
+        method, url, cseq
+    );
+    if let Some(auth) = authorization {
+        request.push_str(&format!("Authorization: {}\r\n", auth));
+    }
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    request
+}
+
+async fn send_request(
+    stream: &mut TcpStream,
+    method: &str,
+    url: &str,
+    cseq: u32,
+    authorization: Option<&str>,
+    headers: &[(&str, &str)],
+    timeout: Duration,
+) -> Result<RtspResponse, String> {
+    let request = format_request(method, url, cseq, authorization, headers);
+    stream.write_all(request.as_bytes()).await.map_err(|e| e.to_string())?;
+
+    tokio::time::timeout(timeout, read_response(stream))
+        .await
+        .map_err(|_| "Timed out waiting for RTSP response".to_string())?
+}
+
+async fn read_response(stream: &mut TcpStream) -> Result<RtspResponse, String> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    // Read until the end of headers
+    let header_end = loop {
+        let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Connection closed before RTSP response".to_string());
+        }
+        data.extend_from_slice(&buf[..n]);
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if data.len() > 64 * 1024 {
+            return Err("RTSP response headers too large".to_string());
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or("");
+    let status = status_line.split_whitespace().nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| format!("Malformed RTSP status line: {}", status_line))?;
+    let headers: HashMap<String, String> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+
+    // Read the body if Content-Length says there is one
+    let content_length = headers.get("content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0)
+        .min(1024 * 1024);
+    while data.len() < header_end + content_length {
+        let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 { break; }
+        data.extend_from_slice(&buf[..n]);
+    }
+    let body_end = (header_end + content_length).min(data.len());
+    let body = String::from_utf8_lossy(&data[header_end..body_end]).to_string();
+
+    Ok(RtspResponse { status, headers, body })
+}
+
+/// Build a Digest `Authorization` header (RFC 2617, MD5, no qop — which is what
+/// camera RTSP servers implement in practice).
+fn digest_authorization(challenge: &str, user: &str, pass: &str, method: &str, uri: &str) -> String {
+    let param = |name: &str| -> String {
+        challenge.split(',')
+            .filter_map(|p| p.trim().trim_start_matches("Digest ").split_once('='))
+            .find(|(k, _)| k.trim().eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.trim().trim_matches('"').to_string())
+            .unwrap_or_default()
+    };
+    let realm = param("realm");
+    let nonce = param("nonce");
+    let ha1 = format!("{:x}", md5::compute(format!("{}:{}:{}", user, realm, pass)));
+    let ha2 = format!("{:x}", md5::compute(format!("{}:{}", method, uri)));
+    let response = format!("{:x}", md5::compute(format!("{}:{}:{}", ha1, nonce, ha2)));
+    format!(
+        "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", response=\"{}\"",
+        user, realm, nonce, uri, response
+    )
+}
+
+/// Session name (`s=`) and information (`i=`) lines from an SDP body.
+/// Placeholder values like "-" or "Session streamed by ..." are dropped.
+pub fn sdp_session_name(sdp: &str) -> Option<String> {
+    sdp.lines()
+        .filter_map(|l| l.strip_prefix("s=").or_else(|| l.strip_prefix("i=")))
+        .map(|v| v.trim())
+        .find(|v| !v.is_empty() && *v != "-" && !v.starts_with("Session streamed by") && !v.eq_ignore_ascii_case("media presentation"))
+        .map(|v| v.to_string())
+}
+
+/// The ONVIF audio backchannel in a DESCRIBE answer: the audio stream the
+/// camera receives, marked `a=sendonly` from its side.
+#[derive(Debug, Clone)]
+pub struct Backchannel {
+    pub control: String, // Absolute URL to SETUP
+    pub payload_type: u8,
+    pub codec: String, // e.g. "PCMU", "PCMA"
+    pub clock_rate: u32,
+}
+
+/// Find the backchannel stream in an SDP. Relative control URLs are resolved
+/// against `base` (the Content-Base header, or the request URL).
+pub fn sdp_backchannel(sdp: &str, base: &str) -> Option<Backchannel> {
+    let mut sections: Vec<Vec<&str>> = Vec::new();
+    for line in sdp.lines().map(str::trim) {
+        if line.starts_with("m=") {
+            sections.push(Vec::new());
+        }
+        if let Some(section) = sections.last_mut() {
+            section.push(line);
+        }
+    }
+    let section = sections.into_iter()
+        .find(|s| s[0].starts_with("m=audio") && s.contains(&"a=sendonly"))?;
+    let payload_type: u8 = section[0].split_whitespace().nth(3)?.parse().ok()?;
+    let rtpmap = section.iter()
+        .filter_map(|l| l.strip_prefix("a=rtpmap:"))
+        .find_map(|m| m.split_once(' ').filter(|(pt, _)| pt.parse::<u8>().ok() == Some(payload_type)).map(|(_, codec)| codec));
+    let (codec, clock_rate) = match rtpmap {
+        Some(map) => {
+            let mut fields = map.split('/');
+            (fields.next()?.to_ascii_uppercase(), fields.next().and_then(|r| r.parse().ok()).unwrap_or(8000))
+        }
+        // Static payload types need no rtpmap
+        None => match payload_type {
+            0 => ("PCMU".to_string(), 8000),
+            8 => ("PCMA".to_string(), 8000),
+            _ => return None,
+        },
+    };
+    let control = section.iter().find_map(|l| l.strip_prefix("a=control:")).unwrap_or("*");
+    let control = if control.starts_with("rtsp://") || control.starts_with("rtsps://") {
+        control.to_string()
+    } else if control == "*" {
+        base.to_string()
+    } else {
+        format!("{}/{}", base.trim_end_matches('/'), control)
+    };
+    Some(Backchannel { control, payload_type, codec, clock_rate })
+}
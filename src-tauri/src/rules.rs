@@ -0,0 +1,242 @@
+//! Auto-solo rules: a detection, tally, loud audio, or ONVIF event on a
+//! camera solos or highlights it for a while, then the wall goes back to what
+//! it showed before. Rules can be switched off as a set (e.g. outside show
+//! hours) via `/api/rules/off` without losing their configuration.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::info;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    Detection, // A Frigate/Home Assistant detection on the camera
+    Tally, // The camera's switcher source went to program
+    Audio, // The camera's audio stayed above `threshold_db` for `hold_ms`
+    Motion, // ONVIF motion detection started on the camera
+    Tamper, // ONVIF tamper or scene-change alarm
+    Input, // ONVIF digital input went active
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    #[default]
+    Solo,
+    Highlight, // Outline the tile and leave the layout alone
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Rule {
+    pub camera: String, // Camera id or name
+    pub trigger: Trigger,
+    #[serde(default)]
+    pub action: Action,
+    #[serde(default = "default_duration_secs")]
+    pub duration_secs: u64, // Then return to the previous view; 0 = stay
+    #[serde(default = "default_threshold_db")]
+    pub threshold_db: f32, // Audio only: RMS level in dBFS
+    #[serde(default = "default_hold_ms")]
+    pub hold_ms: u64, // Audio only: how long the level must stay above the threshold
+    #[serde(default = "crate::default_true")]
+    pub enabled: bool,
+}
+
+fn default_duration_secs() -> u64 { 15 }
+fn default_threshold_db() -> f32 { -20.0 }
+fn default_hold_ms() -> u64 { 500 }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RulesConfig {
+    #[serde(default = "crate::default_true")]
+    pub enabled: bool, // Master switch, toggled by /api/rules/on|off
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        Self { enabled: true, rules: Vec::new() }
+    }
+}
+
+/// A solo a rule is holding, and the view to go back to afterwards.
+struct SoloHold {
+    camera_id: String,
+    previous: Option<String>, // Soloed camera before the rule fired; None = grid
+    token: u64, // Bumped on every trigger, so only the newest timer restores
+}
+
+/// Where a camera's audio level is relative to its rule's threshold.
+#[derive(Default)]
+struct AudioGate {
+    above_since: Option<Instant>,
+    fired: bool, // Re-armed once the level drops back below the threshold
+}
+
+#[derive(Default)]
+pub struct RulesState {
+    hold: Mutex<Option<SoloHold>>,
+    next_token: Mutex<u64>,
+    audio: Mutex<HashMap<String, AudioGate>>,
+    audio_levels: Mutex<Option<HashMap<String, (f32, Duration)>>>, // camera_id -> lowest threshold and shortest hold; None = reread the config
+}
+
+#[derive(Serialize, Clone)]
+struct HighlightEvent {
+    camera_id: String,
+    active: bool,
+}
+
+fn matches_camera(rule: &Rule, camera: &crate::Camera) -> bool {
+    rule.camera == camera.id || rule.camera.eq_ignore_ascii_case(&camera.name)
+}
+
+fn is_audio_rule(rule: &Rule, camera: &crate::Camera) -> bool {
+    rule.enabled && rule.trigger == Trigger::Audio && matches_camera(rule, camera)
+}
+
+/// Whether the camera's pipeline should meter its audio: it has an enabled
+/// audio rule. The master switch isn't considered, so `/api/rules/off` and
+/// `on` don't reconnect anything.
+pub fn wants_audio_meter(config: &crate::AppConfig, camera: &crate::Camera) -> bool {
+    config.rules.rules.iter().any(|r| is_audio_rule(r, camera))
+}
+
+/// Drop the cached audio thresholds; called whenever the config is replaced.
+pub fn config_changed(app: &AppHandle) {
+    if let Ok(mut levels) = app.state::<crate::AppState>().rules.audio_levels.lock() {
+        *levels = None;
+    }
+}
+
+/// Each camera's lowest audio threshold and shortest hold, while rules are on.
+fn audio_levels(config: &crate::AppConfig) -> HashMap<String, (f32, Duration)> {
+    if !config.rules.enabled {
+        return HashMap::new();
+    }
+    config.cameras.iter()
+        .filter_map(|camera| {
+            let rules: Vec<&Rule> = config.rules.rules.iter().filter(|r| is_audio_rule(r, camera)).collect();
+            let threshold = rules.iter().map(|r| r.threshold_db).reduce(f32::min)?;
+            let hold = rules.iter().map(|r| r.hold_ms).min().unwrap_or(0);
+            Some((camera.id.clone(), (threshold, Duration::from_millis(hold))))
+        })
+        .collect()
+}
+
+/// Enabled rules for `trigger` on `camera_id`, with the camera's 1-based index.
+fn rules_for(app: &AppHandle, trigger: Trigger, camera_id: &str) -> Vec<(Rule, usize)> {
+    let Ok(config) = app.state::<crate::AppState>().config.lock() else { return Vec::new() };
+    if !config.rules.enabled {
+        return Vec::new();
+    }
+    let Some(index) = config.cameras.iter().position(|c| c.id == camera_id) else { return Vec::new() };
+    config.rules.rules.iter()
+        .filter(|r| r.enabled && r.trigger == trigger && matches_camera(r, &config.cameras[index]))
+        .map(|r| (r.clone(), index + 1))
+        .collect()
+}
+
+/// Run every enabled rule for `trigger` on this camera.
+pub fn fire(app: &AppHandle, trigger: Trigger, camera_id: &str) {
+    for (rule, index) in rules_for(app, trigger, camera_id) {
+        info!("Rule: {:?} on camera {} → {:?} for {}s", trigger, index, rule.action, rule.duration_secs);
+        match rule.action {
+            Action::Solo => solo(app, camera_id, index, rule.duration_secs),
+            Action::Highlight => highlight(app, camera_id, rule.duration_secs),
+        }
+    }
+}
+
+fn solo(app: &AppHandle, camera_id: &str, index: usize, duration_secs: u64) {
+    let state = app.state::<crate::AppState>();
+    let token = {
+        let Ok(mut next) = state.rules.next_token.lock() else { return };
+        *next += 1;
+        *next
+    };
+    if let Ok(mut hold) = state.rules.hold.lock() {
+        // A trigger during another rule's solo keeps the original view to return to
+        let previous = match hold.take() {
+            Some(h) => h.previous,
+            None => crate::view_state(app).camera_id,
+        };
+        *hold = Some(SoloHold { camera_id: camera_id.to_string(), previous, token });
+    }
+    crate::emit_remote_command(app, "solo", Some(index));
+    if duration_secs == 0 {
+        if let Ok(mut hold) = state.rules.hold.lock() {
+            *hold = None;
+        }
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(duration_secs)).await;
+        let state = app.state::<crate::AppState>();
+        let Some(hold) = state.rules.hold.lock().ok().and_then(|mut h| {
+            if h.as_ref().is_some_and(|h| h.token == token) { h.take() } else { None }
+        }) else {
+            return; // A newer trigger owns the wall now
+        };
+        // Leave it alone if an operator has moved on from the rule's camera
+        if crate::view_state(&app).camera_id.as_deref() != Some(hold.camera_id.as_str()) {
+            return;
+        }
+        let previous_index = hold.previous.as_deref().and_then(|id| crate::camera_index(&app, id));
+        match previous_index {
+            Some(index) => crate::emit_remote_command(&app, "solo", Some(index)),
+            None => crate::emit_remote_command(&app, "grid", None),
+        }
+    });
+}
+
+fn highlight(app: &AppHandle, camera_id: &str, duration_secs: u64) {
+    let _ = app.emit("highlight", HighlightEvent { camera_id: camera_id.to_string(), active: true });
+    if duration_secs == 0 {
+        return;
+    }
+    let app = app.clone();
+    let camera_id = camera_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(duration_secs)).await;
+        let _ = app.emit("highlight", HighlightEvent { camera_id, active: false });
+    });
+}
+
+/// Feed one audio level measurement (RMS dBFS) from a camera's pipeline.
+/// An audio rule fires once the level has stayed above its threshold for
+/// `hold_ms`, and not again until the level has dropped below it.
+pub fn audio_level(app: &AppHandle, camera_id: &str, db: f32) {
+    let state = app.state::<crate::AppState>();
+    // Several lines a second per camera, so the thresholds are cached rather than read from the config each time
+    let levels = {
+        let Ok(mut levels) = state.rules.audio_levels.lock() else { return };
+        if levels.is_none() {
+            let Ok(config) = state.config.lock() else { return };
+            *levels = Some(audio_levels(&config));
+        }
+        levels.as_ref().and_then(|l| l.get(camera_id).copied())
+    };
+    let Some((threshold, hold)) = levels else { return };
+    let fire_now = {
+        let Ok(mut gates) = state.rules.audio.lock() else { return };
+        let gate = gates.entry(camera_id.to_string()).or_default();
+        if db < threshold {
+            *gate = AudioGate::default();
+            false
+        } else {
+            let since = *gate.above_since.get_or_insert_with(Instant::now);
+            let fire = !gate.fired && since.elapsed() >= hold;
+            gate.fired |= fire;
+            fire
+        }
+    };
+    if fire_now {
+        fire(app, Trigger::Audio, camera_id);
+    }
+}
@@ -0,0 +1,172 @@
+//! Idle screensaver for installed displays. After `idle_minutes` with no
+//! commands, the wall is covered by a slow cross-fading montage of camera
+//! stills (or a drifting clock) so no tile border or label sits still long
+//! enough to burn into an OLED panel. The scheduler tick starts it; any API
+//! command, remote command (tally, OSC, Companion, rules), or local input ends
+//! it. Streams keep running underneath, so the wall is live as soon as it ends.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::info;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreensaverMode {
+    #[default]
+    Montage, // One online camera at a time, cross-fading between stills
+    Clock,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScreensaverConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_idle_minutes")]
+    pub idle_minutes: u64, // Minutes without a command before it starts
+    #[serde(default)]
+    pub mode: ScreensaverMode,
+    #[serde(default = "default_rotate_secs")]
+    pub rotate_secs: u64, // Montage: seconds each camera is shown
+    #[serde(default = "default_fade_ms")]
+    pub fade_ms: u64, // Length of the cross-fade between cameras
+}
+
+fn default_idle_minutes() -> u64 { 30 }
+fn default_rotate_secs() -> u64 { 20 }
+fn default_fade_ms() -> u64 { 3000 }
+
+impl Default for ScreensaverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: default_idle_minutes(),
+            mode: ScreensaverMode::default(),
+            rotate_secs: default_rotate_secs(),
+            fade_ms: default_fade_ms(),
+        }
+    }
+}
+
+pub struct ScreensaverState {
+    last_activity: AtomicU64, // Unix ms of the last command or local input
+    active: AtomicBool,
+    generation: AtomicU64, // Bumped on every start, so an old rotation task stops
+}
+
+impl ScreensaverState {
+    pub fn new() -> Self {
+        Self {
+            last_activity: AtomicU64::new(crate::unix_millis()),
+            active: AtomicBool::new(false),
+            generation: AtomicU64::new(0),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct ScreensaverEvent {
+    pub active: bool,
+    pub mode: ScreensaverMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_id: Option<String>, // Montage: the camera to fade to
+    pub fade_ms: u64,
+    pub idle_secs: u64, // Seconds since the last activity
+}
+
+fn event(app: &AppHandle, active: bool, camera_id: Option<String>) -> ScreensaverEvent {
+    let state = app.state::<crate::AppState>();
+    let config = state.config.lock().map(|c| c.screensaver.clone()).unwrap_or_default();
+    let last = state.screensaver.last_activity.load(Ordering::Relaxed);
+    ScreensaverEvent {
+        active,
+        mode: config.mode,
+        camera_id,
+        fade_ms: config.fade_ms,
+        idle_secs: crate::unix_millis().saturating_sub(last) / 1000,
+    }
+}
+
+/// Current state, for `/api/screensaver` and the frontend on load.
+pub fn status(app: &AppHandle) -> ScreensaverEvent {
+    let active = app.state::<crate::AppState>().screensaver.active.load(Ordering::Relaxed);
+    event(app, active, None)
+}
+
+/// Record activity, ending the screensaver if it is showing.
+pub fn touch(app: &AppHandle) {
+    let state = app.state::<crate::AppState>();
+    state.screensaver.last_activity.store(crate::unix_millis(), Ordering::Relaxed);
+    if state.screensaver.active.swap(false, Ordering::Relaxed) {
+        info!("Screensaver dismissed");
+        let _ = app.emit("screensaver", event(app, false, None));
+    }
+}
+
+/// Show the screensaver now, whatever the idle time.
+pub fn start(app: &AppHandle) -> ScreensaverEvent {
+    let state = app.state::<crate::AppState>();
+    if state.screensaver.active.swap(true, Ordering::Relaxed) {
+        return status(app);
+    }
+    let generation = state.screensaver.generation.fetch_add(1, Ordering::Relaxed) + 1;
+    info!("Screensaver started");
+    let first = next_camera(app, None);
+    let started = event(app, true, first.clone());
+    let _ = app.emit("screensaver", started.clone());
+    if started.mode == ScreensaverMode::Montage {
+        let app = app.clone();
+        tauri::async_runtime::spawn(rotate(app, generation, first));
+    }
+    started
+}
+
+/// Online, enabled cameras in wall order, starting after `current`.
+fn next_camera(app: &AppHandle, current: Option<&str>) -> Option<String> {
+    let state = app.state::<crate::AppState>();
+    let statuses = state.camera_statuses.lock().ok()?.clone();
+    let config = state.config.lock().ok()?;
+    let online: Vec<&str> = config.cameras.iter()
+        .filter(|c| c.enabled && statuses.get(&c.id).map(String::as_str) == Some("online"))
+        .map(|c| c.id.as_str())
+        .collect();
+    let after = current.and_then(|id| online.iter().position(|&c| c == id)).map(|i| i + 1).unwrap_or(0);
+    online.get(after).or(online.first()).map(|id| id.to_string())
+}
+
+/// Step the montage to the next camera every `rotate_secs` until dismissed.
+async fn rotate(app: AppHandle, generation: u64, mut current: Option<String>) {
+    loop {
+        let rotate_secs = app.state::<crate::AppState>().config.lock()
+            .map(|c| c.screensaver.rotate_secs)
+            .unwrap_or(default_rotate_secs())
+            .max(1);
+        tokio::time::sleep(Duration::from_secs(rotate_secs)).await;
+        let state = app.state::<crate::AppState>();
+        if !state.screensaver.active.load(Ordering::Relaxed)
+            || state.screensaver.generation.load(Ordering::Relaxed) != generation
+        {
+            return;
+        }
+        current = next_camera(&app, current.as_deref());
+        let _ = app.emit("screensaver", event(&app, true, current.clone()));
+    }
+}
+
+/// Scheduler tick: start once the wall has been idle long enough. Not while
+/// streams are stopped for a dark day or off hours; the standby screen is up.
+pub fn check(app: &AppHandle) {
+    let state = app.state::<crate::AppState>();
+    let config = match state.config.lock() {
+        Ok(c) if c.screensaver.enabled => c.screensaver.clone(),
+        _ => return,
+    };
+    if state.screensaver.active.load(Ordering::Relaxed) || state.dark_day.load(Ordering::Relaxed) {
+        return;
+    }
+    let idle_ms = crate::unix_millis().saturating_sub(state.screensaver.last_activity.load(Ordering::Relaxed));
+    if idle_ms >= config.idle_minutes.max(1) * 60_000 {
+        start(app);
+    }
+}
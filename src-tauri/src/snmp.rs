@@ -0,0 +1,305 @@
+//! Minimal SNMPv2c agent codec: BER encoding/decoding for Get, GetNext,
+//! GetBulk, and SNMPv2-Trap PDUs. Read-only — Set requests are answered with
+//! `notWritable`. The MIB itself is built by the caller as a sorted map.
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+pub type Oid = Vec<u32>;
+
+/// `sysUpTime.0` and `snmpTrapOID.0`, the two mandatory trap varbinds.
+const SYS_UPTIME: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 3, 0];
+const SNMP_TRAP_OID: &[u32] = &[1, 3, 6, 1, 6, 3, 1, 1, 4, 1, 0];
+
+/// Most varbinds returned for one GetBulk request.
+const MAX_BULK_RESULTS: usize = 100;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_GAUGE32: u8 = 0x42;
+const TAG_TIMETICKS: u8 = 0x43;
+const TAG_COUNTER64: u8 = 0x46;
+const NO_SUCH_OBJECT: [u8; 2] = [0x80, 0x00];
+const END_OF_MIB_VIEW: [u8; 2] = [0x82, 0x00];
+
+const PDU_GET: u8 = 0xA0;
+const PDU_GET_NEXT: u8 = 0xA1;
+const PDU_RESPONSE: u8 = 0xA2;
+const PDU_SET: u8 = 0xA3;
+const PDU_GET_BULK: u8 = 0xA5;
+const PDU_TRAP_V2: u8 = 0xA7;
+
+const ERROR_NOT_WRITABLE: i64 = 17;
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Integer(i64),
+    OctetString(String),
+    ObjectId(Oid),
+    Gauge32(u32),
+    TimeTicks(u32), // Hundredths of a second
+    Counter64(u64),
+}
+
+impl Value {
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Value::Integer(v) => tlv(TAG_INTEGER, &encode_integer(*v)),
+            Value::OctetString(s) => tlv(TAG_OCTET_STRING, s.as_bytes()),
+            Value::ObjectId(oid) => tlv(TAG_OID, &encode_oid(oid)),
+            Value::Gauge32(v) => tlv(TAG_GAUGE32, &encode_unsigned(*v as u64)),
+            Value::TimeTicks(v) => tlv(TAG_TIMETICKS, &encode_unsigned(*v as u64)),
+            Value::Counter64(v) => tlv(TAG_COUNTER64, &encode_unsigned(*v)),
+        }
+    }
+}
+
+/// Parse dotted notation ("1.3.6.1.4.1.99999") into an OID.
+pub fn parse_oid(s: &str) -> Option<Oid> {
+    let oid: Option<Oid> = s.trim().trim_start_matches('.').split('.').map(|arc| arc.parse().ok()).collect();
+    oid.filter(|o| o.len() >= 2)
+}
+
+// ── Encoding ─────────────────────────────────────────────────────────────────
+
+fn encode_length(len: usize, out: &mut Vec<u8>) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+}
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    encode_length(content.len(), &mut out);
+    out.extend_from_slice(content);
+    out
+}
+
+/// Minimal two's-complement encoding.
+fn encode_integer(v: i64) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+/// Unsigned application types need a leading zero when the high bit is set.
+fn encode_unsigned(v: u64) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let start = bytes.iter().take_while(|b| **b == 0).count().min(7);
+    let mut out = Vec::with_capacity(9);
+    if bytes[start] & 0x80 != 0 {
+        out.push(0);
+    }
+    out.extend_from_slice(&bytes[start..]);
+    out
+}
+
+fn push_base128(mut v: u32, out: &mut Vec<u8>) {
+    let mut groups = vec![(v & 0x7f) as u8];
+    v >>= 7;
+    while v > 0 {
+        groups.push(0x80 | (v & 0x7f) as u8);
+        v >>= 7;
+    }
+    out.extend(groups.iter().rev());
+}
+
+fn encode_oid(oid: &[u32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    if oid.len() >= 2 {
+        push_base128(oid[0] * 40 + oid[1], &mut out);
+        for &arc in &oid[2..] {
+            push_base128(arc, &mut out);
+        }
+    }
+    out
+}
+
+fn encode_varbinds(varbinds: &[(Oid, Vec<u8>)]) -> Vec<u8> {
+    let mut content = Vec::new();
+    for (oid, value) in varbinds {
+        let mut vb = tlv(TAG_OID, &encode_oid(oid));
+        vb.extend_from_slice(value);
+        content.extend(tlv(TAG_SEQUENCE, &vb));
+    }
+    tlv(TAG_SEQUENCE, &content)
+}
+
+/// Wrap a PDU in a v2c message: SEQUENCE { version 1, community, pdu }.
+fn encode_message(community: &str, pdu_tag: u8, request_id: i64, error_status: i64, error_index: i64, varbinds: &[(Oid, Vec<u8>)]) -> Vec<u8> {
+    let mut pdu = tlv(TAG_INTEGER, &encode_integer(request_id));
+    pdu.extend(tlv(TAG_INTEGER, &encode_integer(error_status)));
+    pdu.extend(tlv(TAG_INTEGER, &encode_integer(error_index)));
+    pdu.extend(encode_varbinds(varbinds));
+
+    let mut message = tlv(TAG_INTEGER, &encode_integer(1));
+    message.extend(tlv(TAG_OCTET_STRING, community.as_bytes()));
+    message.extend(tlv(pdu_tag, &pdu));
+    tlv(TAG_SEQUENCE, &message)
+}
+
+// ── Decoding ─────────────────────────────────────────────────────────────────
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Next tag/content pair, or None at the end or on malformed input.
+    fn read_tlv(&mut self) -> Option<(u8, &'a [u8])> {
+        let tag = *self.data.get(self.pos)?;
+        let first = *self.data.get(self.pos + 1)? as usize;
+        let mut pos = self.pos + 2;
+        let len = if first & 0x80 == 0 {
+            first
+        } else {
+            let count = first & 0x7f;
+            if count == 0 || count > 4 {
+                return None;
+            }
+            let bytes = self.data.get(pos..pos + count)?;
+            pos += count;
+            bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize)
+        };
+        let content = self.data.get(pos..pos + len)?;
+        self.pos = pos + len;
+        Some((tag, content))
+    }
+}
+
+fn decode_integer(content: &[u8]) -> i64 {
+    let mut value: i64 = if content.first().map(|b| b & 0x80 != 0).unwrap_or(false) { -1 } else { 0 };
+    for b in content.iter().take(8) {
+        value = (value << 8) | *b as i64;
+    }
+    value
+}
+
+fn decode_oid(content: &[u8]) -> Oid {
+    let mut oid = Vec::new();
+    let mut arc: u32 = 0;
+    for b in content {
+        arc = (arc << 7) | (b & 0x7f) as u32;
+        if b & 0x80 == 0 {
+            if oid.is_empty() {
+                let first = (arc / 40).min(2);
+                oid.push(first);
+                oid.push(arc - first * 40);
+            } else {
+                oid.push(arc);
+            }
+            arc = 0;
+        }
+    }
+    oid
+}
+
+// ── Agent ────────────────────────────────────────────────────────────────────
+
+fn next_after(mib: &BTreeMap<Oid, Value>, oid: &Oid) -> (Oid, Vec<u8>) {
+    match mib.range((Bound::Excluded(oid.clone()), Bound::Unbounded)).next() {
+        Some((next, value)) => (next.clone(), value.encode()),
+        None => (oid.clone(), END_OF_MIB_VIEW.to_vec()),
+    }
+}
+
+/// Answer one v2c request datagram against `mib`. Returns None for packets
+/// that should be dropped silently (malformed, wrong version or community).
+pub fn handle_request(packet: &[u8], community: &str, mib: &BTreeMap<Oid, Value>) -> Option<Vec<u8>> {
+    let (tag, message) = Reader::new(packet).read_tlv()?;
+    if tag != TAG_SEQUENCE {
+        return None;
+    }
+    let mut m = Reader::new(message);
+    let version = decode_integer(m.read_tlv()?.1);
+    if version != 1 {
+        return None; // v2c only
+    }
+    if m.read_tlv()?.1 != community.as_bytes() {
+        return None;
+    }
+    let (pdu_tag, pdu) = m.read_tlv()?;
+    let mut p = Reader::new(pdu);
+    let request_id = decode_integer(p.read_tlv()?.1);
+    // For GetBulk these two fields are non-repeaters and max-repetitions
+    let non_repeaters = decode_integer(p.read_tlv()?.1).max(0) as usize;
+    let max_repetitions = decode_integer(p.read_tlv()?.1).max(0) as usize;
+    let (_, varbind_list) = p.read_tlv()?;
+
+    let mut oids = Vec::new();
+    let mut vr = Reader::new(varbind_list);
+    while let Some((_, varbind)) = vr.read_tlv() {
+        let (oid_tag, oid) = Reader::new(varbind).read_tlv()?;
+        if oid_tag != TAG_OID {
+            return None;
+        }
+        oids.push(decode_oid(oid));
+    }
+
+    let mut results: Vec<(Oid, Vec<u8>)> = Vec::new();
+    match pdu_tag {
+        PDU_GET => {
+            for oid in oids {
+                let value = mib.get(&oid).map(|v| v.encode()).unwrap_or_else(|| NO_SUCH_OBJECT.to_vec());
+                results.push((oid, value));
+            }
+        }
+        PDU_GET_NEXT => {
+            for oid in &oids {
+                results.push(next_after(mib, oid));
+            }
+        }
+        PDU_GET_BULK => {
+            let split = non_repeaters.min(oids.len());
+            for oid in &oids[..split] {
+                results.push(next_after(mib, oid));
+            }
+            let mut cursors: Vec<Oid> = oids[split..].to_vec();
+            'bulk: for _ in 0..max_repetitions {
+                for cursor in cursors.iter_mut() {
+                    if results.len() >= MAX_BULK_RESULTS {
+                        break 'bulk;
+                    }
+                    let (next, value) = next_after(mib, cursor);
+                    *cursor = next.clone();
+                    results.push((next, value));
+                }
+            }
+        }
+        PDU_SET => {
+            let varbinds: Vec<(Oid, Vec<u8>)> = oids.into_iter().map(|o| (o, vec![0x05, 0x00])).collect();
+            return Some(encode_message(community, PDU_RESPONSE, request_id, ERROR_NOT_WRITABLE, 1, &varbinds));
+        }
+        _ => return None,
+    }
+
+    Some(encode_message(community, PDU_RESPONSE, request_id, 0, 0, &results))
+}
+
+/// Build an SNMPv2-Trap datagram for `trap_oid` with the given extra varbinds.
+pub fn encode_trap(community: &str, request_id: i32, uptime_ticks: u32, trap_oid: &[u32], varbinds: &[(Oid, Value)]) -> Vec<u8> {
+    let mut all: Vec<(Oid, Vec<u8>)> = vec![
+        (SYS_UPTIME.to_vec(), Value::TimeTicks(uptime_ticks).encode()),
+        (SNMP_TRAP_OID.to_vec(), Value::ObjectId(trap_oid.to_vec()).encode()),
+    ];
+    all.extend(varbinds.iter().map(|(oid, value)| (oid.clone(), value.encode())));
+    encode_message(community, PDU_TRAP_V2, request_id as i64, 0, 0, &all)
+}
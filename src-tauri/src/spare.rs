@@ -0,0 +1,199 @@
+//! Hot spare: a standby StageView that mirrors a primary over its API, so it
+//! can take over if the primary machine dies mid-show. The spare polls the
+//! primary's `/api/replication` snapshot and applies its config (cameras,
+//! layout, rules, ...) and what the wall is showing (solo, page, PiP, audio).
+//! Settings that belong to the machine (API port and token, window, spare
+//! settings) stay the spare's own. The mirrored config is only held in
+//! memory until `promote`, which saves it and stops mirroring.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Longest a snapshot request may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpareConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub primary_url: String, // The primary's API, e.g. "http://10.0.0.5:8090"
+    #[serde(default)]
+    pub token: Option<String>, // The primary's api_token, if it has one
+    #[serde(default = "default_poll_secs")]
+    pub poll_secs: u64,
+}
+
+fn default_poll_secs() -> u64 { 2 }
+
+impl Default for SpareConfig {
+    fn default() -> Self {
+        Self { enabled: false, primary_url: String::new(), token: None, poll_secs: default_poll_secs() }
+    }
+}
+
+/// What a primary serves to its spares.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Snapshot {
+    pub config: crate::AppConfig,
+    pub view: Option<String>, // Soloed camera_id (None = grid)
+    pub page: usize, // 1-based
+    pub pip: Option<String>, // camera_id shown picture-in-picture
+    pub audio: Option<String>, // camera_id whose audio plays on the wall
+}
+
+#[derive(Deserialize)]
+struct SnapshotReply {
+    snapshot: Snapshot,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct SpareStatus {
+    pub mirroring: bool, // Following a primary right now
+    pub promoted: bool, // Took over this session
+    pub primary_url: String,
+    pub last_sync_ms: Option<u64>, // Unix ms of the last snapshot applied
+    pub last_error: Option<String>, // Why the last poll failed, while it keeps failing
+}
+
+/// This machine's state for `/api/replication`.
+pub fn snapshot(app: &AppHandle) -> Result<Snapshot, String> {
+    let state = app.state::<crate::AppState>();
+    let mut config = state.config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?
+        .clone();
+    // The spare keeps its own; no need to hand out the primary's token
+    config.api_token = None;
+    config.spare = SpareConfig::default();
+    Ok(Snapshot {
+        config,
+        view: state.solo_camera.lock().ok().and_then(|v| v.clone()),
+        page: state.current_page.load(Ordering::Relaxed) + 1,
+        pip: state.pip_camera.lock().ok().and_then(|p| p.clone()),
+        audio: state.monitor_camera.lock().ok().and_then(|m| m.clone()),
+    })
+}
+
+pub fn status(app: &AppHandle) -> SpareStatus {
+    app.state::<crate::AppState>().spare.lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+async fn fetch(config: &SpareConfig) -> Result<Snapshot, String> {
+    let base = config.primary_url.trim().trim_end_matches('/');
+    if base.is_empty() {
+        return Err("spare.primary_url is not set".to_string());
+    }
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.get(format!("{}/api/replication", base));
+    if let Some(token) = config.token.as_deref().filter(|t| !t.is_empty()) {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|e| format!("Primary {} unreachable: {}", base, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Primary {} answered {}", base, response.status()));
+    }
+    response.json::<SnapshotReply>().await
+        .map(|r| r.snapshot)
+        .map_err(|e| format!("Unexpected reply from {}: {}", base, e))
+}
+
+/// Make this machine match a snapshot, touching only what differs.
+fn apply(app: &AppHandle, snapshot: Snapshot) -> Result<(), String> {
+    let state = app.state::<crate::AppState>();
+    let current = state.config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?
+        .clone();
+    let mut config = snapshot.config;
+    crate::keep_machine_settings(&mut config, &current);
+    // Passwords aren't mirrored; apply_config takes them from the spare's own keychain
+    if serde_json::to_value(&config).ok() != serde_json::to_value(&current).ok() {
+        info!("Hot spare: applying the primary's config");
+        crate::apply_config(app, config)?;
+    }
+
+    if crate::view_state(app).camera_id != snapshot.view {
+        match snapshot.view.as_deref().and_then(|id| crate::camera_index(app, id)) {
+            Some(index) => crate::emit_remote_command(app, "solo", Some(index)),
+            None => crate::emit_remote_command(app, "grid", None),
+        }
+    }
+    // A page the spare can't show shouldn't hold up PiP and audio; it's reported after them
+    let mut paged = Ok(());
+    if state.current_page.load(Ordering::Relaxed) + 1 != snapshot.page {
+        paged = crate::go_to_page(app, crate::PageTarget::Number(snapshot.page))
+            .map(|_| ())
+            .map_err(|e| format!("Can't follow the primary to page {}: {}", snapshot.page, e));
+    }
+    let pip_changed = state.pip_camera.lock().map(|mut pip| {
+        let changed = *pip != snapshot.pip;
+        *pip = snapshot.pip;
+        changed
+    }).unwrap_or(false);
+    if pip_changed {
+        let _ = app.emit("pip-changed", crate::current_pip_state(&state)?);
+    }
+    if state.monitor_camera.lock().map(|m| *m != snapshot.audio).unwrap_or(false) {
+        crate::set_audio_monitor(app, snapshot.audio)?;
+    }
+    paged
+}
+
+fn update(app: &AppHandle, change: impl FnOnce(&mut SpareStatus)) {
+    if let Ok(mut status) = app.state::<crate::AppState>().spare.lock() {
+        change(&mut status);
+    }
+}
+
+/// Mirror the primary until promoted.
+pub async fn run(app: AppHandle, config: SpareConfig) {
+    info!("Hot spare: mirroring {} every {}s", config.primary_url, config.poll_secs.max(1));
+    update(&app, |s| {
+        s.mirroring = true;
+        s.primary_url = config.primary_url.clone();
+    });
+    loop {
+        let result = fetch(&config).await;
+        if status(&app).promoted {
+            return;
+        }
+        match result.and_then(|snapshot| apply(&app, snapshot)) {
+            Ok(()) => update(&app, |s| {
+                if s.last_error.take().is_some() {
+                    info!("Hot spare: primary reachable again");
+                }
+                s.last_sync_ms = Some(crate::unix_millis());
+            }),
+            Err(e) => update(&app, |s| {
+                if s.last_error.is_none() {
+                    warn!("Hot spare: {}", e);
+                }
+                s.last_error = Some(e);
+            }),
+        }
+        tokio::time::sleep(Duration::from_secs(config.poll_secs.max(1))).await;
+    }
+}
+
+/// Stop mirroring and take over: the mirrored config is saved as this
+/// machine's own, with spare mode off so a restart comes up as the primary.
+pub fn promote(app: &AppHandle) -> Result<SpareStatus, String> {
+    if !status(app).mirroring {
+        return Err("This machine is not mirroring a primary".to_string());
+    }
+    update(app, |s| {
+        s.mirroring = false;
+        s.promoted = true;
+    });
+    crate::edit_config(app, |config| {
+        config.spare.enabled = false;
+        Ok(())
+    }).map_err(|(_, e)| e)?;
+    warn!("Hot spare promoted: this machine is now the primary");
+    Ok(status(app))
+}
@@ -0,0 +1,273 @@
+//! Sync check: measure how far each camera lags the others, to set `delay_ms`.
+//! A `test://sync` camera flashes white and beeps once every `period` seconds.
+//! Shown on a screen (and speaker) the cameras can see and hear, each flash
+//! reaches StageView twice: straight from the generator, and through every
+//! camera. Each camera's flashes are timed against the generator's, and the
+//! spread between cameras becomes the suggested `delay_ms`.
+
+use serde::Serialize;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncBufReadExt;
+use tracing::{info, warn};
+
+/// Name of the test pattern that generates the reference signal.
+pub const SYNC_PATTERN: &str = "sync";
+
+/// Default flash interval; a camera must lag the reference by less than this.
+const DEFAULT_PERIOD_SECS: u64 = 2;
+
+/// Shortest and longest measurement.
+const MIN_SECS: u64 = 5;
+const MAX_SECS: u64 = 60;
+
+/// A camera needs this many flashes seen to be reported.
+const MIN_EDGES: usize = 2;
+
+/// Brightness (per frame) and loudness (per 10 ms of audio), printed to stderr.
+const VIDEO_FILTER: &str = r"signalstats,metadata=mode=print:key=lavfi.signalstats.YAVG:file=pipe\:2";
+const AUDIO_FILTER: &str = r"asetnsamples=n=480:p=0,astats=metadata=1:reset=1,ametadata=mode=print:key=lavfi.astats.Overall.RMS_level:file=pipe\:2";
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SyncMeasurement {
+    pub camera_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>, // Flash seen this long after the reference's, not counting delay_ms
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_latency_ms: Option<u64>, // Same for the beep, on cameras with audio_enabled
+    pub flashes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_delay_ms: Option<u64>, // delay_ms that lines it up with the slowest camera
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SyncReport {
+    pub reference_id: String,
+    pub period_ms: u64,
+    pub seconds: u64,
+    pub cameras: Vec<SyncMeasurement>,
+    pub applied: bool, // suggested_delay_ms was written to each camera's delay_ms
+}
+
+/// Times at which a source's picture turned bright and its audio turned loud
+/// (Unix ms, as the frames reached StageView).
+#[derive(Default)]
+struct Edges {
+    video: Vec<i64>,
+    audio: Vec<i64>,
+}
+
+/// Whether a URL is the sync reference generator.
+pub fn is_sync_source(url: &str) -> bool {
+    crate::TEST_PATTERN_PREFIXES.iter()
+        .filter_map(|p| url.strip_prefix(p))
+        .any(|rest| rest.split(['?', '/']).next().is_some_and(|n| n.eq_ignore_ascii_case(SYNC_PATTERN)))
+}
+
+/// Flash interval of a sync reference URL, in seconds (1-10).
+pub fn period_secs(url: &str) -> u64 {
+    let query = url.split_once('?').map(|(_, q)| q).unwrap_or("");
+    crate::query_param(query, "period").and_then(|p| p.parse::<u64>().ok())
+        .filter(|p| (1..=10).contains(p))
+        .unwrap_or(DEFAULT_PERIOD_SECS)
+}
+
+/// Lavfi graph for the reference: black with a 100 ms white flash and 1 kHz
+/// beep at the start of every period.
+pub fn reference_graph(size: &str, rate: u32, period: u64) -> String {
+    format!(
+        "color=c=black:size={size}:rate={rate},drawbox=c=white:t=fill:enable='lt(mod(t,{period}),0.1)'[out0];aevalsrc='0.5*sin(2*PI*1000*t)*lt(mod(t,{period}),0.1)':s=48000[out1]"
+    )
+}
+
+/// Rising edges through the midpoint of a series of (time, level) samples.
+/// A flat series (nothing flashed) has none.
+fn rising_edges(samples: &[(i64, f64)], min_swing: f64) -> Vec<i64> {
+    let low = samples.iter().map(|s| s.1).fold(f64::INFINITY, f64::min);
+    let high = samples.iter().map(|s| s.1).fold(f64::NEG_INFINITY, f64::max);
+    if high - low < min_swing {
+        return Vec::new();
+    }
+    let threshold = (low + high) / 2.0;
+    samples.windows(2)
+        .filter(|w| w[0].1 < threshold && w[1].1 >= threshold)
+        .map(|w| w[1].0)
+        .collect()
+}
+
+/// Watch one camera's stream through StageView for `seconds` and time its
+/// flashes and beeps. Frames arrive in bursts, one fragment at a time, so each
+/// frame is placed by its timestamp on the earliest arrival seen rather than by
+/// its own arrival.
+async fn watch(ffmpeg_path: &std::path::Path, api_port: u16, camera_id: &str, seconds: u64) -> Result<Edges, String> {
+    let input = format!("http://127.0.0.1:{}/camera/{}/stream", api_port, camera_id);
+    let duration = seconds.to_string();
+    let mut cmd = crate::hidden_command(ffmpeg_path);
+    cmd.args([
+        "-hide_banner", "-loglevel", "error", "-nostats",
+        "-i", input.as_str(),
+        "-t", duration.as_str(),
+        "-vf", VIDEO_FILTER,
+        "-af", AUDIO_FILTER,
+        "-f", "null", "-",
+    ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to start FFmpeg: {}", e))?;
+    let stderr = child.stderr.take().ok_or("FFmpeg has no stderr")?;
+    let mut lines = tokio::io::BufReader::new(stderr).lines();
+
+    // Each "frame:N pts:P pts_time:T" header is followed by its key=value line
+    let mut pts_ms: Option<f64> = None;
+    let mut offset: Option<f64> = None; // min(arrival - pts) over every frame
+    let (mut video, mut audio): (Vec<(f64, f64)>, Vec<(f64, f64)>) = (Vec::new(), Vec::new());
+    let mut last_error = None;
+    let read = async {
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(t) = line.split_whitespace().find_map(|f| f.strip_prefix("pts_time:")) {
+                pts_ms = t.parse::<f64>().ok().map(|t| t * 1000.0);
+                if let Some(pts) = pts_ms {
+                    let skew = crate::unix_millis() as f64 - pts;
+                    offset = Some(offset.map_or(skew, |o| o.min(skew)));
+                }
+            } else if let Some(value) = line.strip_prefix("lavfi.signalstats.YAVG=") {
+                if let (Some(pts), Ok(level)) = (pts_ms, value.trim().parse::<f64>()) {
+                    video.push((pts, level));
+                }
+            } else if let Some(value) = line.strip_prefix("lavfi.astats.Overall.RMS_level=") {
+                if let Some(pts) = pts_ms {
+                    // Silence is "-inf"
+                    audio.push((pts, value.trim().parse::<f64>().unwrap_or(-120.0).max(-120.0)));
+                }
+            } else if !line.trim().is_empty() {
+                last_error = Some(line);
+            }
+        }
+    };
+    // Time to connect and probe, on top of the watch itself
+    let _ = tokio::time::timeout(Duration::from_secs(seconds + 15), read).await;
+    let _ = child.kill().await;
+
+    let offset = offset.ok_or_else(|| format!("No frames received{}", last_error.map(|e| format!(": {}", e)).unwrap_or_default()))?;
+    let place = |samples: &[(f64, f64)]| samples.iter().map(|&(pts, level)| ((pts + offset) as i64, level)).collect::<Vec<_>>();
+    Ok(Edges {
+        video: rising_edges(&place(&video), 40.0),
+        audio: rising_edges(&place(&audio), 20.0),
+    })
+}
+
+/// Median lag of each edge behind the latest reference edge before it.
+fn lag(edges: &[i64], reference: &[i64], period_ms: i64) -> Option<u64> {
+    let mut lags: Vec<i64> = edges.iter()
+        .filter_map(|&e| reference.iter().rev().find(|&&r| r <= e).map(|&r| e - r))
+        .filter(|&l| l < period_ms)
+        .collect();
+    if lags.len() < MIN_EDGES {
+        return None;
+    }
+    lags.sort_unstable();
+    Some(lags[lags.len() / 2] as u64)
+}
+
+/// Measure every enabled camera against the reference (`reference_id`, or the
+/// first `test://sync` camera) for `seconds`. With `apply`, each measured
+/// camera's `delay_ms` is set to its suggestion.
+pub async fn measure(app: &AppHandle, reference_id: Option<&str>, seconds: u64, apply: bool) -> Result<SyncReport, String> {
+    let seconds = seconds.clamp(MIN_SECS, MAX_SECS);
+    let state = app.state::<crate::AppState>();
+    let (reference, cameras, api_port) = {
+        let config = state.config.lock()
+            .map_err(|_| "Config mutex poisoned - please restart application".to_string())?;
+        let reference = match reference_id {
+            Some(id) => config.cameras.iter().find(|c| c.id == id).ok_or_else(|| format!("Unknown camera {}", id))?,
+            None => config.cameras.iter().find(|c| c.enabled && is_sync_source(&c.url))
+                .ok_or("Add a camera with the URL test://sync and show it where the cameras can see it")?,
+        }.clone();
+        let cameras: Vec<crate::Camera> = config.cameras.iter()
+            .filter(|c| c.enabled && c.id != reference.id && !crate::is_test_pattern(&c.url))
+            .cloned()
+            .collect();
+        (reference, cameras, config.api_port)
+    };
+    if cameras.is_empty() {
+        return Err("No cameras to measure".to_string());
+    }
+    let period_ms = period_secs(&reference.url) as i64 * 1000;
+    info!("Sync check: {} camera(s) against {} for {}s", cameras.len(), reference.id, seconds);
+
+    // Watch the reference and every camera at once, so their edges share a clock
+    let ffmpeg_path = state.ffmpeg_path.clone();
+    let watches = std::iter::once(&reference).chain(&cameras).map(|c| {
+        let (path, id) = (ffmpeg_path.clone(), c.id.clone());
+        tauri::async_runtime::spawn(async move { watch(&path, api_port, &id, seconds).await })
+    }).collect::<Vec<_>>();
+    let mut results = Vec::with_capacity(watches.len());
+    for handle in watches {
+        results.push(handle.await.map_err(|e| e.to_string()).and_then(|r| r));
+    }
+    let mut results = results.into_iter();
+    let reference_edges = results.next().unwrap_or_else(|| Err("Reference not watched".to_string()))
+        .map_err(|e| format!("Reference {}: {}", reference.id, e))?;
+    if reference_edges.video.len() < MIN_EDGES {
+        return Err(format!("Reference {} showed no flashes; is its URL test://sync?", reference.id));
+    }
+
+    let mut measurements: Vec<SyncMeasurement> = cameras.iter().zip(results).map(|(camera, result)| {
+        let mut m = SyncMeasurement {
+            camera_id: camera.id.clone(),
+            name: camera.name.clone(),
+            latency_ms: None,
+            audio_latency_ms: None,
+            flashes: 0,
+            suggested_delay_ms: None,
+            error: None,
+        };
+        match result {
+            Ok(edges) => {
+                m.flashes = edges.video.len();
+                // The camera's current delay_ms is part of what was measured
+                m.latency_ms = lag(&edges.video, &reference_edges.video, period_ms).map(|l| l.saturating_sub(camera.delay_ms));
+                m.audio_latency_ms = lag(&edges.audio, &reference_edges.audio, period_ms).map(|l| l.saturating_sub(camera.delay_ms));
+                if m.latency_ms.is_none() {
+                    m.error = Some("No flashes seen; point the camera at the sync reference".to_string());
+                }
+            }
+            Err(e) => m.error = Some(e),
+        }
+        m
+    }).collect();
+
+    let slowest = measurements.iter().filter_map(|m| m.latency_ms).max().unwrap_or(0);
+    for m in &mut measurements {
+        m.suggested_delay_ms = m.latency_ms.map(|l| (slowest - l).min(crate::MAX_DISPLAY_DELAY_MS));
+    }
+    for m in &measurements {
+        match (&m.latency_ms, &m.error) {
+            (Some(l), _) => info!("Sync check: camera {} lags the reference by {} ms", m.camera_id, l),
+            (None, Some(e)) => warn!("Sync check: camera {}: {}", m.camera_id, e),
+            (None, None) => {}
+        }
+    }
+
+    let applied = apply && measurements.iter().any(|m| m.suggested_delay_ms.is_some());
+    if applied {
+        crate::edit_config(app, |config| {
+            for camera in &mut config.cameras {
+                if let Some(delay) = measurements.iter().find(|m| m.camera_id == camera.id).and_then(|m| m.suggested_delay_ms) {
+                    camera.delay_ms = delay;
+                }
+            }
+            Ok(())
+        }).map_err(|(_, e)| e)?;
+        info!("Sync check: delay_ms applied");
+    }
+
+    Ok(SyncReport { reference_id: reference.id, period_ms: period_ms as u64, seconds, cameras: measurements, applied })
+}
@@ -0,0 +1,251 @@
+//! Push-to-talk to cameras with a speaker, over the ONVIF audio backchannel
+//! (Profile T). StageView opens the camera's RTSP backchannel, captures the
+//! operator's microphone with FFmpeg, and sends it as G.711 RTP interleaved on
+//! the RTSP connection — e.g. paging a camera operator from FOH. One talkback
+//! runs at a time; starting another ends the first.
+
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncReadExt;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+/// ONVIF Profile T option tag that makes a camera describe its backchannel.
+const BACKCHANNEL_REQUIRE: (&str, &str) = ("Require", "www.onvif.org/ver20/backchannel");
+
+/// RTSP request timeout while setting up.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Audio per RTP packet: 20 ms of 8 kHz G.711.
+const PACKET_SAMPLES: usize = 160;
+
+/// Keeps the RTSP session alive while talking.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(20);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TalkbackConfig {
+    /// FFmpeg input format for the microphone (None = dshow on Windows,
+    /// avfoundation on macOS, pulse on Linux).
+    #[serde(default)]
+    pub input_format: Option<String>,
+    /// FFmpeg input device (None = ":0" on macOS, "default" on Linux; Windows
+    /// needs the DirectShow name, e.g. "audio=Microphone (USB Audio)").
+    #[serde(default)]
+    pub input_device: Option<String>,
+    #[serde(default = "default_max_secs")]
+    pub max_secs: u64, // Talkback ends on its own after this long, in case the release is missed
+}
+
+fn default_max_secs() -> u64 { 60 }
+
+impl Default for TalkbackConfig {
+    fn default() -> Self {
+        Self { input_format: None, input_device: None, max_secs: default_max_secs() }
+    }
+}
+
+/// The running talkback; dropping `stop` ends it.
+pub struct ActiveTalkback {
+    camera_id: String,
+    stop: oneshot::Sender<()>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct TalkbackEvent {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Which camera is being talked to, if any.
+pub fn status(app: &AppHandle) -> TalkbackEvent {
+    let camera_id = app.state::<crate::AppState>().talkback.lock().ok()
+        .and_then(|t| t.as_ref().filter(|t| !t.stop.is_closed()).map(|t| t.camera_id.clone()));
+    TalkbackEvent { active: camera_id.is_some(), camera_id, error: None }
+}
+
+/// Start talking to a camera (ending any other talkback), or stop.
+pub fn set(app: &AppHandle, camera_id: &str, active: bool) -> Result<TalkbackEvent, String> {
+    let state = app.state::<crate::AppState>();
+    let (url, config) = {
+        let config = state.config.lock()
+            .map_err(|_| "Config mutex poisoned - please restart application".to_string())?;
+        let camera = config.cameras.iter().find(|c| c.id == camera_id)
+            .ok_or_else(|| format!("Unknown camera {}", camera_id))?;
+        (crate::credentials::connect_url(camera), config.talkback.clone())
+    };
+    let ffmpeg_path = state.ffmpeg_path.clone();
+    let mut current = state.talkback.lock().map_err(|_| "Talkback mutex poisoned".to_string())?;
+    if !active {
+        if current.as_ref().is_some_and(|t| t.camera_id == camera_id) {
+            *current = None; // Dropping the sender stops the session
+        }
+        drop(current);
+        return Ok(status(app));
+    }
+    if current.as_ref().is_some_and(|t| t.camera_id == camera_id && !t.stop.is_closed()) {
+        drop(current);
+        return Ok(status(app));
+    }
+    if !url.to_ascii_lowercase().starts_with("rtsp://") {
+        return Err("Talkback needs an rtsp:// camera with an ONVIF audio backchannel".to_string());
+    }
+
+    let (stop, stopped) = oneshot::channel();
+    *current = Some(ActiveTalkback { camera_id: camera_id.to_string(), stop });
+    drop(current);
+
+    let event = TalkbackEvent { active: true, camera_id: Some(camera_id.to_string()), error: None };
+    let _ = app.emit("talkback", event.clone());
+    info!("Talkback to camera {} started", camera_id);
+
+    let app = app.clone();
+    let camera_id = camera_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let result = session(&url, &config, &ffmpeg_path, stopped).await;
+        if let Err(e) = &result {
+            warn!("Talkback to camera {} failed: {}", camera_id, e);
+        } else {
+            info!("Talkback to camera {} ended", camera_id);
+        }
+        if let Ok(mut current) = app.state::<crate::AppState>().talkback.lock() {
+            // Our receiver is gone now, so a closed sender is this session's
+            if current.as_ref().is_some_and(|t| t.stop.is_closed()) {
+                *current = None;
+            }
+        }
+        let _ = app.emit("talkback", TalkbackEvent { active: false, camera_id: Some(camera_id), error: result.err() });
+    });
+    Ok(event)
+}
+
+/// FFmpeg arguments capturing the microphone as raw G.711 at 8 kHz mono.
+fn capture_args(config: &TalkbackConfig, codec: &str) -> Result<Vec<String>, String> {
+    let (default_format, default_device) = if cfg!(windows) {
+        ("dshow", None)
+    } else if cfg!(target_os = "macos") {
+        ("avfoundation", Some(":0"))
+    } else {
+        ("pulse", Some("default"))
+    };
+    let format = config.input_format.clone().unwrap_or_else(|| default_format.to_string());
+    let device = config.input_device.clone().or(default_device.map(str::to_string))
+        .ok_or("Set talkback.input_device to the microphone, e.g. \"audio=Microphone (USB Audio)\"")?;
+    let output = match codec {
+        "PCMU" => "mulaw",
+        "PCMA" => "alaw",
+        other => return Err(format!("Camera backchannel wants {}; only G.711 (PCMU/PCMA) is supported", other)),
+    };
+    Ok([
+        "-hide_banner", "-loglevel", "error", "-fflags", "nobuffer", "-f", format.as_str(), "-i", device.as_str(),
+        "-ac", "1", "-ar", "8000", "-f", output, "pipe:1",
+    ].iter().map(|a| a.to_string()).collect())
+}
+
+/// Set up the backchannel and stream the microphone until `stopped` fires
+/// (or its sender is dropped), `max_secs` passes, or either side fails.
+async fn session(url: &str, config: &TalkbackConfig, ffmpeg_path: &std::path::Path, mut stopped: oneshot::Receiver<()>) -> Result<(), String> {
+    let mut rtsp = crate::rtsp::RtspConnection::connect(url, REQUEST_TIMEOUT).await?;
+    let base = rtsp.url().to_string();
+    let describe = rtsp.request("DESCRIBE", &base, &[BACKCHANNEL_REQUIRE]).await?;
+    if describe.status != 200 {
+        return Err(format!("DESCRIBE returned {}", describe.status));
+    }
+    let content_base = describe.headers.get("content-base").cloned().unwrap_or(base);
+    let backchannel = crate::rtsp::sdp_backchannel(&describe.body, &content_base)
+        .ok_or("Camera offers no audio backchannel (ONVIF Profile T)")?;
+    let args = capture_args(config, &backchannel.codec)?;
+
+    let setup = rtsp.request("SETUP", &backchannel.control, &[
+        BACKCHANNEL_REQUIRE,
+        ("Transport", "RTP/AVP/TCP;unicast;interleaved=0-1"),
+    ]).await?;
+    if setup.status != 200 {
+        return Err(format!("SETUP returned {}", setup.status));
+    }
+    // The camera may pick different channels than asked for
+    let channel = setup.headers.get("transport")
+        .and_then(|t| t.split(';').find_map(|p| p.trim().strip_prefix("interleaved=")))
+        .and_then(|c| c.split('-').next()?.parse().ok())
+        .unwrap_or(0u8);
+    let play = rtsp.request("PLAY", &content_base, &[BACKCHANNEL_REQUIRE, ("Range", "npt=0.000-")]).await?;
+    if play.status != 200 {
+        return Err(format!("PLAY returned {}", play.status));
+    }
+
+    let mut cmd = crate::hidden_command(ffmpeg_path);
+    cmd.args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    let mut capture = cmd.spawn().map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+    let mut microphone = capture.stdout.take().ok_or("FFmpeg stdout unavailable")?;
+
+    let ssrc: u32 = rand::random();
+    let mut sequence: u16 = rand::random();
+    let mut timestamp: u32 = rand::random();
+    let mut chunk = [0u8; 4096];
+    let mut incoming = [0u8; 4096];
+    let mut samples: Vec<u8> = Vec::new();
+    let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+    keepalive.tick().await;
+    let deadline = tokio::time::sleep(Duration::from_secs(config.max_secs.max(1)));
+    tokio::pin!(deadline);
+    let mut first = true;
+
+    let result = loop {
+        tokio::select! {
+            _ = &mut stopped => break Ok(()),
+            _ = &mut deadline => break Ok(()),
+            _ = keepalive.tick() => {
+                if let Err(e) = rtsp.send("GET_PARAMETER", &content_base).await {
+                    break Err(e);
+                }
+            }
+            // Keepalive responses and the camera's RTCP, read only so its sends never block
+            read = rtsp.read_incoming(&mut incoming) => {
+                if !matches!(read, Ok(n) if n > 0) {
+                    break Err("Camera closed the backchannel connection".to_string());
+                }
+            }
+            // read() rather than read_exact(), so the other branches can't cut a read short
+            read = microphone.read(&mut chunk) => {
+                let n = match read {
+                    Ok(n) if n > 0 => n,
+                    _ => break Err("Microphone capture stopped; check talkback.input_format and input_device".to_string()),
+                };
+                samples.extend_from_slice(&chunk[..n]);
+                let mut failed = None;
+                while samples.len() >= PACKET_SAMPLES {
+                    let mut packet = Vec::with_capacity(12 + PACKET_SAMPLES);
+                    // RTP header: version 2, marker on the first packet of the talkspurt
+                    packet.push(0x80);
+                    packet.push(backchannel.payload_type | if first { 0x80 } else { 0 });
+                    packet.extend_from_slice(&sequence.to_be_bytes());
+                    packet.extend_from_slice(&timestamp.to_be_bytes());
+                    packet.extend_from_slice(&ssrc.to_be_bytes());
+                    packet.extend(samples.drain(..PACKET_SAMPLES));
+                    if let Err(e) = rtsp.write_interleaved(channel, &packet).await {
+                        failed = Some(e);
+                        break;
+                    }
+                    first = false;
+                    sequence = sequence.wrapping_add(1);
+                    timestamp = timestamp.wrapping_add(PACKET_SAMPLES as u32);
+                }
+                if let Some(e) = failed {
+                    break Err(e);
+                }
+            }
+        }
+    };
+    let _ = capture.kill().await;
+    let _ = rtsp.send("TEARDOWN", &content_base).await;
+    result
+}
@@ -0,0 +1,266 @@
+//! TSL UMD tally input. Vision switchers publish each source's tally over
+//! TSL 3.1 or 5.0; when a source mapped to a camera goes to program (red
+//! tally), StageView solos that camera so the wall follows the switcher.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tracing::{debug, error, info};
+
+/// TSL 3.1 messages are a fixed 18 bytes: address, control, 16 characters of text.
+const TSL3_MESSAGE_LEN: usize = 18;
+
+/// TSL 5.0 over TCP starts each packet with DLE STX, and a DLE inside the packet is sent twice.
+const DLE: u8 = 0xFE;
+const STX: u8 = 0x02;
+
+/// TSL 5.0 index addressed to every display at once; says nothing about a single source.
+const TSL5_BROADCAST: u16 = 0xFFFF;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TallyProtocol {
+    Tsl3,
+    #[default]
+    Tsl5,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TallyTransport {
+    #[default]
+    Udp,
+    Tcp, // StageView listens; the switcher connects as a client
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TallyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub protocol: TallyProtocol,
+    #[serde(default)]
+    pub transport: TallyTransport,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub map: BTreeMap<u16, usize>, // TSL address → camera number (1-based); empty means address N is camera N
+}
+
+fn default_port() -> u16 { 8900 }
+
+impl Default for TallyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            protocol: TallyProtocol::default(),
+            transport: TallyTransport::default(),
+            port: default_port(),
+            map: BTreeMap::new(),
+        }
+    }
+}
+
+/// One source's program state as carried in a packet.
+struct Tally {
+    address: u16,
+    program: bool,
+}
+
+/// TSL 3.1: bit 7 of the first byte marks a message, the low 7 bits are the
+/// address, and tally 1 (bit 0 of the control byte) is program by convention.
+fn parse_tsl3(data: &[u8]) -> Vec<Tally> {
+    data.chunks_exact(TSL3_MESSAGE_LEN)
+        .filter(|m| m[0] & 0x80 != 0)
+        .map(|m| Tally { address: (m[0] & 0x7F) as u16, program: m[1] & 0x01 != 0 })
+        .collect()
+}
+
+/// TSL 5.0: PBC(2) VER(1) FLAGS(1) SCREEN(2), then display messages of
+/// INDEX(2) CONTROL(2) LENGTH(2) TEXT, all little-endian. A source is on
+/// program if any of its three lamps (right, text, left) is red.
+fn parse_tsl5(packet: &[u8]) -> Vec<Tally> {
+    let mut tallies = Vec::new();
+    if packet.len() < 6 {
+        return tallies;
+    }
+    let end = (u16::from_le_bytes([packet[0], packet[1]]) as usize + 2).min(packet.len());
+    let mut pos = 6;
+    while pos + 6 <= end {
+        let index = u16::from_le_bytes([packet[pos], packet[pos + 1]]);
+        let control = u16::from_le_bytes([packet[pos + 2], packet[pos + 3]]);
+        let length = u16::from_le_bytes([packet[pos + 4], packet[pos + 5]]) as usize;
+        pos += 6 + length;
+        if control & 0x8000 != 0 || index == TSL5_BROADCAST {
+            continue; // Control data rather than a display message
+        }
+        let red = [control & 0x03, (control >> 2) & 0x03, (control >> 4) & 0x03].contains(&1);
+        tallies.push(Tally { address: index, program: red });
+    }
+    tallies
+}
+
+/// Reassembles TSL 5.0 packets from a TCP byte stream. There is no end
+/// marker, so a packet is complete once its byte count (PBC) has arrived.
+#[derive(Default)]
+struct Tsl5Framer {
+    packet: Vec<u8>,
+    in_packet: bool,
+    escape: bool,
+}
+
+impl Tsl5Framer {
+    fn push(&mut self, byte: u8) -> Option<Vec<u8>> {
+        if self.escape {
+            self.escape = false;
+            match byte {
+                STX => {
+                    self.packet.clear();
+                    self.in_packet = true;
+                    return None;
+                }
+                DLE => {} // Stuffed DLE: a literal 0xFE
+                _ => {
+                    self.in_packet = false; // Out of sync; wait for the next DLE STX
+                    return None;
+                }
+            }
+        } else if byte == DLE {
+            self.escape = true;
+            return None;
+        }
+        if !self.in_packet {
+            return None;
+        }
+        self.packet.push(byte);
+        if self.packet.len() >= 2 && self.packet.len() == u16::from_le_bytes([self.packet[0], self.packet[1]]) as usize + 2 {
+            self.in_packet = false;
+            return Some(std::mem::take(&mut self.packet));
+        }
+        None
+    }
+}
+
+/// Remembers which sources are on program, so a camera is soloed once when it
+/// goes on air rather than on every repeated packet.
+#[derive(Default)]
+struct Tracker {
+    on_air: HashMap<u16, bool>,
+}
+
+impl Tracker {
+    fn apply(&mut self, app: &AppHandle, config: &TallyConfig, tallies: Vec<Tally>) {
+        for tally in tallies {
+            let was_on_air = self.on_air.insert(tally.address, tally.program).unwrap_or(false);
+            if tally.program && !was_on_air {
+                solo(app, config, tally.address);
+            }
+        }
+    }
+}
+
+fn solo(app: &AppHandle, config: &TallyConfig, address: u16) {
+    let camera = match config.map.get(&address) {
+        Some(&camera) => camera,
+        None if config.map.is_empty() => address as usize,
+        None => return, // Unmapped source, e.g. graphics or a VT
+    };
+    let camera_id = app.state::<crate::AppState>().config.lock().ok()
+        .and_then(|c| c.cameras.get(camera.wrapping_sub(1)).map(|cam| cam.id.clone()));
+    let Some(camera_id) = camera_id else {
+        debug!("Tally: source {} maps to camera {}, which isn't configured", address, camera);
+        return;
+    };
+    info!("Tally: source {} on program, soloing camera {}", address, camera);
+    // Rules first, so a tally rule remembers the view from before this solo to go back to
+    crate::rules::fire(app, crate::rules::Trigger::Tally, &camera_id);
+    crate::emit_remote_command(app, "solo", Some(camera));
+}
+
+/// Listen for tally packets on the configured port until the app exits.
+pub async fn run(app: AppHandle, config: TallyConfig) {
+    match config.transport {
+        TallyTransport::Udp => run_udp(app, config).await,
+        TallyTransport::Tcp => run_tcp(app, config).await,
+    }
+}
+
+async fn run_udp(app: AppHandle, config: TallyConfig) {
+    let socket = match UdpSocket::bind(("0.0.0.0", config.port)).await {
+        Ok(s) => {
+            info!("Tally: listening for {:?} on udp/{}", config.protocol, config.port);
+            s
+        }
+        Err(e) => {
+            error!("Tally: failed to bind udp/{}: {}", config.port, e);
+            return;
+        }
+    };
+    let mut tracker = Tracker::default();
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let Ok((n, _)) = socket.recv_from(&mut buf).await else { continue };
+        let tallies = match config.protocol {
+            TallyProtocol::Tsl3 => parse_tsl3(&buf[..n]),
+            TallyProtocol::Tsl5 => parse_tsl5(&buf[..n]),
+        };
+        tracker.apply(&app, &config, tallies);
+    }
+}
+
+async fn run_tcp(app: AppHandle, config: TallyConfig) {
+    let listener = match TcpListener::bind(("0.0.0.0", config.port)).await {
+        Ok(l) => {
+            info!("Tally: listening for {:?} on tcp/{}", config.protocol, config.port);
+            l
+        }
+        Err(e) => {
+            error!("Tally: failed to bind tcp/{}: {}", config.port, e);
+            return;
+        }
+    };
+    loop {
+        let Ok((stream, peer)) = listener.accept().await else { continue };
+        info!("Tally: switcher connected from {}", peer);
+        let app = app.clone();
+        let config = config.clone();
+        tokio::spawn(async move {
+            serve_tcp(&app, &config, stream).await;
+            info!("Tally: switcher {} disconnected", peer);
+        });
+    }
+}
+
+async fn serve_tcp(app: &AppHandle, config: &TallyConfig, mut stream: TcpStream) {
+    let mut tracker = Tracker::default();
+    let mut tsl3 = Vec::new();
+    let mut tsl5 = Tsl5Framer::default();
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let n = match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        match config.protocol {
+            TallyProtocol::Tsl3 => {
+                tsl3.extend_from_slice(&buf[..n]);
+                // Text and control bytes never have bit 7 set, so an address byte marks a message start
+                let start = tsl3.iter().position(|b| b & 0x80 != 0).unwrap_or(tsl3.len());
+                tsl3.drain(..start);
+                let complete = tsl3.len() - tsl3.len() % TSL3_MESSAGE_LEN;
+                let tallies = parse_tsl3(&tsl3[..complete]);
+                tsl3.drain(..complete);
+                tracker.apply(app, config, tallies);
+            }
+            TallyProtocol::Tsl5 => {
+                for &byte in &buf[..n] {
+                    if let Some(packet) = tsl5.push(byte) {
+                        tracker.apply(app, config, parse_tsl5(&packet));
+                    }
+                }
+            }
+        }
+    }
+}
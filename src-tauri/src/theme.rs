@@ -0,0 +1,140 @@
+//! Tile theming: status colors, tile borders, and how offline tiles look, so
+//! a venue can match its house style and operators who can't tell red from
+//! green can pick a palette they can. The wall, the browser viewer, and the
+//! control panel all read the resolved theme from `get_theme` / `/api/theme`
+//! and follow `theme-changed`. Colors end up in CSS, so only hex colors and
+//! plain color names are accepted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+/// Thickest tile border allowed, in pixels.
+const MAX_BORDER_WIDTH: u32 = 24;
+
+/// Status colors to start from; any of them can be overridden.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    #[default]
+    Standard, // Green, amber, red
+    ColorBlind, // Okabe-Ito sky blue, yellow, vermillion: apart for every common color vision deficiency
+    HighContrast, // Saturated colors that stand out on a dim wall or through a camera
+}
+
+impl Palette {
+    /// Online, connecting, and offline colors.
+    fn colors(self) -> [&'static str; 3] {
+        match self {
+            Palette::Standard => ["#22c55e", "#ffa500", "#ef4444"],
+            Palette::ColorBlind => ["#56b4e9", "#f0e442", "#d55e00"],
+            Palette::HighContrast => ["#00ff66", "#ffd400", "#ff2d55"],
+        }
+    }
+}
+
+/// What an offline tile looks like besides its status dot and border.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OfflineTile {
+    #[default]
+    Normal, // Last frame stays as it was
+    Dim,
+    Grayscale,
+    Hatched, // Diagonal stripes: readable without telling colors apart
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub palette: Palette,
+    #[serde(default)]
+    pub online: Option<String>, // Overrides the palette's color, e.g. "#00a3e0"
+    #[serde(default)]
+    pub connecting: Option<String>,
+    #[serde(default)]
+    pub offline: Option<String>,
+    #[serde(default)]
+    pub border_width: u32, // Tile border in px (0 = none)
+    #[serde(default)]
+    pub border_color: Option<String>, // None = the tile's status color
+    #[serde(default)]
+    pub offline_tile: OfflineTile,
+}
+
+/// The theme as clients apply it, with every color worked out.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub palette: Palette,
+    pub online: String,
+    pub connecting: String,
+    pub offline: String,
+    pub border_width: u32,
+    pub border_color: Option<String>, // None = the tile's status color
+    pub offline_tile: OfflineTile,
+    pub camera_borders: BTreeMap<String, String>, // camera_id -> its own `border_color`
+}
+
+/// `#rgb`, `#rrggbb`, `#rrggbbaa`, or a CSS color name like "orange".
+fn is_css_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !color.is_empty() && color.len() <= 32 && color.chars().all(|c| c.is_ascii_alphabetic()),
+    }
+}
+
+/// Why a theme can't be saved, naming the setting.
+fn validate(theme: &ThemeConfig) -> Result<(), String> {
+    let colors = [("online", &theme.online), ("connecting", &theme.connecting), ("offline", &theme.offline), ("border_color", &theme.border_color)];
+    for (field, color) in colors {
+        if let Some(color) = color.as_deref().filter(|c| !is_css_color(c)) {
+            return Err(format!("{}: {:?} is not a color; use #rrggbb or a color name", field, color));
+        }
+    }
+    if theme.border_width > MAX_BORDER_WIDTH {
+        return Err(format!("border_width: at most {} px", MAX_BORDER_WIDTH));
+    }
+    Ok(())
+}
+
+/// Work out the theme for `config`. Colors that aren't valid (e.g. from a
+/// hand edit) fall back to the palette's.
+pub fn resolve(config: &crate::AppConfig) -> Theme {
+    let theme = &config.theme;
+    let [online, connecting, offline] = theme.palette.colors();
+    let pick = |color: &Option<String>, default: &str| {
+        color.as_deref().filter(|c| is_css_color(c)).unwrap_or(default).to_string()
+    };
+    Theme {
+        palette: theme.palette,
+        online: pick(&theme.online, online),
+        connecting: pick(&theme.connecting, connecting),
+        offline: pick(&theme.offline, offline),
+        border_width: theme.border_width.min(MAX_BORDER_WIDTH),
+        border_color: theme.border_color.clone().filter(|c| is_css_color(c)),
+        offline_tile: theme.offline_tile,
+        camera_borders: config.cameras.iter()
+            .filter_map(|c| Some((c.id.clone(), c.border_color.clone().filter(|b| is_css_color(b))?)))
+            .collect(),
+    }
+}
+
+pub fn get(app: &AppHandle) -> Result<Theme, String> {
+    let config = app.state::<crate::AppState>().config.lock()
+        .map_err(|_| "Config mutex poisoned - please restart application".to_string())?;
+    Ok(resolve(&config))
+}
+
+/// Save a new theme. Applying the config pushes it to the wall and browser
+/// clients as `theme-changed`.
+pub fn set(app: &AppHandle, theme: ThemeConfig) -> Result<Theme, String> {
+    validate(&theme)?;
+    crate::edit_config(app, |config| {
+        config.theme = theme;
+        Ok(())
+    }).map_err(|(_, e)| e)?;
+    let resolved = get(app)?;
+    info!("Theme set: {:?} palette, {}px borders, offline tiles {:?}", resolved.palette, resolved.border_width, resolved.offline_tile);
+    Ok(resolved)
+}
@@ -0,0 +1,161 @@
+//! H.264 encoder selection for cameras that need transcoding. `detect` asks
+//! FFmpeg which H.264 encoders it was built with, then runs a one-frame test
+//! encode with each hardware candidate — an encoder being compiled in says
+//! nothing about whether the GPU and driver are actually present. The first
+//! one that works is used instead of libx264.
+
+use serde::Serialize;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+
+/// Longest a single test encode may take before the encoder is ruled out.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Device used for VAAPI encodes (first render node).
+const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoder {
+    Nvenc,
+    Qsv,
+    Vaapi,
+    VideoToolbox,
+    Software,
+}
+
+/// Hardware encoders in order of preference for this platform.
+#[cfg(target_os = "macos")]
+const HARDWARE: &[Encoder] = &[Encoder::VideoToolbox];
+#[cfg(not(target_os = "macos"))]
+const HARDWARE: &[Encoder] = &[Encoder::Nvenc, Encoder::Qsv, Encoder::Vaapi];
+
+impl Encoder {
+    pub fn ffmpeg_name(self) -> &'static str {
+        match self {
+            Encoder::Nvenc => "h264_nvenc",
+            Encoder::Qsv => "h264_qsv",
+            Encoder::Vaapi => "h264_vaapi",
+            Encoder::VideoToolbox => "h264_videotoolbox",
+            Encoder::Software => "libx264",
+        }
+    }
+
+    /// Device setup that has to precede the input.
+    pub fn input_args(self) -> Vec<String> {
+        match self {
+            Encoder::Vaapi => vec!["-vaapi_device".to_string(), VAAPI_DEVICE.to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Output-side args for a low-latency 8-bit 4:2:0 H.264 encode that MSE can play.
+    pub fn video_args(self) -> Vec<String> {
+        let args: &[&str] = match self {
+            Encoder::Nvenc => &["-c:v", "h264_nvenc", "-preset", "p2", "-tune", "ll", "-pix_fmt", "yuv420p"],
+            Encoder::Qsv => &["-c:v", "h264_qsv", "-preset", "veryfast", "-pix_fmt", "nv12"],
+            // Frames are decoded in software, so upload them to the GPU first
+            Encoder::Vaapi => &["-vf", "format=nv12,hwupload", "-c:v", "h264_vaapi"],
+            Encoder::VideoToolbox => &["-c:v", "h264_videotoolbox", "-realtime", "1", "-pix_fmt", "yuv420p"],
+            // yuv420p: 10-bit HEVC sources would otherwise yield High 10, which browsers won't decode
+            Encoder::Software => &["-c:v", "libx264", "-preset", "veryfast", "-tune", "zerolatency", "-pix_fmt", "yuv420p"],
+        };
+        let mut args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        args.extend([
+            "-bf".to_string(),
+            "0".to_string(), // B-frames only add latency on a live wall
+            "-force_key_frames".to_string(),
+            "expr:gte(t,n_forced*2)".to_string(), // Keyframe every 2s keeps fragment/HLS startup quick
+        ]);
+        args
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EncoderCapabilities {
+    pub compiled: Vec<String>, // H.264 encoders listed by `ffmpeg -encoders`
+    pub available: Vec<Encoder>, // Hardware encoders that passed a test encode
+    pub selected: Encoder, // Used for every transcoded camera
+}
+
+impl Default for EncoderCapabilities {
+    fn default() -> Self {
+        Self { compiled: Vec::new(), available: Vec::new(), selected: Encoder::Software }
+    }
+}
+
+fn command(ffmpeg_path: &Path) -> Command {
+    let mut cmd = crate::hidden_command(ffmpeg_path);
+    cmd.stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    cmd
+}
+
+/// Names from `ffmpeg -encoders` output, e.g. " V....D h264_nvenc  NVIDIA NVENC H.264 encoder".
+async fn list_encoders(ffmpeg_path: &Path) -> Result<Vec<String>, String> {
+    let output = command(ffmpeg_path)
+        .args(["-hide_banner", "-encoders"])
+        .stdout(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .filter(|name| *name == "libx264" || name.starts_with("h264_"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Encode one synthetic frame to nowhere; success means the hardware is usable.
+async fn test_encode(ffmpeg_path: &Path, encoder: Encoder) -> bool {
+    let mut cmd = command(ffmpeg_path);
+    cmd.args(["-hide_banner", "-loglevel", "error"])
+        .args(encoder.input_args())
+        .args(["-f", "lavfi", "-i", "color=black:s=640x360:d=1", "-frames:v", "1"])
+        .args(encoder.video_args())
+        .args(["-f", "null", "-"])
+        .stdout(Stdio::null());
+    let Ok(mut child) = cmd.spawn() else { return false };
+    match tokio::time::timeout(PROBE_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) => status.success(),
+        _ => {
+            let _ = child.kill().await;
+            false
+        }
+    }
+}
+
+/// Probe the hardware encoders this FFmpeg build offers and pick one.
+pub async fn detect(ffmpeg_path: &Path) -> EncoderCapabilities {
+    let compiled = match list_encoders(ffmpeg_path).await {
+        Ok(names) => names,
+        Err(e) => {
+            warn!("Encoder detection failed: {}", e);
+            return EncoderCapabilities::default();
+        }
+    };
+
+    let mut available = Vec::new();
+    for &encoder in HARDWARE {
+        if !compiled.iter().any(|name| name == encoder.ffmpeg_name()) {
+            continue;
+        }
+        if test_encode(ffmpeg_path, encoder).await {
+            available.push(encoder);
+        } else {
+            debug!("{} is compiled in but failed a test encode", encoder.ffmpeg_name());
+        }
+    }
+
+    let selected = available.first().copied().unwrap_or(Encoder::Software);
+    if selected == Encoder::Software && !compiled.iter().any(|name| name == "libx264") {
+        warn!("FFmpeg has no libx264 and no working hardware encoder; transcoding will fail");
+    }
+    info!("Transcode encoder: {} (hardware available: {:?})", selected.ffmpeg_name(), available);
+    EncoderCapabilities { compiled, available, selected }
+}
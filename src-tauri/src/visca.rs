@@ -0,0 +1,247 @@
+//! VISCA-over-IP PTZ control, for broadcast PTZ cameras that don't speak ONVIF
+//! (Sony, PTZOptics, BirdDog, Panasonic, ...). Cameras with `visca` set are
+//! steered from here instead of over ONVIF, through the same `ptz` functions
+//! and `/api/ptz` routes. VISCA has no move timeout of its own, so one is kept
+//! here: each move schedules a stop, and the next move or stop replaces it.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tracing::{debug, info, warn};
+
+/// Time allowed for a command to be acknowledged.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Highest VISCA drive speeds (pan 0x18, tilt 0x14, zoom 7).
+const MAX_PAN_SPEED: f32 = 24.0;
+const MAX_TILT_SPEED: f32 = 20.0;
+const MAX_ZOOM_SPEED: f32 = 7.0;
+
+/// Presets 1-255 map to VISCA memories 0-254.
+const MAX_PRESET: u32 = 255;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ViscaProtocol {
+    #[default]
+    Sony, // UDP with Sony's VISCA-over-IP header, port 52381
+    Tcp, // Plain VISCA over TCP (PTZOptics, most others), port 5678
+    Udp, // Plain VISCA over UDP, port 1259
+}
+
+impl ViscaProtocol {
+    fn default_port(self) -> u16 {
+        match self {
+            ViscaProtocol::Sony => 52381,
+            ViscaProtocol::Tcp => 5678,
+            ViscaProtocol::Udp => 1259,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ViscaSettings {
+    #[serde(default)]
+    pub host: Option<String>, // None = the stream URL's host
+    #[serde(default)]
+    pub port: Option<u16>, // None = the protocol's usual port
+    #[serde(default)]
+    pub protocol: ViscaProtocol,
+    #[serde(default = "default_address")]
+    pub address: u8, // Camera address 1-7, for cameras chained behind a serial bridge
+}
+
+fn default_address() -> u8 { 1 }
+
+/// Where to send VISCA commands for one camera.
+#[derive(Clone, Debug)]
+pub struct Target {
+    host: String,
+    port: u16,
+    protocol: ViscaProtocol,
+    address: u8,
+}
+
+/// The camera's VISCA target, or `None` when it isn't steered over VISCA.
+pub fn target(camera: &crate::Camera) -> Option<Result<Target, String>> {
+    let settings = camera.visca.as_ref()?;
+    let host = settings.host.clone().or_else(|| crate::UrlParts::parse(&camera.url).map(|p| p.host));
+    Some(host.ok_or_else(|| "Set visca.host; the camera URL has no host".to_string()).map(|host| Target {
+        host,
+        port: settings.port.unwrap_or(settings.protocol.default_port()),
+        protocol: settings.protocol,
+        address: settings.address.clamp(1, 7),
+    }))
+}
+
+/// Map a -1 to 1 speed onto VISCA's 1..=max steps.
+fn speed(value: f32, max: f32) -> u8 {
+    (value.abs() * max).ceil().clamp(1.0, max) as u8
+}
+
+/// Whether a reply message ends the wait: ACK or completion is success, an
+/// error is reported. Anything else (another socket's reply) is skipped.
+fn reply_status(reply: &[u8]) -> Option<Result<(), String>> {
+    match reply.get(1).map(|b| b & 0xF0) {
+        Some(0x40) | Some(0x50) => Some(Ok(())),
+        Some(0x60) => Some(Err(match reply.get(2) {
+            Some(0x02) => "VISCA syntax error; the camera doesn't support this command".to_string(),
+            Some(0x03) => "VISCA command buffer full; try again".to_string(),
+            Some(0x41) => "VISCA command not executable right now".to_string(),
+            other => format!("VISCA error {:02X}", other.copied().unwrap_or(0)),
+        })),
+        _ => None,
+    }
+}
+
+/// Sony's 8-byte header: payload type, payload length, sequence number.
+fn sony_packet(payload_type: u16, sequence: u32, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(8 + payload.len());
+    packet.extend_from_slice(&payload_type.to_be_bytes());
+    packet.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/// Send one command (without address and terminator) and wait for the camera
+/// to accept it.
+async fn send(target: &Target, command: &[u8]) -> Result<(), String> {
+    let mut message = Vec::with_capacity(command.len() + 2);
+    message.push(0x80 | target.address);
+    message.extend_from_slice(command);
+    message.push(0xFF);
+    let addr = (target.host.as_str(), target.port);
+    let io = |e: std::io::Error| format!("VISCA {}:{}: {}", target.host, target.port, e);
+
+    let exchange = async {
+        let mut buf = [0u8; 64];
+        match target.protocol {
+            ViscaProtocol::Sony => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await.map_err(io)?;
+                socket.connect(addr).await.map_err(io)?;
+                // A fresh socket starts its own numbering, so reset the camera's counter first
+                socket.send(&sony_packet(0x0200, 0, &[0x01])).await.map_err(io)?;
+                socket.recv(&mut buf).await.map_err(io)?;
+                socket.send(&sony_packet(0x0100, 1, &message)).await.map_err(io)?;
+                loop {
+                    let n = socket.recv(&mut buf).await.map_err(io)?;
+                    if n > 8 && buf[..2] == [0x01, 0x11] {
+                        if let Some(status) = reply_status(&buf[8..n]) {
+                            return status;
+                        }
+                    }
+                }
+            }
+            ViscaProtocol::Udp => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await.map_err(io)?;
+                socket.connect(addr).await.map_err(io)?;
+                socket.send(&message).await.map_err(io)?;
+                loop {
+                    let n = socket.recv(&mut buf).await.map_err(io)?;
+                    if let Some(status) = reply_status(&buf[..n]) {
+                        return status;
+                    }
+                }
+            }
+            ViscaProtocol::Tcp => {
+                let mut stream = TcpStream::connect(addr).await.map_err(io)?;
+                stream.write_all(&message).await.map_err(io)?;
+                let mut reply = Vec::new();
+                loop {
+                    let n = stream.read(&mut buf).await.map_err(io)?;
+                    if n == 0 {
+                        return Err("VISCA camera closed the connection".to_string());
+                    }
+                    reply.extend_from_slice(&buf[..n]);
+                    // Replies are FF-terminated and may arrive split or back to back
+                    while let Some(end) = reply.iter().position(|&b| b == 0xFF) {
+                        let one: Vec<u8> = reply.drain(..=end).collect();
+                        if let Some(status) = reply_status(&one) {
+                            return status;
+                        }
+                    }
+                }
+            }
+        }
+    };
+    tokio::time::timeout(REQUEST_TIMEOUT, exchange).await
+        .map_err(|_| format!("VISCA camera {}:{} did not answer", target.host, target.port))?
+}
+
+/// Cancel the pending auto-stop for a camera, if any.
+fn cancel_timeout(app: &AppHandle, camera_id: &str) {
+    if let Ok(mut timeouts) = app.state::<crate::AppState>().visca_timeouts.lock() {
+        if let Some(timer) = timeouts.remove(camera_id) {
+            timer.abort();
+        }
+    }
+}
+
+/// Drive pan/tilt and zoom at the given speeds (-1 to 1, already clamped), and
+/// stop after `timeout_ms` unless another move or stop comes first.
+pub async fn continuous_move(app: &AppHandle, camera_id: &str, target: &Target, pan: f32, tilt: f32, zoom: f32, timeout_ms: u64) -> Result<(), String> {
+    cancel_timeout(app, camera_id);
+    let pan_dir = if pan > 0.0 { 0x02 } else if pan < 0.0 { 0x01 } else { 0x03 };
+    let tilt_dir = if tilt > 0.0 { 0x01 } else if tilt < 0.0 { 0x02 } else { 0x03 };
+    send(target, &[0x01, 0x06, 0x01, speed(pan, MAX_PAN_SPEED), speed(tilt, MAX_TILT_SPEED), pan_dir, tilt_dir]).await?;
+    let zoom_drive = if zoom > 0.0 {
+        0x20 | speed(zoom, MAX_ZOOM_SPEED)
+    } else if zoom < 0.0 {
+        0x30 | speed(zoom, MAX_ZOOM_SPEED)
+    } else {
+        0x00
+    };
+    send(target, &[0x01, 0x04, 0x07, zoom_drive]).await?;
+
+    let timer = tauri::async_runtime::spawn({
+        let (camera_id, target) = (camera_id.to_string(), target.clone());
+        async move {
+            tokio::time::sleep(Duration::from_millis(timeout_ms)).await;
+            if let Err(e) = halt(&target).await {
+                warn!("VISCA auto-stop for camera {} failed: {}", camera_id, e);
+            }
+        }
+    });
+    if let Ok(mut timeouts) = app.state::<crate::AppState>().visca_timeouts.lock() {
+        if let Some(previous) = timeouts.insert(camera_id.to_string(), timer) {
+            previous.abort();
+        }
+    }
+    debug!("VISCA move on camera {}: pan {} tilt {} zoom {}", camera_id, pan, tilt, zoom);
+    Ok(())
+}
+
+/// Stop pan, tilt, and zoom.
+async fn halt(target: &Target) -> Result<(), String> {
+    send(target, &[0x01, 0x06, 0x01, 0x01, 0x01, 0x03, 0x03]).await?;
+    send(target, &[0x01, 0x04, 0x07, 0x00]).await
+}
+
+pub async fn stop(app: &AppHandle, camera_id: &str, target: &Target) -> Result<(), String> {
+    cancel_timeout(app, camera_id);
+    halt(target).await
+}
+
+fn memory(n: u32) -> Result<u8, String> {
+    if !(1..=MAX_PRESET).contains(&n) {
+        return Err(format!("VISCA presets run from 1 to {}", MAX_PRESET));
+    }
+    Ok((n - 1) as u8)
+}
+
+/// Recall preset `n` (1-based; VISCA memory `n - 1`).
+pub async fn goto_preset(camera_id: &str, target: &Target, n: u32) -> Result<String, String> {
+    send(target, &[0x01, 0x04, 0x3F, 0x02, memory(n)?]).await?;
+    info!("VISCA: camera {} to preset {}", camera_id, n);
+    Ok(n.to_string())
+}
+
+/// Store the current position as preset `n`.
+pub async fn set_preset(camera_id: &str, target: &Target, n: u32) -> Result<String, String> {
+    send(target, &[0x01, 0x04, 0x3F, 0x01, memory(n)?]).await?;
+    info!("VISCA: camera {} saved preset {}", camera_id, n);
+    Ok(n.to_string())
+}
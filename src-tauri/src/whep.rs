@@ -0,0 +1,375 @@
+//! WebRTC egress over WHEP. Each session subscribes to a camera's fMP4
+//! broadcaster, pulls the H.264 samples out of every moof/mdat fragment,
+//! converts them to Annex B, and hands them to a webrtc-rs sample track for
+//! RTP packetization. No transcoding, so only H.264 cameras are supported.
+
+use bytes::Bytes;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_H264};
+use webrtc::api::APIBuilder;
+use webrtc::interceptor::registry::Registry;
+use webrtc::media::Sample;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use webrtc::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use webrtc::track::track_local::TrackLocal;
+
+use crate::{mp4_child_boxes, read_be_u32, AppState};
+
+const ANNEX_B_START_CODE: [u8; 4] = [0, 0, 0, 1];
+const NAL_IDR: u8 = 5;
+
+/// Longest wait for ICE candidate gathering before answering with what we have.
+const ICE_GATHER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A session whose peer hasn't connected this long after the answer, or has
+/// been disconnected this long, is closed.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The H.264 track of an init segment, as needed to unpack its samples.
+struct H264Track {
+    track_id: u32,
+    timescale: u32,
+    nal_length_size: usize,
+    parameter_sets: Vec<u8>, // SPS + PPS from avcC, Annex B framed
+}
+
+/// One access unit ready for `write_sample`.
+struct AccessUnit {
+    data: Vec<u8>, // Annex B
+    duration: Duration,
+    keyframe: bool,
+}
+
+/// Find the avc1/avc3 track in an init segment (ftyp+moov).
+fn parse_h264_track(init_segment: &[u8]) -> Option<H264Track> {
+    let (_, moov_range) = mp4_child_boxes(init_segment).into_iter().find(|(t, _)| t == b"moov")?;
+    let moov = &init_segment[moov_range];
+    for (trak_type, trak_range) in mp4_child_boxes(moov) {
+        if &trak_type != b"trak" { continue; }
+        let trak = &moov[trak_range];
+        let mut track_id = None;
+        let mut timescale = None;
+        let mut avcc = None;
+        for (child_type, child_range) in mp4_child_boxes(trak) {
+            let child = &trak[child_range];
+            if &child_type == b"tkhd" {
+                track_id = read_be_u32(child, if child.first() == Some(&1) { 20 } else { 12 });
+            } else if &child_type == b"mdia" {
+                for (mdia_type, mdia_range) in mp4_child_boxes(child) {
+                    let mdia = &child[mdia_range];
+                    if &mdia_type == b"mdhd" {
+                        timescale = read_be_u32(mdia, if mdia.first() == Some(&1) { 20 } else { 12 });
+                    } else if &mdia_type == b"minf" {
+                        avcc = find_avcc(mdia);
+                    }
+                }
+            }
+        }
+        if let (Some(track_id), Some(timescale), Some(avcc)) = (track_id, timescale, avcc) {
+            return parse_avcc(avcc).map(|(nal_length_size, parameter_sets)| H264Track {
+                track_id,
+                timescale,
+                nal_length_size,
+                parameter_sets,
+            });
+        }
+    }
+    None
+}
+
+/// minf → stbl → stsd → avc1|avc3 → avcC
+fn find_avcc(minf: &[u8]) -> Option<&[u8]> {
+    let (_, stbl_range) = mp4_child_boxes(minf).into_iter().find(|(t, _)| t == b"stbl")?;
+    let stbl = &minf[stbl_range];
+    let (_, stsd_range) = mp4_child_boxes(stbl).into_iter().find(|(t, _)| t == b"stsd")?;
+    let entries = stbl.get(stsd_range.start + 8..stsd_range.end)?; // Skip version/flags and entry count
+    let (_, entry_range) = mp4_child_boxes(entries).into_iter().find(|(t, _)| t == b"avc1" || t == b"avc3")?;
+    // 78 bytes of VisualSampleEntry fields precede the child boxes
+    let children = entries.get(entry_range.start + 78..entry_range.end)?;
+    let (_, avcc_range) = mp4_child_boxes(children).into_iter().find(|(t, _)| t == b"avcC")?;
+    Some(&children[avcc_range])
+}
+
+/// AVCDecoderConfigurationRecord → (NAL length size, Annex B SPS/PPS).
+fn parse_avcc(avcc: &[u8]) -> Option<(usize, Vec<u8>)> {
+    let nal_length_size = (*avcc.get(4)? & 0x03) as usize + 1;
+    let mut parameter_sets = Vec::new();
+    let mut offset = 5;
+    for mask in [0x1f, 0xff] {
+        // SPS count is the low 5 bits; PPS count is a full byte
+        let count = *avcc.get(offset)? & mask;
+        offset += 1;
+        for _ in 0..count {
+            let len = u16::from_be_bytes([*avcc.get(offset)?, *avcc.get(offset + 1)?]) as usize;
+            parameter_sets.extend_from_slice(&ANNEX_B_START_CODE);
+            parameter_sets.extend_from_slice(avcc.get(offset + 2..offset + 2 + len)?);
+            offset += 2 + len;
+        }
+    }
+    Some((nal_length_size, parameter_sets))
+}
+
+/// Split a moof+mdat fragment into Annex B access units for `track`.
+/// Keyframes get the SPS/PPS prepended so a new viewer can start decoding.
+fn fragment_access_units(fragment: &[u8], track: &H264Track) -> Vec<AccessUnit> {
+    let mut units = Vec::new();
+    let boxes = mp4_child_boxes(fragment);
+    let Some((_, moof_range)) = boxes.iter().find(|(t, _)| t == b"moof").cloned() else { return units };
+    let moof_start = moof_range.start - 8; // default_base_moof: data offsets are relative to the moof
+    let moof = &fragment[moof_range];
+
+    for (traf_type, traf_range) in mp4_child_boxes(moof) {
+        if &traf_type != b"traf" { continue; }
+        let traf = &moof[traf_range];
+        let mut default_duration = 0;
+        let mut default_size = 0;
+        let mut ours = false;
+        for (child_type, child_range) in mp4_child_boxes(traf) {
+            let child = &traf[child_range];
+            if &child_type == b"tfhd" {
+                ours = read_be_u32(child, 4) == Some(track.track_id);
+                let flags = read_be_u32(child, 0).unwrap_or(0) & 0x00ff_ffff;
+                let mut offset = 8;
+                if flags & 0x01 != 0 { offset += 8; } // base_data_offset
+                if flags & 0x02 != 0 { offset += 4; } // sample_description_index
+                if flags & 0x08 != 0 {
+                    default_duration = read_be_u32(child, offset).unwrap_or(0);
+                    offset += 4;
+                }
+                if flags & 0x10 != 0 {
+                    default_size = read_be_u32(child, offset).unwrap_or(0);
+                }
+            } else if &child_type == b"trun" && ours {
+                let flags = read_be_u32(child, 0).unwrap_or(0) & 0x00ff_ffff;
+                let sample_count = read_be_u32(child, 4).unwrap_or(0);
+                let mut offset = 8;
+                let mut data_pos = moof_start;
+                if flags & 0x01 != 0 {
+                    data_pos = (moof_start as i64 + read_be_u32(child, offset).unwrap_or(0) as i32 as i64) as usize;
+                    offset += 4;
+                }
+                if flags & 0x04 != 0 { offset += 4; } // first_sample_flags
+                for _ in 0..sample_count {
+                    let mut duration = default_duration;
+                    let mut size = default_size;
+                    if flags & 0x100 != 0 {
+                        duration = read_be_u32(child, offset).unwrap_or(0);
+                        offset += 4;
+                    }
+                    if flags & 0x200 != 0 {
+                        size = read_be_u32(child, offset).unwrap_or(0);
+                        offset += 4;
+                    }
+                    if flags & 0x400 != 0 { offset += 4; } // sample_flags
+                    if flags & 0x800 != 0 { offset += 4; } // composition time offset
+                    let Some(sample) = fragment.get(data_pos..data_pos + size as usize) else { return units };
+                    data_pos += size as usize;
+                    let (data, keyframe) = to_annex_b(sample, track);
+                    units.push(AccessUnit {
+                        data,
+                        duration: Duration::from_secs_f64(duration as f64 / track.timescale.max(1) as f64),
+                        keyframe,
+                    });
+                }
+            }
+        }
+    }
+    units
+}
+
+/// Length-prefixed NAL units → start-code framed, with SPS/PPS in front of
+/// access units containing an IDR slice. Returns (data, is IDR).
+fn to_annex_b(sample: &[u8], track: &H264Track) -> (Vec<u8>, bool) {
+    let mut nals = Vec::with_capacity(sample.len() + 16);
+    let mut idr = false;
+    let mut pos = 0;
+    while pos + track.nal_length_size <= sample.len() {
+        let len = sample[pos..pos + track.nal_length_size].iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        pos += track.nal_length_size;
+        let Some(nal) = sample.get(pos..pos + len) else { break };
+        idr |= nal.first().map(|b| b & 0x1f) == Some(NAL_IDR);
+        nals.extend_from_slice(&ANNEX_B_START_CODE);
+        nals.extend_from_slice(nal);
+        pos += len;
+    }
+    if !idr {
+        return (nals, false);
+    }
+    let mut out = Vec::with_capacity(track.parameter_sets.len() + nals.len());
+    out.extend_from_slice(&track.parameter_sets);
+    out.extend(nals);
+    (out, true)
+}
+
+/// Answer a WHEP offer for `camera_id`. Returns (session id, answer SDP).
+/// Sessions from other machines (`remote`) count towards maintenance mode's
+/// drain and are closed with the other remote connections.
+pub async fn create_session(app: &AppHandle, camera_id: &str, offer_sdp: String, remote: bool) -> Result<(String, String), String> {
+    let state = app.state::<AppState>();
+    let init = state.init_segments.lock().ok().and_then(|c| c.get(camera_id).cloned());
+    if init.as_ref().is_some_and(|init| parse_h264_track(init).is_none()) {
+        return Err("camera stream is not H.264".to_string());
+    }
+    let rx = state.frame_broadcasters.lock()
+        .map_err(|_| "Broadcaster mutex poisoned - please restart application".to_string())?
+        .get(camera_id)
+        .map(|sender| sender.subscribe())
+        .ok_or_else(|| "camera is not streaming".to_string())?;
+
+    let mut media_engine = MediaEngine::default();
+    media_engine.register_default_codecs().map_err(|e| e.to_string())?;
+    let registry = register_default_interceptors(Registry::new(), &mut media_engine).map_err(|e| e.to_string())?;
+    let api = APIBuilder::new()
+        .with_media_engine(media_engine)
+        .with_interceptor_registry(registry)
+        .build();
+    let pc = Arc::new(api.new_peer_connection(RTCConfiguration::default()).await.map_err(|e| e.to_string())?);
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability { mime_type: MIME_TYPE_H264.to_owned(), ..Default::default() },
+        "video".to_owned(),
+        format!("stageview-{}", camera_id),
+    ));
+
+    let answer = match negotiate(&pc, &track, offer_sdp).await {
+        Ok(sdp) => sdp,
+        Err(e) => {
+            let _ = pc.close().await;
+            return Err(e.to_string());
+        }
+    };
+
+    let session_id = format!("{:016x}", rand::random::<u64>());
+    if let Ok(mut sessions) = state.whep_sessions.lock() {
+        sessions.insert(session_id.clone(), (camera_id.to_string(), pc.clone()));
+    }
+    info!("WHEP session {} started for {}", session_id, camera_id);
+    crate::refresh_stream_choice(app, camera_id);
+    tauri::async_runtime::spawn(pump(app.clone(), camera_id.to_string(), session_id.clone(), pc, track, rx, remote));
+    Ok((session_id, answer))
+}
+
+async fn negotiate(pc: &RTCPeerConnection, track: &Arc<TrackLocalStaticSample>, offer_sdp: String) -> Result<String, webrtc::Error> {
+    let sender = pc.add_track(Arc::clone(track) as Arc<dyn TrackLocal + Send + Sync>).await?;
+    // Drain RTCP so the interceptors (NACK, reports) keep running
+    tauri::async_runtime::spawn(async move {
+        let mut buf = vec![0u8; 1500];
+        while sender.read(&mut buf).await.is_ok() {}
+    });
+
+    pc.set_remote_description(RTCSessionDescription::offer(offer_sdp)?).await?;
+    let answer = pc.create_answer(None).await?;
+    // Non-trickle: WHEP clients expect every candidate in the answer
+    let mut gathered = pc.gathering_complete_promise().await;
+    pc.set_local_description(answer).await?;
+    if tokio::time::timeout(ICE_GATHER_TIMEOUT, gathered.recv()).await.is_err() {
+        debug!("WHEP: ICE gathering still running after {:?}, answering with the candidates so far", ICE_GATHER_TIMEOUT);
+    }
+    Ok(pc.local_description().await.map(|d| d.sdp).unwrap_or_default())
+}
+
+/// Close a session in response to the client's DELETE on its resource URL.
+pub async fn close_session(state: &AppState, session_id: &str) -> bool {
+    let session = state.whep_sessions.lock().ok().and_then(|mut s| s.remove(session_id));
+    match session {
+        Some((_, pc)) => {
+            let _ = pc.close().await;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Feed the camera's fragments to the track until the peer goes away.
+/// Starts at the next keyframe rather than replaying the cached GOP, which
+/// would put the viewer behind live by up to a GOP.
+async fn pump(
+    app: AppHandle,
+    camera_id: String,
+    session_id: String,
+    pc: Arc<RTCPeerConnection>,
+    track: Arc<TrackLocalStaticSample>,
+    mut rx: tokio::sync::broadcast::Receiver<Bytes>,
+    remote: bool,
+) {
+    let state = app.state::<AppState>();
+    let drain = state.maintenance_drain.subscribe();
+    if remote {
+        state.remote_connections.fetch_add(1, Ordering::Relaxed);
+    }
+    let init = state.init_segments.lock().ok().and_then(|c| c.get(&camera_id).cloned());
+    let mut h264 = init.and_then(|init| parse_h264_track(&init));
+    let mut awaiting_keyframe = true;
+    let mut unconnected_since = Some(std::time::Instant::now()); // None while connected
+
+    loop {
+        if remote && *drain.borrow() {
+            info!("WHEP {}: closing for maintenance", session_id);
+            break;
+        }
+        match pc.connection_state() {
+            RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed => break,
+            RTCPeerConnectionState::Connected => unconnected_since = None,
+            _ => {
+                let since = *unconnected_since.get_or_insert_with(std::time::Instant::now);
+                if since.elapsed() >= CONNECT_TIMEOUT {
+                    info!("WHEP {}: peer not connected after {:?}, closing", session_id, CONNECT_TIMEOUT);
+                    break;
+                }
+            }
+        }
+        let message = match tokio::time::timeout(Duration::from_secs(1), rx.recv()).await {
+            Ok(Ok(message)) => message,
+            Ok(Err(RecvError::Lagged(skipped))) => {
+                debug!("WHEP {} lagged {} fragments, resyncing at next keyframe", session_id, skipped);
+                awaiting_keyframe = true;
+                continue;
+            }
+            Ok(Err(RecvError::Closed)) => break,
+            Err(_) => continue, // Re-check the connection state
+        };
+
+        if message.get(4..8) == Some(b"ftyp") {
+            // FFmpeg restarted: parameters may have changed
+            h264 = parse_h264_track(&message);
+            if h264.is_none() {
+                warn!("WHEP {}: {} is no longer H.264, closing", session_id, camera_id);
+                break;
+            }
+            awaiting_keyframe = true;
+            continue;
+        }
+        let Some(ref track_info) = h264 else { continue };
+
+        for unit in fragment_access_units(&message, track_info) {
+            if awaiting_keyframe {
+                if !unit.keyframe {
+                    continue;
+                }
+                awaiting_keyframe = false;
+            }
+            let sample = Sample { data: Bytes::from(unit.data), duration: unit.duration, ..Default::default() };
+            if let Err(e) = track.write_sample(&sample).await {
+                debug!("WHEP {}: write failed: {}", session_id, e);
+            }
+        }
+    }
+
+    close_session(&state, &session_id).await;
+    let _ = pc.close().await;
+    if remote {
+        state.remote_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+    info!("WHEP session {} for {} ended", session_id, camera_id);
+    crate::refresh_stream_choice(&app, &camera_id);
+}
@@ -0,0 +1,150 @@
+//! `/ws` event channel for remote clients: pushes `camera-status`, `stream-health-batch`, `stream-health` (deprecated), `stream-info`,
+//! `remote-command`, `view-changed`, `detection`, `onvif-event`, `highlight`, `screensaver`, `burn-in`, `talkback`, `maintenance`, `theme-changed`, and `announcement` events as `{"event": ..., "payload": ...}` text messages
+//! and accepts JSON commands (`{"command": "solo", "index": 2}`, `grid`,
+//! `fullscreen`) on the same socket.
+
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use tauri::{AppHandle, Listener, Manager};
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+/// App events forwarded to every socket.
+const FORWARDED_EVENTS: &[&str] = &["camera-status", "stream-health-batch", "stream-health", "stream-info", "remote-command", "view-changed", "detection", "onvif-event", "highlight", "screensaver", "burn-in", "talkback", "maintenance", "theme-changed", "announcement"];
+
+/// Messages queued for a slow client before new events are dropped.
+const QUEUE_DEPTH: usize = 256;
+
+/// Client messages are small JSON commands; anything bigger is a protocol error.
+const MAX_MESSAGE: usize = 64 * 1024;
+
+/// Run one JSON command and build the reply; a client-supplied `id` is echoed back.
+async fn handle_command(app: &AppHandle, message: &[u8]) -> serde_json::Value {
+    let request: serde_json::Value = match serde_json::from_slice(message) {
+        Ok(v) => v,
+        Err(e) => return serde_json::json!({"ok": false, "error": format!("invalid JSON: {}", e)}),
+    };
+    let command = request.get("command").and_then(|c| c.as_str()).unwrap_or("");
+    let mut reply = match command {
+        "solo" => {
+            let count = app.state::<crate::AppState>().config.lock().map(|c| c.cameras.len()).unwrap_or(0);
+            match request.get("index").and_then(|i| i.as_u64()).map(|i| i as usize) {
+                Some(index) if index >= 1 && index <= count => {
+                    crate::emit_remote_command(app, "solo", Some(index));
+                    serde_json::json!({"ok": true, "action": "solo", "index": index})
+                }
+                _ => serde_json::json!({"ok": false, "error": format!("index must be 1-{}", count)}),
+            }
+        }
+        "grid" => {
+            crate::emit_remote_command(app, "grid", None);
+            serde_json::json!({"ok": true, "action": "grid"})
+        }
+        "fullscreen" => match crate::api_fullscreen(app.clone()).await {
+            Ok(result) => result,
+            Err(e) => serde_json::json!({"ok": false, "error": e}),
+        },
+        other => serde_json::json!({"ok": false, "error": format!("unknown command: {:?}", other), "commands": ["solo", "grid", "fullscreen"]}),
+    };
+    if let (Some(id), Some(obj)) = (request.get("id"), reply.as_object_mut()) {
+        obj.insert("id".to_string(), id.clone());
+    }
+    reply
+}
+
+/// `GET /ws`: complete the upgrade and serve the socket until it closes.
+pub async fn handler(
+    State(app): State<AppHandle>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    match upgrade {
+        Ok(upgrade) => upgrade
+            .max_message_size(MAX_MESSAGE)
+            .on_upgrade(move |socket| serve(app, socket, peer)),
+        Err(_) => (
+            StatusCode::UPGRADE_REQUIRED,
+            [(header::UPGRADE, "websocket"), (header::CONTENT_TYPE, "application/json")],
+            r#"{"ok":false,"error":"expected a WebSocket upgrade"}"#,
+        ).into_response(),
+    }
+}
+
+async fn serve(app: AppHandle, mut socket: WebSocket, peer: SocketAddr) {
+    info!("WebSocket client connected: {}", peer);
+    let state = app.state::<crate::AppState>();
+
+    // Upgraded sockets outlive their HTTP connection, so remote ones are counted
+    // and closed for maintenance here rather than by the API server
+    let remote = !peer.ip().is_loopback();
+    let mut drain = state.maintenance_drain.subscribe();
+    if remote {
+        state.remote_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let (tx, mut events) = mpsc::channel::<String>(QUEUE_DEPTH);
+
+    // Current statuses and view first, so a new client doesn't wait for the next change
+    let statuses = state.camera_statuses.lock()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    for (camera_id, status) in statuses {
+        let payload = serde_json::json!({"camera_id": camera_id, "status": status});
+        let _ = tx.try_send(serde_json::json!({"event": "camera-status", "payload": payload}).to_string());
+    }
+    let view = crate::view_state(&app);
+    let _ = tx.try_send(serde_json::json!({"event": "view-changed", "payload": view}).to_string());
+
+    let listeners: Vec<tauri::EventId> = FORWARDED_EVENTS.iter().map(|&name| {
+        let tx = tx.clone();
+        app.listen_any(name, move |event| {
+            // Payloads are already JSON; splice them in rather than re-parsing
+            let _ = tx.try_send(format!(r#"{{"event":"{}","payload":{}}}"#, name, event.payload()));
+        })
+    }).collect();
+    drop(tx);
+
+    loop {
+        tokio::select! {
+            message = events.recv() => {
+                let Some(message) = message else { break };
+                if socket.send(Message::Text(message.into())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                let result = match message {
+                    Some(Ok(Message::Text(text))) => {
+                        let reply = handle_command(&app, text.as_str().as_bytes()).await;
+                        socket.send(Message::Text(reply.to_string().into())).await
+                    }
+                    // Close replies and pongs are sent by the socket itself
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => Ok(()), // Binary, ping, and pong messages are ignored
+                    Some(Err(e)) => {
+                        debug!("WebSocket read ended: {}", e);
+                        break;
+                    }
+                };
+                if result.is_err() {
+                    break;
+                }
+            }
+            _ = drain.wait_for(|drained| *drained), if remote => break,
+        }
+    }
+
+    for id in listeners {
+        app.unlisten(id);
+    }
+    if remote {
+        state.remote_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+    info!("WebSocket client disconnected: {}", peer);
+}
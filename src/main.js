@@ -29,10 +29,46 @@ function escapeHtml(str) {
 // Media Source Extensions for hardware-accelerated H.264 decode.
 // No transcoding needed - direct playback of H.264 RTP streams.
 
+const VIDEO_CODEC = 'video/mp4; codecs="avc1.42E01E"';
+const AUDIO_VIDEO_CODEC = 'video/mp4; codecs="avc1.42E01E, mp4a.40.2"';
+
+/**
+ * Whether the init segment at the head of `chunks` has an audio track
+ * (an hdlr box with handler type "soun" inside moov). Returns null until
+ * the whole moov has arrived.
+ */
+function initSegmentHasAudio(chunks) {
+  const total = chunks.reduce((sum, c) => sum + c.byteLength, 0);
+  const data = new Uint8Array(total);
+  let pos = 0;
+  for (const c of chunks) {
+    data.set(c, pos);
+    pos += c.byteLength;
+  }
+  const type = (i) => String.fromCharCode(data[i], data[i + 1], data[i + 2], data[i + 3]);
+  let offset = 0;
+  while (offset + 8 <= total) {
+    const size = ((data[offset] << 24) >>> 0) + (data[offset + 1] << 16) + (data[offset + 2] << 8) + data[offset + 3];
+    const boxType = type(offset + 4);
+    if (boxType === 'moov') {
+      if (offset + size > total) return null;
+      for (let i = offset + 8; i + 16 <= offset + size; i++) {
+        if (type(i) === 'hdlr' && type(i + 12) === 'soun') return true;
+      }
+      return false;
+    }
+    if (size < 8 || boxType === 'moof') return false; // No init segment up front
+    offset += size;
+  }
+  return null;
+}
+
 class Mp4StreamReader {
-  constructor(url, video) {
+  constructor(url, video, { audio = false } = {}) {
     this.url = url;
     this.video = video;
+    this.audio = audio; // Camera has audio_enabled: pick the codec string from the init segment
+    this._awaitingInit = false; // SourceBuffer deferred until the init segment arrives
     this.mediaSource = null;
     this.sourceBuffer = null;
     this.abortController = null;
@@ -93,41 +129,14 @@ class Mp4StreamReader {
     this.video.src = URL.createObjectURL(this.mediaSource);
 
     this.mediaSource.addEventListener('sourceopen', () => {
-      try {
-        const codec = 'video/mp4; codecs="avc1.42E01E"';
-        if (!MediaSource.isTypeSupported(codec)) {
-          if (this.onError) this.onError('H.264 codec not supported');
-          return;
-        }
-
-        this.sourceBuffer = this.mediaSource.addSourceBuffer(codec);
-        this.sourceBuffer.mode = 'sequence';
-        
-        this.sourceBuffer.addEventListener('updateend', () => {
-          this.isUpdating = false;
-          this._chaseLiveEdge();
-          this._processQueue();
-        });
-
-        this.sourceBuffer.addEventListener('error', (e) => {
-          console.warn('[Mp4StreamReader] SourceBuffer error, restarting pipeline');
-          this._restart();
-        });
-
-        // Start periodic buffer trimming (every 5s) and corrupt-frame monitor
-        if (this._trimTimer) clearInterval(this._trimTimer);
-        this._trimTimer = setInterval(() => {
-          this._trimBuffer();
-          this._checkCorruptFrames();
-        }, 5000);
-
-        // Flush any segments that arrived before sourceopen fired.
-        // Without this, data queued during pipeline startup sits idle
-        // until the next incoming chunk triggers _processQueue().
+      if (this.audio) {
+        // The codec string must match the tracks FFmpeg actually produced
+        // (audio_enabled cameras may have no audio), so wait for the moov
+        this._awaitingInit = true;
         this._processQueue();
-      } catch (e) {
-        if (this.onError) this.onError('Failed to create SourceBuffer');
+        return;
       }
+      this._createSourceBuffer(VIDEO_CODEC);
     });
 
     this.mediaSource.addEventListener('error', (e) => {
@@ -136,6 +145,43 @@ class Mp4StreamReader {
     });
   }
 
+  _createSourceBuffer(codec) {
+    try {
+      if (!MediaSource.isTypeSupported(codec)) {
+        if (this.onError) this.onError(codec === VIDEO_CODEC ? 'H.264 codec not supported' : 'AAC audio not supported');
+        return;
+      }
+
+      this.sourceBuffer = this.mediaSource.addSourceBuffer(codec);
+      this.sourceBuffer.mode = 'sequence';
+
+      this.sourceBuffer.addEventListener('updateend', () => {
+        this.isUpdating = false;
+        this._chaseLiveEdge();
+        this._processQueue();
+      });
+
+      this.sourceBuffer.addEventListener('error', (e) => {
+        console.warn('[Mp4StreamReader] SourceBuffer error, restarting pipeline');
+        this._restart();
+      });
+
+      // Start periodic buffer trimming (every 5s) and corrupt-frame monitor
+      if (this._trimTimer) clearInterval(this._trimTimer);
+      this._trimTimer = setInterval(() => {
+        this._trimBuffer();
+        this._checkCorruptFrames();
+      }, 5000);
+
+      // Flush any segments that arrived before sourceopen fired.
+      // Without this, data queued during pipeline startup sits idle
+      // until the next incoming chunk triggers _processQueue().
+      this._processQueue();
+    } catch (e) {
+      if (this.onError) this.onError('Failed to create SourceBuffer');
+    }
+  }
+
   /**
    * Capture the current video frame onto a canvas overlay so the user
    * sees the last good frame while the MSE pipeline is being rebuilt.
@@ -296,6 +342,7 @@ class Mp4StreamReader {
       // Tear down old MSE state
       this.queue = [];
       this.isUpdating = false;
+      this._awaitingInit = false;
       this._firstFrame = false;
       if (this._trimTimer) { clearInterval(this._trimTimer); this._trimTimer = null; }
 
@@ -359,7 +406,7 @@ class Mp4StreamReader {
           if (value && value.byteLength > 0) {
             bytesReceived += value.byteLength;
             
-            if (this.sourceBuffer) {
+            if (this.sourceBuffer || this._awaitingInit) {
               this.queue.push(value);
               this._processQueue();
 
@@ -389,6 +436,13 @@ class Mp4StreamReader {
   }
 
   _processQueue() {
+    if (!this.sourceBuffer && this._awaitingInit && this.queue.length > 0) {
+      const hasAudio = initSegmentHasAudio(this.queue);
+      if (hasAudio === null) return;
+      this._awaitingInit = false;
+      this._createSourceBuffer(hasAudio ? AUDIO_VIDEO_CODEC : VIDEO_CODEC);
+      return; // _createSourceBuffer flushes the queue
+    }
     if (this.isUpdating || this.queue.length === 0 || !this.sourceBuffer) return;
 
     try {
@@ -510,13 +564,43 @@ class StageView {
     this.nextShuffleAt = 0;
     this.unlistenStatus = null;
     this.unlistenCommand = null;
+    this.unlistenLayout = null;
+    this.unlistenPage = null;
+    this.unlistenPip = null;
+    this.unlistenHealth = null;
+    this.unlistenStreamError = null;
+    this.unlistenTheme = null;
+    this.unlistenAnnouncement = null;
+    this.unlistenOverlay = null;
+    this.unlistenBurnIn = null;
+    this.unlistenAudioMonitor = null;
+    this.unlistenIdentify = null;
+    this.unlistenDetection = null;
+    this.unlistenOnvifEvent = null;
+    this.unlistenTalkback = null;
+    this.unlistenHighlight = null;
+    this.unlistenDataTile = null;
+    this.unlistenStandby = null;
+    this.unlistenScreensaver = null;
+    this.unlistenMaintenance = null;
+    this.unlistenBlackout = null;
+    this.unlistenReloadConfig = null;
     this.soloIndex = null; // null = grid view, number = 1-based solo index
+    this.talkbackCamera = null; // Camera id while T is held for push-to-talk
+    this.audioCameraId = null; // Monitored camera from the backend (monitor_audio); null = sound follows solo
+    this.page = null; // Current page from the backend ({ page, page_count, page_size, camera_ids })
+    this.pip = null; // Picture-in-picture state from the backend ({ active, camera_id, position, size_percent })
+    this.theme = null; // Resolved tile theme from the backend (status colors, borders, offline look)
+    this.pipReader = null; // Mp4StreamReader for the PiP overlay
+    this.overlay = null; // Overlay graphic from the backend ({ active, image_url, position, size_percent })
     this.pixelShiftIndex = 0; // cycles through shift positions for burn-in protection
+    this.backendPixelShift = false; // burn_in.pixel_shift is on; the backend moves the layout instead
     this._outsideClickHandler = null; // single handler for camera menu outside clicks
     this.healthStats = new Map(); // camera_id -> health object
     this.cameraStatuses = new Map(); // camera_id -> status string (online/offline/connecting/reconnecting)
     this._configSavePromise = null; // serializes config save operations
     this.streamReaders = new Map(); // camera_id -> Mp4StreamReader
+    this.dataTiles = new Map(); // camera_id -> latest content of a data: tile (JSON value or text)
     this._countdownTimer = null;
     this._idleTimer = null;
     this._isIdle = false;
@@ -530,13 +614,26 @@ class StageView {
     try {
       const config = await invoke("get_config");
       this.cameras = config.cameras;
-      this.displayOrder = this.cameras.map((_, i) => i); // initialize display order
+      this.displayOrder = this.displayOrderFromIds(config.tile_order); // persisted tile order, if any
       this.shuffleIntervalSecs = config.shuffle_interval_secs;
       this.showStatusDots = config.show_status_dots !== false;
       this.showCameraNames = config.show_camera_names !== false;
       this.apiPort = config.api_port || 8090;
+      this.apiToken = config.api_token || "";
+      this.page = await invoke("get_page").catch(() => null);
+      this.pip = await invoke("get_pip").catch(() => null);
+      const monitor = await invoke("get_audio_monitor").catch(() => null);
+      this.audioCameraId = monitor?.camera_id ?? null;
+      this.showLintWarnings();
+
+      // Status colors, tile borders, and offline look (set_theme, /api/theme)
+      this.applyTheme(await invoke("get_theme").catch(() => null));
+      this.unlistenTheme = await listen("theme-changed", (event) => this.applyTheme(event.payload));
 
-      // Listen for camera status events (online / offline / error / connecting / reconnecting)
+      // Wall state changes worded for screen readers (announce.verbosity)
+      this.unlistenAnnouncement = await listen("announcement", (event) => this.announce(event.payload.text));
+
+      // Listen for camera status events (online / offline / error / connecting / reconnecting / idle)
       this.unlistenStatus = await listen("camera-status", (event) => {
         const { camera_id, status } = event.payload;
 
@@ -551,6 +648,53 @@ class StageView {
         if (row) row.setAttribute('data-health-state', this._healthStateFromStatus(status));
       });
 
+      // Page flipped (/api/page/*, PageUp/PageDown, or a reorder moved cameras between pages)
+      this.unlistenPage = await listen("page-changed", (event) => {
+        this.page = event.payload;
+        if (this.soloIndex === null) this.layoutGrid();
+      });
+
+      // Picture-in-picture switched on/off (/api/pip/:index/on|off)
+      this.unlistenPip = await listen("pip-changed", (event) => {
+        this.pip = event.payload;
+        this.renderPip();
+        this.refreshStandby();
+      });
+
+      // Overlay graphic pushed or cleared (/api/overlay)
+      this.unlistenOverlay = await listen("overlay-changed", (event) => {
+        this.overlay = event.payload;
+        this.renderOverlay();
+      });
+      this.overlay = await invoke("get_overlay").catch(() => null);
+
+      // Burn-in mitigation: the backend steps the layout around a few-pixel spiral and dims long-running overlays
+      const setBurnIn = ({ x, y, pixel_shift, overlay_opacity }) => {
+        // The backend shift replaces the solo-mode pixel refresh's own, so the two never add up
+        this.backendPixelShift = pixel_shift;
+        if (pixel_shift) document.getElementById("grid").style.transform = "";
+        for (const id of ["grid", "pip", "overlay-graphic"]) {
+          document.getElementById(id).style.translate = `${x}px ${y}px`;
+        }
+        document.querySelector("#overlay-graphic img").style.opacity = overlay_opacity;
+      };
+      this.unlistenBurnIn = await listen("burn-in", (event) => setBurnIn(event.payload));
+      invoke("get_burn_in").then(setBurnIn).catch(() => {});
+
+      // Audio monitor routed to another camera ("A" key, /api/audio/:index)
+      this.unlistenAudioMonitor = await listen("audio-monitor-changed", (event) => {
+        this.audioCameraId = event.payload.camera_id ?? null;
+        this.updateAudio();
+        this.refreshStandby();
+      });
+
+      // A remote layout editor (/api/layout/tiles) rearranged the wall
+      this.unlistenLayout = await listen("layout-changed", (event) => {
+        this.displayOrder = this.displayOrderFromIds(event.payload.tiles);
+        if (this.soloIndex === null) this.applyDisplayOrder();
+        this.startShuffleTimer(); // Hold the new arrangement for a full interval
+      });
+
       // Listen for remote commands from the API server
       this.unlistenCommand = await listen("remote-command", (event) => {
         const { command, index } = event.payload;
@@ -561,14 +705,17 @@ class StageView {
         }
       });
 
-      // Listen for stream health updates — update fps display directly, no debounce
-      this.unlistenHealth = await listen("stream-health", (event) => {
-        const { camera_id, health } = event.payload;
-        this.healthStats.set(camera_id, health);
-        const row = document.querySelector(`#health-stats-container [data-camera-id="${camera_id}"]`);
-        if (!row) return;
-        const fpsEl = row.querySelector('[data-metric="fps"]');
-        if (fpsEl) fpsEl.textContent = health.fps > 0 ? health.fps.toFixed(1) : '--';
+      // Listen for batched stream health updates — update fps display directly, no debounce.
+      // The ack tells the backend this batch is applied; it holds the next one until then.
+      this.unlistenHealth = await listen("wall-health-batch", (event) => {
+        const { seq, health: batch } = event.payload;
+        for (const health of batch) {
+          this.healthStats.set(health.camera_id, health);
+          const row = document.querySelector(`#health-stats-container [data-camera-id="${health.camera_id}"]`);
+          const fpsEl = row?.querySelector('[data-metric="fps"]');
+          if (fpsEl) fpsEl.textContent = health.fps > 0 ? health.fps.toFixed(1) : '--';
+        }
+        invoke("ack_health_batch", { seq }).catch(() => {});
       });
 
       // Listen for stream errors (deduplicate per camera to prevent toast flood)
@@ -585,9 +732,152 @@ class StageView {
         setTimeout(() => this._activeErrorToasts.delete(camera_id), 10300);
       });
 
-      // Listen for reload-config event
-      await listen("reload-config", () => {
-        location.reload();
+      // Identification overlay (/api/identify/:index) — backend controls timing
+      this.unlistenIdentify = await listen("identify", (event) => {
+        const { camera_id, index, name, active } = event.payload;
+        const tile = document.querySelector(`[data-id="${camera_id}"]`);
+        if (!tile) return;
+        tile.querySelector(".identify-overlay")?.remove();
+        if (active) {
+          tile.insertAdjacentHTML('beforeend', `
+            <div class="identify-overlay">
+              <div class="identify-index">${index}</div>
+              <div class="identify-name">${escapeHtml(name)}</div>
+            </div>
+          `);
+        }
+      });
+
+      // Frigate/Home Assistant detection — flash the tile (soloing, if enabled, arrives as a remote command)
+      this.unlistenDetection = await listen("detection", (event) => {
+        const { camera_id, label } = event.payload;
+        this.flashTile(camera_id, label || "event");
+      });
+
+      // ONVIF motion/tamper/input from the camera itself — flash on start only
+      this.unlistenOnvifEvent = await listen("onvif-event", (event) => {
+        const { camera_id, kind, active } = event.payload;
+        if (active) this.flashTile(camera_id, kind);
+      });
+
+      // Push-to-talk — mark the camera being talked to, and say why a talkback ended early
+      this.unlistenTalkback = await listen("talkback", (event) => {
+        const { camera_id, active, error } = event.payload;
+        document.querySelector(`[data-id="${camera_id}"]`)?.classList.toggle("talking", active);
+        if (error) this.showToast(`Talkback: ${error}`, "error");
+      });
+
+      // Auto-solo rule with the highlight action — outline the tile until the backend clears it
+      this.unlistenHighlight = await listen("highlight", (event) => {
+        const { camera_id, active } = event.payload;
+        document.querySelector(`[data-id="${camera_id}"]`)?.classList.toggle("highlighted", active);
+      });
+
+      // Data tile content changed (data: URLs poll a JSON/text source instead of video)
+      this.unlistenDataTile = await listen("data-tile", (event) => {
+        const { camera_id, content } = event.payload;
+        this.dataTiles.set(camera_id, content);
+        this.renderDataTile(camera_id);
+      });
+      for (const tile of await invoke("get_data_tiles").catch(() => [])) {
+        this.dataTiles.set(tile.camera_id, tile.content);
+      }
+
+      // Standby screen (dark day or outside show hours) — backend stops streams while it's shown
+      const setStandbyScreen = ({ active, reason, blank }) => {
+        const screen = document.getElementById("standby-screen");
+        const offHours = reason === "off hours";
+        screen.querySelector("h2").textContent = offHours ? "Outside show hours" : "Closed today";
+        screen.querySelector("p").textContent = offHours
+          ? "Streams resume automatically when show hours begin"
+          : "Streams resume automatically on the next open day";
+        screen.classList.toggle("blank", !!blank);
+        screen.classList.toggle("active", active);
+      };
+      this.unlistenStandby = await listen("standby-screen", (event) => setStandbyScreen(event.payload));
+      invoke("get_standby_screen").then(setStandbyScreen).catch(() => {});
+
+      // Idle screensaver — the backend decides when it starts and which camera the montage shows next
+      const screensaver = document.getElementById("screensaver");
+      const clock = screensaver.querySelector(".screensaver-clock");
+      const stills = screensaver.querySelectorAll(".screensaver-still");
+      let stillIndex = 0;
+      const moveClock = () => {
+        clock.textContent = new Date().toLocaleTimeString([], { hour: "2-digit", minute: "2-digit" });
+        clock.style.left = `${Math.random() * Math.max(0, window.innerWidth - clock.offsetWidth)}px`;
+        clock.style.top = `${Math.random() * Math.max(0, window.innerHeight - clock.offsetHeight)}px`;
+      };
+      const setScreensaver = ({ active, mode, camera_id, fade_ms }) => {
+        screensaver.style.setProperty("--screensaver-fade", `${fade_ms}ms`);
+        screensaver.classList.toggle("active", active);
+        screensaver.classList.toggle("clock", mode === "clock");
+        clearInterval(this._clockTimer);
+        if (!active) {
+          stills.forEach((img) => img.classList.remove("visible"));
+          return;
+        }
+        if (mode === "clock") {
+          moveClock();
+          this._clockTimer = setInterval(moveClock, 60000);
+        } else if (camera_id) {
+          // Load the next still into the hidden layer, then fade it over the current one
+          const next = stills[stillIndex ^ 1];
+          next.onload = () => {
+            next.classList.add("visible");
+            stills[stillIndex].classList.remove("visible");
+            stillIndex ^= 1;
+          };
+          next.src = `http://localhost:${this.apiPort}/camera/${camera_id}/snapshot.jpg?t=${Date.now()}`;
+        }
+      };
+      this.unlistenScreensaver = await listen("screensaver", (event) => setScreensaver(event.payload));
+      invoke("get_screensaver").then(setScreensaver).catch(() => {});
+      // Local input counts as activity; report it at most every few seconds unless it ends the screensaver
+      let lastActivity = 0;
+      const reportActivity = () => {
+        const now = Date.now();
+        if (!screensaver.classList.contains("active") && now - lastActivity < 5000) return;
+        lastActivity = now;
+        invoke("screensaver_activity").catch(() => {});
+      };
+      for (const type of ["pointermove", "pointerdown", "keydown", "wheel"]) {
+        window.addEventListener(type, reportActivity, { passive: true });
+      }
+
+      // Maintenance mode (/api/maintenance/on): tell the operator when the drain is done
+      this.unlistenMaintenance = await listen("maintenance", (event) => {
+        const { active, safe_to_update } = event.payload;
+        if (active && safe_to_update) this.showToast("Maintenance mode: safe to update or reboot", "info");
+      });
+
+      // Emergency blackout (/api/blackout/on|off, on either API port)
+      const setBlackout = (active) => {
+        document.getElementById("blackout").classList.toggle("active", active);
+      };
+      this.unlistenBlackout = await listen("blackout", (event) => setBlackout(event.payload.active));
+      invoke("get_blackout").then(setBlackout).catch(() => {});
+
+      // Config reloaded from disk (/api/reload or an edit to config.json). Unchanged cameras keep their
+      // streams; only the tiles whose pipelines were cycled reconnect.
+      this.unlistenReloadConfig = await listen("reload-config", async (event) => {
+        const { restarted = [], ui_changed = true } = event.payload || {};
+        if (ui_changed) {
+          location.reload();
+          return;
+        }
+        const config = await invoke("get_config").catch(() => null);
+        if (config) this.cameras = config.cameras;
+        for (const id of restarted) {
+          const cam = this.cameras.find((c) => c.id === id);
+          const reader = this.streamReaders.get(id);
+          if (reader) {
+            reader.audio = !!cam?.audio_enabled;
+            reader._restart();
+          }
+          if (this.pipReader && this.pip?.camera_id === id) {
+            this.pipReader._restart();
+          }
+        }
       });
 
       // Start FFmpeg immediately (fire-and-forget) so streams begin probing
@@ -600,6 +890,8 @@ class StageView {
       }
 
       this.render();
+      this.renderPip();
+      this.renderOverlay();
       this._startRenderLoop();
       this.startShuffleTimer();
 
@@ -640,6 +932,9 @@ class StageView {
     document.getElementById('add-camera-btn').addEventListener('click', () => this.addCameraField());
     document.getElementById('save-settings-btn').addEventListener('click', () => this.saveSettings());
     document.getElementById('check-update-btn').addEventListener('click', () => this.checkForUpdates(true));
+    document.getElementById('rotate-token-btn').addEventListener('click', () => this.rotateApiToken());
+    document.getElementById('clear-token-btn').addEventListener('click', () => this.clearApiToken());
+    document.getElementById('status-palette').addEventListener('change', (e) => this.setPalette(e.target.value));
 
     // Update modal buttons
     document.getElementById('update-close-btn').addEventListener('click', () => this.closeUpdateModal());
@@ -666,13 +961,11 @@ class StageView {
   }
 
   renderGridLayout(grid) {
-    const cols = Math.ceil(Math.sqrt(this.cameras.length));
-    const rows = Math.ceil(this.cameras.length / cols);
-    grid.style.gridTemplateColumns = `repeat(${cols}, 1fr)`;
-    grid.style.gridTemplateRows = `repeat(${rows}, 1fr)`;
     grid.style.position = "";
 
+    // Disabled cameras have no pipeline, so they get no tile
     grid.innerHTML = this.displayOrder
+      .filter((index) => this.cameras[index].enabled !== false)
       .map((index) => {
         const cam = this.cameras[index];
         return this.createCameraTile(cam, index);
@@ -680,6 +973,7 @@ class StageView {
       .join("");
 
     this.bindTileEvents(grid);
+    this.layoutGrid();
 
     // Restore camera status for any cameras that already have a known status
     grid.querySelectorAll(".camera-tile").forEach((tile) => {
@@ -689,19 +983,61 @@ class StageView {
         this.applyCameraStatus(tile, status);
       }
     });
+
+    this.updateAudio();
   }
 
   createCameraTile(cam, idx) {
+    // Data tiles show polled text instead of video, so they get no <video> and no stream reader
+    const body = cam.url.startsWith("data:")
+      ? `<div class="data-tile">${this.dataTileHtml(this.dataTiles.get(cam.id))}</div>`
+      : `<video autoplay muted playsinline crossorigin="anonymous"></video>`;
     return `
-      <div class="camera-tile" data-id="${cam.id}">
+      <div class="camera-tile" data-id="${cam.id}" style="${this.tileBorderStyle(cam.id)}">
         <div class="loading-spinner"></div>
-        <video autoplay muted playsinline crossorigin="anonymous"></video>
+        ${body}
         <div class="camera-status" style="${this.showStatusDots ? '' : 'display:none'}"></div>
         <div class="camera-label" style="${this.showCameraNames ? '' : 'display:none'}">${escapeHtml(cam.name)}</div>
       </div>
     `;
   }
 
+  /**
+   * Markup for a data tile's content: an object becomes label/value rows, an
+   * array one line per item, anything else plain text.
+   */
+  dataTileHtml(content) {
+    if (content === undefined || content === null) return "";
+    const text = (v) => escapeHtml(typeof v === "object" && v !== null ? Object.values(v).join(" · ") : String(v));
+    if (Array.isArray(content)) {
+      return `<ul>${content.map((item) => `<li>${text(item)}</li>`).join("")}</ul>`;
+    }
+    if (typeof content === "object") {
+      return `<dl>${Object.entries(content).map(([k, v]) => `<dt>${escapeHtml(k)}</dt><dd>${text(v)}</dd>`).join("")}</dl>`;
+    }
+    return `<pre>${escapeHtml(String(content))}</pre>`;
+  }
+
+  renderDataTile(camId) {
+    const el = document.querySelector(`#grid [data-id="${camId}"] .data-tile`);
+    if (el) el.innerHTML = this.dataTileHtml(this.dataTiles.get(camId));
+  }
+
+  flashTile(cameraId, label) {
+    const tile = document.querySelector(`[data-id="${cameraId}"]`);
+    if (!tile) return;
+    tile.querySelector(".detection-badge")?.remove();
+    tile.classList.remove("detected");
+    void tile.offsetWidth; // Restart the animation for back-to-back events
+    tile.classList.add("detected");
+    tile.insertAdjacentHTML('beforeend', `<div class="detection-badge">${escapeHtml(label)}</div>`);
+    clearTimeout(tile._detectionTimer);
+    tile._detectionTimer = setTimeout(() => {
+      tile.classList.remove("detected");
+      tile.querySelector(".detection-badge")?.remove();
+    }, 6000);
+  }
+
   applyCameraStatus(tile, status) {
     const spinner = tile.querySelector(".loading-spinner");
     const statusEl = tile.querySelector(".camera-status");
@@ -709,15 +1045,68 @@ class StageView {
     if (status === "online") {
       spinner.style.display = "none";
       statusEl.classList.remove("offline", "reconnecting");
-    } else if (status === "connecting" || status.startsWith("reconnecting")) {
+      tile.dataset.status = "online";
+    } else if (status === "connecting" || status === "idle" || status.startsWith("reconnecting")) {
+      // "idle" = on-demand camera waiting for a viewer; this tile is one, so it's about to connect
       spinner.style.display = "";
       statusEl.classList.add("reconnecting");
       statusEl.classList.remove("offline");
+      tile.dataset.status = "connecting";
     } else {
       // "error" or "offline"
       spinner.style.display = "none";
       statusEl.classList.add("offline");
       statusEl.classList.remove("reconnecting");
+      tile.dataset.status = "offline";
+    }
+  }
+
+  /**
+   * Apply the tile theme: status colors and border width as CSS variables,
+   * the offline look as a body attribute, and each camera's own border color.
+   */
+  applyTheme(theme) {
+    if (!theme) return;
+    this.theme = theme;
+    const root = document.documentElement.style;
+    root.setProperty("--status-online", theme.online);
+    root.setProperty("--status-connecting", theme.connecting);
+    root.setProperty("--status-offline", theme.offline);
+    root.setProperty("--tile-border-width", `${theme.border_width}px`);
+    if (theme.border_color) root.setProperty("--theme-border", theme.border_color);
+    else root.removeProperty("--theme-border");
+    document.body.dataset.offlineTile = theme.offline_tile;
+    document.querySelectorAll("#grid .camera-tile").forEach((tile) => {
+      const color = theme.camera_borders[tile.dataset.id];
+      if (color) tile.style.setProperty("--tile-border", color);
+      else tile.style.removeProperty("--tile-border");
+    });
+    const palette = document.getElementById("status-palette");
+    if (palette) palette.value = theme.palette;
+  }
+
+  /**
+   * Hand an announcement to the screen reader. The region is cleared first
+   * so the same sentence twice in a row is still read out.
+   */
+  announce(text) {
+    const region = document.getElementById("announcer");
+    if (!region || !text) return;
+    region.textContent = "";
+    setTimeout(() => { region.textContent = text; }, 50);
+  }
+
+  tileBorderStyle(cameraId) {
+    const color = this.theme?.camera_borders?.[cameraId];
+    return color ? `--tile-border: ${color}` : "";
+  }
+
+  async setPalette(palette) {
+    try {
+      const config = await invoke("get_config");
+      await invoke("set_theme", { theme: { ...config.theme, palette } });
+    } catch (err) {
+      this.showToast("Failed to change status colors: " + err, 'error');
     }
   }
 
@@ -734,7 +1123,8 @@ class StageView {
       const video = tile.querySelector("video");
       if (!video) return;
       
-      const reader = new Mp4StreamReader(url, video);
+      const cam = this.cameras.find((c) => c.id === camId);
+      const reader = new Mp4StreamReader(url, video, { audio: !!cam?.audio_enabled });
       
       reader.onFirstFrame = () => {
         video.classList.add("has-frame");
@@ -744,6 +1134,7 @@ class StageView {
         if (statusEl) {
           statusEl.classList.remove("offline", "reconnecting");
         }
+        tile.dataset.status = "online";
       };
 
       // No onError handler — transient MSE/stream errors are auto-recovered
@@ -851,7 +1242,11 @@ class StageView {
         [this.displayOrder[j], this.displayOrder[i]];
     }
 
-    // Apply visual reorder via CSS order (preserves DOM tree / canvas context)
+    this.applyDisplayOrder();
+  }
+
+  /** Apply displayOrder via CSS order (preserves DOM tree / canvas context) */
+  applyDisplayOrder() {
     const grid = document.getElementById("grid");
     grid.querySelectorAll(".camera-tile").forEach((tile) => {
       const camId = tile.dataset.id;
@@ -859,14 +1254,29 @@ class StageView {
       const orderPos = this.displayOrder.indexOf(camIndex);
       tile.style.order = orderPos >= 0 ? orderPos : 0;
     });
+    // GET /api/layout/tiles reports the order actually shown
+    const cameraIds = this.displayOrder.map((i) => this.cameras[i]?.id).filter(Boolean);
+    invoke("report_tile_order", { cameraIds }).catch(() => {});
+  }
+
+  /** Camera indices in tile order from camera IDs; unlisted cameras go last */
+  displayOrderFromIds(ids) {
+    const order = (ids || [])
+      .map((id) => this.cameras.findIndex((c) => c.id === id))
+      .filter((i) => i >= 0);
+    this.cameras.forEach((_, i) => {
+      if (!order.includes(i)) order.push(i);
+    });
+    return order;
   }
 
   // ── Solo Camera Mode ────────────────────────────────────────────────────
 
   async soloCamera(index) {
     if (index < 1 || index > this.cameras.length) return;
-    this.soloIndex = index;
     const cam = this.cameras[index - 1];
+    if (cam.enabled === false) return;
+    this.soloIndex = index;
 
     const grid = document.getElementById("grid");
 
@@ -887,9 +1297,16 @@ class StageView {
     grid.style.gridTemplateRows = "1fr";
     grid.style.position = "";
 
+    // Sound follows solo
+    this.updateAudio();
+
     // Notify backend (streams stay running for instant grid recovery)
     await invoke("solo_camera", { cameraId: cam.id });
 
+    // Hidden tiles go to warm standby (no-op unless enabled in config)
+    this.refreshStandby();
+    this.renderPip();
+
     this.updateToolbar();
 
     // Reset shuffle timer for pixel refresh in solo mode
@@ -901,32 +1318,132 @@ class StageView {
     this.soloIndex = null;
 
     const grid = document.getElementById("grid");
-    const cols = Math.ceil(Math.sqrt(this.cameras.length));
-    const rows = Math.ceil(this.cameras.length / cols);
 
-    // Restore grid layout and show all tiles instantly (no DOM rebuild).
+    // Restore grid layout and show the page's tiles instantly (no DOM rebuild).
     // All stream readers stayed running, so cameras appear immediately.
-    grid.style.gridTemplateColumns = `repeat(${cols}, 1fr)`;
-    grid.style.gridTemplateRows = `repeat(${rows}, 1fr)`;
-
     grid.querySelectorAll(".camera-tile").forEach((tile) => {
       tile.classList.remove("solo");
-      tile.style.display = "";
     });
+    this.layoutGrid();
+    this.renderPip();
+    this.updateAudio();
 
     // Restore display order from shuffle state
-    grid.querySelectorAll(".camera-tile").forEach((tile) => {
-      const camId = tile.dataset.id;
-      const camIndex = this.cameras.findIndex(c => c.id === camId);
-      const orderPos = this.displayOrder.indexOf(camIndex);
-      tile.style.order = orderPos >= 0 ? orderPos : 0;
-    });
+    this.applyDisplayOrder();
+
+    // A camera with a substream drops back to it
+    invoke("exit_solo").catch(() => {});
 
     this.updateToolbar();
     this.closeCameraMenu();
     this.startShuffleTimer();
   }
 
+  // ── Paging ─────────────────────────────────────────────────────────────
+
+  /**
+   * Size the grid for the current page and show only its tiles (grid view).
+   * Off-page cameras go to warm standby; with paging off every tile shows.
+   */
+  layoutGrid() {
+    const grid = document.getElementById("grid");
+    const pageIds = this.page && this.page.page_size > 0 ? this.page.camera_ids : null;
+    // Size for a full page so tiles don't grow on a short last page
+    const count = Math.max(1, pageIds ? this.page.page_size : this.cameras.filter((c) => c.enabled !== false).length);
+    const cols = Math.ceil(Math.sqrt(count));
+    const rows = Math.ceil(count / cols);
+    grid.style.gridTemplateColumns = `repeat(${cols}, 1fr)`;
+    grid.style.gridTemplateRows = `repeat(${rows}, 1fr)`;
+
+    grid.querySelectorAll(".camera-tile").forEach((tile) => {
+      tile.style.display = !pageIds || pageIds.includes(tile.dataset.id) ? "" : "none";
+    });
+
+    this.refreshStandby();
+  }
+
+  /**
+   * Tell the backend which cameras are off screen (solo view, other pages) so
+   * they can go to warm standby.
+   */
+  refreshStandby() {
+    let hiddenIds;
+    if (this.soloIndex !== null) {
+      const soloId = this.cameras[this.soloIndex - 1]?.id;
+      hiddenIds = this.cameras.filter((c) => c.id !== soloId).map((c) => c.id);
+    } else {
+      const pageIds = this.page && this.page.page_size > 0 ? this.page.camera_ids : null;
+      hiddenIds = pageIds ? this.cameras.filter((c) => !pageIds.includes(c.id)).map((c) => c.id) : [];
+    }
+    // The PiP camera is on screen and the monitored camera is audible in every view
+    const pipId = this.pip?.active ? this.pip.camera_id : null;
+    const keep = (id) => id === pipId || id === this.audioCameraId;
+    invoke("set_standby_cameras", { cameraIds: hiddenIds.filter((id) => !keep(id)) }).catch(() => {});
+  }
+
+  /** Flip to the next (+1) or previous (-1) page, wrapping around */
+  flipPage(step) {
+    if (!this.page || this.page.page_count < 2) return;
+    const { page, page_count } = this.page;
+    const next = ((page - 1 + step + page_count) % page_count) + 1;
+    invoke("set_page", { page: next }).catch(() => {});
+  }
+
+  // ── Picture-in-Picture ─────────────────────────────────────────────────
+
+  /**
+   * Show the PiP camera in its corner with a dedicated stream reader. The
+   * overlay hides (reader kept running) while that camera is the solo camera.
+   */
+  renderPip() {
+    const el = document.getElementById("pip");
+    const cam = this.pip?.active ? this.cameras.find((c) => c.id === this.pip.camera_id) : null;
+
+    if (!cam) {
+      el.classList.remove("active");
+      el.dataset.pipId = "";
+      if (this.pipReader) {
+        this.pipReader.stop();
+        this.pipReader = null;
+      }
+      return;
+    }
+
+    const soloId = this.soloIndex !== null ? this.cameras[this.soloIndex - 1]?.id : null;
+    el.className = `camera-tile pip-${this.pip.position}`;
+    el.classList.toggle("active", cam.id !== soloId);
+    el.style.width = `${this.pip.size_percent}%`;
+    const label = el.querySelector(".camera-label");
+    label.textContent = cam.name;
+    label.style.display = this.showCameraNames ? "" : "none";
+
+    if (el.dataset.pipId === cam.id && this.pipReader) return;
+    if (this.pipReader) this.pipReader.stop();
+    el.dataset.pipId = cam.id;
+    const video = el.querySelector("video");
+    video.classList.remove("has-frame");
+    this.pipReader = new Mp4StreamReader(`http://localhost:${this.apiPort}/camera/${cam.id}/stream`, video);
+    this.pipReader.onFirstFrame = () => video.classList.add("has-frame");
+    this.pipReader.start();
+  }
+
+  renderOverlay() {
+    const el = document.getElementById("overlay-graphic");
+    const img = el.querySelector("img");
+    if (!this.overlay?.active) {
+      el.className = "";
+      img.removeAttribute("src");
+      return;
+    }
+    // Uploaded PNGs come back as an API path; remote URLs are loaded as given
+    const src = this.overlay.image_url.startsWith("/")
+      ? `http://localhost:${this.apiPort}${this.overlay.image_url}`
+      : this.overlay.image_url;
+    if (img.getAttribute("src") !== src) img.src = src;
+    el.className = `active overlay-${this.overlay.position}`;
+    el.style.width = `${this.overlay.size_percent}%`;
+  }
+
   // ── Pixel Refresh (burn-in protection in solo mode) ─────────────────────
 
   doPixelRefresh() {
@@ -936,11 +1453,14 @@ class StageView {
       { x:  2, y:  0 }, { x:  0, y:  2 }, { x: -2, y:  0 }, { x:  0, y: -2 },
     ];
 
-    this.pixelShiftIndex = (this.pixelShiftIndex + 1) % orbits.length;
-    const shift = orbits[this.pixelShiftIndex];
-    const grid = document.getElementById("grid");
-    grid.style.transition = "transform 1.5s ease-in-out";
-    grid.style.transform = `translate(${shift.x}px, ${shift.y}px)`;
+    // With the backend's pixel_shift on, only the noise pass runs here
+    if (!this.backendPixelShift) {
+      this.pixelShiftIndex = (this.pixelShiftIndex + 1) % orbits.length;
+      const shift = orbits[this.pixelShiftIndex];
+      const grid = document.getElementById("grid");
+      grid.style.transition = "transform 1.5s ease-in-out, translate 1.5s ease-in-out";
+      grid.style.transform = `translate(${shift.x}px, ${shift.y}px)`;
+    }
 
     // Generate noise texture once, reuse across calls
     if (!this._noiseDataUrl) {
@@ -1068,6 +1588,31 @@ class StageView {
     await win.setFullscreen(!isFs);
   }
 
+  // ── Audio ──────────────────────────────────────────────────────────────
+
+  /** Unmute only the audible camera: the solo camera, else the grid selection */
+  updateAudio() {
+    // The monitored camera wins; without one, sound follows solo
+    const audibleId = this.audioCameraId
+      ?? (this.soloIndex !== null ? this.cameras[this.soloIndex - 1]?.id : null);
+    document.querySelectorAll("#grid .camera-tile").forEach((tile) => {
+      const cam = this.cameras.find((c) => c.id === tile.dataset.id);
+      const audible = !!cam?.audio_enabled && tile.dataset.id === audibleId;
+      const video = tile.querySelector("video");
+      if (video) video.muted = !audible;
+      tile.classList.toggle("audible", audible);
+    });
+  }
+
+  /** Step the monitored camera through audio-enabled cameras, then back to none */
+  cycleAudioCamera() {
+    const ids = this.cameras.filter((c) => c.audio_enabled).map((c) => c.id);
+    if (ids.length === 0) return;
+    const next = ids.indexOf(this.audioCameraId) + 1;
+    const cameraId = next < ids.length ? ids[next] : null;
+    invoke("monitor_audio", { cameraId }).catch((err) => this.showToast(`Audio: ${err}`, 'error'));
+  }
+
   // ── Keyboard Shortcuts ──────────────────────────────────────────────────
 
   bindKeys() {
@@ -1100,6 +1645,29 @@ class StageView {
         return;
       }
 
+      // PageDown / PageUp: flip between pages of cameras
+      if (e.key === "PageDown" || e.key === "PageUp") {
+        e.preventDefault();
+        this.flipPage(e.key === "PageDown" ? 1 : -1);
+        return;
+      }
+
+      // T (hold): push-to-talk to the soloed camera's speaker
+      if (e.key.toLowerCase() === "t" && !e.ctrlKey && !e.altKey) {
+        if (!e.repeat && this.soloIndex !== null) {
+          this.talkbackCamera = this.cameras[this.soloIndex - 1]?.id;
+          invoke("set_talkback", { cameraId: this.talkbackCamera, active: true })
+            .catch((err) => this.showToast(`Talkback: ${err}`, "error"));
+        }
+        return;
+      }
+
+      // A: choose which camera is audible in grid view
+      if (e.key.toLowerCase() === "a" && !e.ctrlKey && !e.altKey) {
+        this.cycleAudioCamera();
+        return;
+      }
+
       // 0 or Escape: return to grid view
       if (e.key === "0" || (e.key === "Escape" && this.soloIndex !== null)) {
         this.exitSolo();
@@ -1112,6 +1680,13 @@ class StageView {
         return;
       }
     });
+
+    document.addEventListener("keyup", (e) => {
+      if (e.key.toLowerCase() === "t" && this.talkbackCamera) {
+        invoke("set_talkback", { cameraId: this.talkbackCamera, active: false }).catch(() => {});
+        this.talkbackCamera = null;
+      }
+    });
   }
 
   async exitFullscreen() {
@@ -1150,6 +1725,7 @@ class StageView {
     document.getElementById("show-status-dots").checked = this.showStatusDots;
     document.getElementById("show-camera-names").checked = this.showCameraNames;
     document.getElementById("api-port").value = this.apiPort;
+    document.getElementById("api-token").value = this.apiToken;
     this.renderCameraList();
     this.injectHealthSection();
   }
@@ -1197,10 +1773,10 @@ class StageView {
   }
 
   async refreshHealthStats() {
-    // healthStats is kept current by the stream-health event listener.
+    // healthStats is kept current by the wall-health-batch event listener.
     // Just repaint from what we already have in memory — no Rust invocation needed.
     // If no events have fired yet (stream just started), shows '--' until the
-    // next 2-second health tick arrives.
+    // next health batch arrives.
     this.updateHealthDisplay();
   }
 
@@ -1229,6 +1805,19 @@ class StageView {
     });
   }
 
+  // Risky config (duplicate URLs, open API, ...) from lint_config, toasted once at startup
+  async showLintWarnings() {
+    const warnings = await invoke("lint_config").catch(() => []);
+    // Every default install has no API token; say so on the first launch, not on each one
+    const tokenSeen = localStorage.getItem("sv_lint_api_no_token") === "1";
+    if (warnings.some((w) => w.code === "api_no_token")) localStorage.setItem("sv_lint_api_no_token", "1");
+    const serious = warnings.filter((w) => w.severity !== "info" && !(w.code === "api_no_token" && tokenSeen));
+    serious.slice(0, 3).forEach((w) => this.showToast(w.message, w.severity === "error" ? "error" : "warn"));
+    if (serious.length > 3) {
+      this.showToast(`${serious.length - 3} more config warnings; see /api/lint`, "warn");
+    }
+  }
+
   showToast(message, type = 'info') {
     // Get or create toast container
     let container = document.getElementById('toast-container');
@@ -1445,10 +2034,11 @@ class StageView {
       .map(
         (cam, i) => {
           return `
-      <div class="camera-entry" data-index="${i}">
+      <div class="camera-entry" data-index="${i}" data-id="${escapeHtml(cam.id)}">
         <span class="api-index" title="API: /api/solo/${i + 1}">Camera ${i + 1}</span>
         <input type="text" placeholder="Camera name" value="${escapeHtml(cam.name)}" data-field="name" />
-        <input type="text" placeholder="rtp://224.1.2.4:4000" value="${escapeHtml(cam.url)}" data-field="url" />
+        <input type="text" placeholder="rtp://224.1.2.4:4000" value="${escapeHtml(this.displayUrl(cam))}" data-field="url" />
+        <input type="text" placeholder="Model (optional)" value="${escapeHtml(cam.model || "")}" data-field="model" />
         <button class="remove-btn" data-remove-index="${i}">✕</button>
       </div>
     `;
@@ -1460,6 +2050,40 @@ class StageView {
     list.querySelectorAll('[data-remove-index]').forEach(btn => {
       btn.addEventListener('click', () => this.removeCameraField(parseInt(btn.dataset.removeIndex)));
     });
+    list.querySelectorAll('.camera-entry').forEach(entry => this.bindCameraInfoProbe(entry));
+  }
+
+  /**
+   * The camera's URL as the settings list shows it: with the user name of a
+   * stored login (never the password), so deleting `user@` removes the login.
+   */
+  displayUrl(cam) {
+    if (!cam.username || cam.url.includes('|') || !cam.url.includes('://')) return cam.url;
+    const [scheme, rest] = cam.url.split('://', 2);
+    const authority = rest.split(/[/?]/)[0];
+    if (authority.includes('@')) return cam.url;
+    return `${scheme}://${encodeURIComponent(cam.username)}@${rest}`;
+  }
+
+  /**
+   * When a camera's URL is entered, ask the backend to query the device
+   * (RTSP DESCRIBE / ONVIF) for its model, and pre-fill the name if empty.
+   */
+  bindCameraInfoProbe(entry) {
+    const urlInput = entry.querySelector('[data-field="url"]');
+    const nameInput = entry.querySelector('[data-field="name"]');
+    const modelInput = entry.querySelector('[data-field="model"]');
+    urlInput.addEventListener('change', async () => {
+      const url = urlInput.value.trim();
+      if (!url) return;
+      try {
+        const info = await invoke("probe_camera_info", { url, cameraId: entry.dataset.id || null });
+        if (info.name && !nameInput.value.trim()) nameInput.value = info.name;
+        if (info.model) modelInput.value = info.model;
+      } catch (err) {
+        console.debug("Camera info probe failed:", err);
+      }
+    });
   }
 
   addCameraField() {
@@ -1471,9 +2095,11 @@ class StageView {
     entry.innerHTML = `
       <input type="text" placeholder="Camera name" value="" data-field="name" />
       <input type="text" placeholder="rtp://224.1.2.4:4000" value="" data-field="url" />
+      <input type="text" placeholder="Model (optional)" value="" data-field="model" />
       <button class="remove-btn">✕</button>
     `;
     entry.querySelector('.remove-btn').addEventListener('click', () => this.removeCameraField(index));
+    this.bindCameraInfoProbe(entry);
     list.appendChild(entry);
   }
 
@@ -1501,19 +2127,26 @@ class StageView {
     const entries = document.querySelectorAll("#camera-list .camera-entry");
     const cameras = [];
 
-    // Build a map of existing cameras by URL for ID preservation
-    const existingCamerasMap = new Map(this.cameras.map(c => [c.url, c]));
+    // Existing cameras by id, or by URL for rows added before this list was rendered
+    const existingById = new Map(this.cameras.map(c => [c.id, c]));
+    const existingByUrl = new Map(this.cameras.map(c => [this.displayUrl(c), c]));
 
     entries.forEach((entry) => {
       const name = entry.querySelector('[data-field="name"]').value.trim();
       const url = entry.querySelector('[data-field="url"]').value.trim();
       if (url) {
-        const existingCamera = existingCamerasMap.get(url);
+        const existingCamera = existingById.get(entry.dataset.id) || existingByUrl.get(url);
+        const unchanged = existingCamera && url === this.displayUrl(existingCamera);
+        // Spread the existing camera first so fields not edited here survive a save
         const camera = {
+          ...existingCamera,
           id: existingCamera?.id || crypto.randomUUID(),
           name: name || `Camera ${cameras.length + 1}`,
-          url,
+          url: unchanged ? existingCamera.url : url,
+          model: entry.querySelector('[data-field="model"]').value.trim() || null,
         };
+        // An edited URL brings its own login, or none; a user name left in it keeps the stored password
+        if (existingCamera && !unchanged) camera.username = null;
         cameras.push(camera);
       }
     });
@@ -1530,7 +2163,12 @@ class StageView {
     try {
       // Read existing config to preserve window_state and other fields
       const config = await invoke("get_config");
-      config.cameras = cameras;
+      // Input tuning the backend learned since this window loaded its copy of the cameras
+      const current = new Map(config.cameras.map(c => [c.id, c]));
+      config.cameras = cameras.map(c => {
+        const latest = current.get(c.id);
+        return latest && latest.url === c.url ? { ...c, learned_input: latest.learned_input ?? null } : c;
+      });
       config.shuffle_interval_secs = shuffleIntervalSecs;
       config.show_status_dots = showStatusDots;
       config.show_camera_names = showCameraNames;
@@ -1539,7 +2177,11 @@ class StageView {
       await invoke("save_config", { config });
 
       this.cameras = cameras;
-      this.displayOrder = this.cameras.map((_, i) => i); // reinitialize display order
+      this.displayOrder = this.displayOrderFromIds(config.tile_order); // reinitialize display order
+      this.page = await invoke("get_page").catch(() => null);
+      this.pip = await invoke("get_pip").catch(() => null); // PiP camera may have been removed
+      const monitor = await invoke("get_audio_monitor").catch(() => null);
+      this.audioCameraId = monitor?.camera_id ?? null;
       this.shuffleIntervalSecs = shuffleIntervalSecs;
       this.showStatusDots = showStatusDots;
       this.showCameraNames = showCameraNames;
@@ -1547,8 +2189,13 @@ class StageView {
 
       // Restart streams with new camera list and codec settings
       this._stopAllReaders();
+      if (this.pipReader) {
+        this.pipReader.stop();
+        this.pipReader = null;
+      }
       await invoke("stop_streams");
       this.render();
+      this.renderPip();
       this.startShuffleTimer();
       if (this.cameras.length > 0) {
         await invoke("start_streams");
@@ -1559,6 +2206,30 @@ class StageView {
     }
   }
 
+  // ── API Token ──────────────────────────────────────────────────────────
+
+  async rotateApiToken() {
+    if (this.apiToken && !confirm("Replace the API token? Panels and buttons using the old token will stop working.")) return;
+    try {
+      this.apiToken = await invoke("rotate_api_token");
+      document.getElementById("api-token").value = this.apiToken;
+      this.showToast("New API token generated", 'success');
+    } catch (err) {
+      this.showToast("Failed to generate token: " + err, 'error');
+    }
+  }
+
+  async clearApiToken() {
+    try {
+      await invoke("clear_api_token");
+      this.apiToken = "";
+      document.getElementById("api-token").value = "";
+      this.showToast("API token removed — the API is open to the local network", 'info');
+    } catch (err) {
+      this.showToast("Failed to clear token: " + err, 'error');
+    }
+  }
+
   // ── Serialized Config Save ──────────────────────────────────────────────
 
   /**